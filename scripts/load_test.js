@@ -1,5 +1,7 @@
 import http from "k6/http";
 import { check, sleep, fail } from "k6";
+import { hmac } from "k6/crypto";
+import encoding from "k6/encoding";
 import { randomItem } from "https://jslib.k6.io/k6-utils/1.2.0/index.js";
 
 export const options = {
@@ -8,8 +10,13 @@ export const options = {
 };
 
 const BASE_URL = __ENV.BASE_URL || "http://localhost:3000";
-const FLASH_SALE_ID =
-  __ENV.FLASH_SALE_ID || "11111111-1111-1111-1111-111111111111";
+// One of the sales printed by `flash-sale seed`
+const FLASH_SALE_ID = (__ENV.FLASH_SALE_ID || "").trim();
+// HS256 secret matching the server's JWT_KEY_FILE
+const JWT_SECRET = (__ENV.JWT_SECRET || "").trim();
+// Upper bound on users loaded in setup(), fetched one page at a time
+const MAX_USERS = parseInt(__ENV.MAX_USERS || "1000", 10);
+const USERS_PAGE_SIZE = 200;
 
 // Polling config
 const MAX_POLL_ATTEMPTS = 10;
@@ -17,54 +24,112 @@ const POLL_INTERVAL_SEC = 1;
 
 // Idempotency retry simulation
 const RETRY_RATE = 0.2; // 20% of requests will reuse an idempotency key
-const RETRY_POOL_SIZE = 50; // Pool of idempotency keys to reuse for simulating retries
+const RETRY_POOL_SIZE = 50; // Pool of (user, idempotency key) pairs to reuse for simulating retries
+
+// Mint an HS256 bearer token for a user (valid for one hour)
+function signToken(userId, role = "customer") {
+  const header = encoding.b64encode(
+    JSON.stringify({ alg: "HS256", typ: "JWT" }),
+    "rawurl",
+  );
+  const claims = encoding.b64encode(
+    JSON.stringify({
+      sub: userId,
+      role,
+      exp: Math.floor(Date.now() / 1000) + 3600,
+    }),
+    "rawurl",
+  );
+  const signature = hmac(
+    "sha256",
+    JWT_SECRET,
+    `${header}.${claims}`,
+    "base64rawurl",
+  );
+
+  return `${header}.${claims}.${signature}`;
+}
 
 export function setup() {
-  const res = http.get(`${BASE_URL}/users`);
-  const users = res.json();
+  if (!JWT_SECRET) {
+    fail("JWT_SECRET is required to sign bearer tokens");
+  }
+  if (!FLASH_SALE_ID) {
+    fail("FLASH_SALE_ID is required, create a sale with `flash-sale seed`");
+  }
 
-  if (!users || users.length === 0) {
+  // Listing users is restricted to operators
+  const operatorToken = signToken(crypto.randomUUID(), "operator");
+  const users = [];
+  let cursor = null;
+  do {
+    const query = cursor
+      ? `limit=${USERS_PAGE_SIZE}&cursor=${cursor}`
+      : `limit=${USERS_PAGE_SIZE}`;
+    const res = http.get(`${BASE_URL}/users?${query}`, {
+      headers: { Authorization: `Bearer ${operatorToken}` },
+    });
+    if (res.status !== 200) {
+      fail(`Listing users failed with status ${res.status}`);
+    }
+
+    const page = res.json();
+    users.push(...page.items);
+    cursor = page.next_cursor;
+  } while (cursor && users.length < MAX_USERS);
+
+  if (users.length === 0) {
     console.error("No users found! Please create some users first.");
-    return { userIds: [], retryPool: [] };
+    return { tokens: [], retryPool: [] };
   }
 
-  // Pre-populate retry pool with random UUIDs
+  const tokens = users.map((u) => signToken(u.id));
+
+  // Pre-populate retry pool with random UUIDs, each bound to one user
+  // (a key replayed by a different user is rejected as a mismatch)
   const retryPool = [];
   for (let i = 0; i < RETRY_POOL_SIZE; i++) {
-    retryPool.push(crypto.randomUUID());
+    retryPool.push({
+      token: tokens[Math.floor(Math.random() * tokens.length)],
+      idempotencyKey: crypto.randomUUID(),
+    });
   }
 
-  return { userIds: users.map((u) => u.id), retryPool };
+  return { tokens, retryPool };
 }
 
 export default function (data) {
-  if (data.userIds.length === 0) {
+  if (data.tokens.length === 0) {
     return;
   }
 
   const url = `${BASE_URL}/orders`;
   const payload = {
-    user_id: data.userIds[Math.floor(Math.random() * data.userIds.length)],
     flash_sale_id: FLASH_SALE_ID,
     quantity: 1,
   };
 
   // Generate or reuse idempotency key (simulate retries)
   const shouldRetry = Math.random() < RETRY_RATE;
-  const idempotencyKey =
+  const { token, idempotencyKey } =
     shouldRetry && data.retryPool.length > 0
       ? data.retryPool[Math.floor(Math.random() * data.retryPool.length)]
-      : crypto.randomUUID();
+      : {
+          token: data.tokens[Math.floor(Math.random() * data.tokens.length)],
+          idempotencyKey: crypto.randomUUID(),
+        };
 
   const headers = {
     "Content-Type": "application/json",
+    Authorization: `Bearer ${token}`,
     "Idempotency-Key": idempotencyKey,
   };
 
   const res = http.post(url, JSON.stringify(payload), { headers });
 
   check(res, {
-    "valid response": (r) => [202, 409, 404, 429, 503].includes(r.status),
+    "valid response": (r) => [200, 202, 409, 404, 429, 503].includes(r.status),
+    "200 = retry of finished order": (r) => r.status === 200,
     "202 = accepted": (r) => r.status === 202,
     "409 = sold out": (r) => r.status === 409,
     "404 = not found": (r) => r.status === 404,
@@ -86,6 +151,7 @@ export default function (data) {
     sleep(POLL_INTERVAL_SEC);
 
     const statusRes = http.get(`${BASE_URL}/orders/${orderId}/status`, {
+      headers: { Authorization: `Bearer ${token}` },
       tags: { endpoint: "order_status" },
     });
 
@@ -0,0 +1,24 @@
+-- Phase 5: Store a fingerprint of the request alongside the idempotency key
+-- A key replayed with different parameters must be rejected, not answered
+-- with somebody else's order
+
+-- Add request_fingerprint column
+ALTER TABLE orders
+ADD COLUMN request_fingerprint TEXT;
+
+-- Backfill existing orders using the same canonical form as the server:
+-- sha256 hex of "user_id:flash_sale_id:quantity"
+UPDATE orders
+SET request_fingerprint = encode(
+        sha256(
+            convert_to(
+                user_id::TEXT || ':' || flash_sale_id::TEXT || ':' || quantity::TEXT,
+                'UTF8'
+            )
+        ),
+        'hex'
+    );
+
+-- Future inserts must provide explicit value
+ALTER TABLE orders
+ALTER COLUMN request_fingerprint SET NOT NULL;
@@ -0,0 +1,20 @@
+-- Phase 5: Generic idempotency for mutating endpoints
+-- Stores the first response for an Idempotency-Key so retries can be replayed
+
+CREATE TABLE idempotency_records (
+    -- Route and caller the key was used by (e.g. "POST /users user:<id>"),
+    -- keys are scoped per route and caller
+    scope TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_fingerprint TEXT NOT NULL,
+    -- NULL while the original request is still in progress
+    status_code INTEGER,
+    content_type TEXT,
+    response_body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
+
+-- Supports the periodic cleanup of expired records
+CREATE INDEX idx_idempotency_records_expires_at ON idempotency_records (expires_at);
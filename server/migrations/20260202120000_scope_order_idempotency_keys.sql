@@ -0,0 +1,11 @@
+-- Phase 5: Scope order idempotency keys to their caller
+-- Clients pick their own keys, so two users may pick the same one; a key is
+-- unique per user
+
+ALTER TABLE orders
+DROP CONSTRAINT orders_idempotency_key_unique;
+
+DROP INDEX idx_orders_idempotency_key;
+
+CREATE UNIQUE INDEX orders_idempotency_key_unique
+ON orders (user_id, idempotency_key);
@@ -0,0 +1,21 @@
+-- API keys for server-to-server clients (partner backends)
+-- Only a SHA-256 hash of the key is stored, the plaintext is shown once at issue
+
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4 (),
+    -- Shared by every key issued to the same client, kept across rotations
+    client_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    -- Leading characters of the key, lets operators identify a key in logs
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL,
+    scopes TEXT[] NOT NULL,
+    rate_limit_per_second INTEGER NOT NULL CHECK (rate_limit_per_second > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    -- Set on the old key during rotation so both keys work for a while
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    CONSTRAINT api_keys_key_hash_unique UNIQUE (key_hash)
+);
+
+CREATE INDEX idx_api_keys_client_id ON api_keys (client_id);
@@ -0,0 +1,10 @@
+-- Phase 5: Users each partner client may act for
+-- A partner key can only place and read orders on behalf of users linked to
+-- its client; links survive key rotation since they hang off the client id
+
+CREATE TABLE partner_users (
+    client_id UUID NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (client_id, user_id)
+);
@@ -0,0 +1,16 @@
+-- Phase 5: User profiles with email and username
+-- Users created before profiles existed keep NULL values until they
+-- fill them in with PATCH /users/{id}
+
+-- Add profile columns
+ALTER TABLE users
+ADD COLUMN email TEXT,
+    ADD COLUMN username TEXT;
+
+-- Emails are lowercased by the application, a plain unique constraint
+-- is enough to reject duplicates
+ALTER TABLE users
+ADD CONSTRAINT users_email_unique UNIQUE (email);
+
+-- Usernames keep the casing the user chose but are unique regardless of it
+CREATE UNIQUE INDEX users_username_unique ON users (LOWER(username));
@@ -0,0 +1,6 @@
+-- Phase 5: Keyset pagination for list endpoints
+-- Listings page over (created_at, id) in both directions
+
+CREATE INDEX idx_users_created_at_id ON users (created_at, id);
+
+CREATE INDEX idx_products_created_at_id ON products (created_at, id);
@@ -0,0 +1,6 @@
+-- Phase 5: Remember which partner client placed an order
+-- NULL for orders placed by users themselves; partners may only read
+-- back the orders they placed
+
+ALTER TABLE orders
+ADD COLUMN api_client_id UUID;
@@ -0,0 +1,8 @@
+-- Phase 5: Scope partner order idempotency keys to the client
+-- A partner picks keys across all the users it acts for, so the keys of the
+-- orders it places are unique per client rather than per user
+
+DROP INDEX orders_idempotency_key_unique;
+
+CREATE UNIQUE INDEX orders_idempotency_key_unique
+ON orders ((COALESCE(api_client_id, user_id)), idempotency_key);
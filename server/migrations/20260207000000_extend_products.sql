@@ -0,0 +1,18 @@
+-- Phase 5: Product management
+-- Optional catalog details, soft archiving and name search
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Add catalog details
+ALTER TABLE products
+ADD COLUMN description TEXT,
+    ADD COLUMN sku TEXT,
+    ADD COLUMN image_urls TEXT[] NOT NULL DEFAULT '{}',
+    -- Archived products stay readable but cannot get new flash sales
+    ADD COLUMN archived_at TIMESTAMPTZ;
+
+ALTER TABLE products
+ADD CONSTRAINT products_sku_unique UNIQUE (sku);
+
+-- Trigram index backs both similarity search and substring matches on names
+CREATE INDEX idx_products_name_trgm ON products USING GIN (name gin_trgm_ops);
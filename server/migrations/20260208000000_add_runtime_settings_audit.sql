@@ -0,0 +1,14 @@
+-- Phase 5: Audit trail for runtime settings
+-- Every hot reload of the runtime settings, whether made through the admin
+-- API or picked up from the config file, with the values before and after
+
+CREATE TABLE runtime_settings_audit (
+    id UUID PRIMARY KEY,
+    source TEXT NOT NULL,
+    changed_by UUID,
+    previous JSONB NOT NULL,
+    current JSONB NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_runtime_settings_audit_changed_at ON runtime_settings_audit (changed_at DESC);
@@ -0,0 +1,22 @@
+-- Phase 5: Durable handoff between the API and order workers
+-- When the API and the workers run as separate processes, admitted orders
+-- are written here and claimed by workers with FOR UPDATE SKIP LOCKED; the
+-- outcome is stored on the row for the API to pick up
+
+CREATE TABLE order_jobs (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id),
+    flash_sale_id UUID NOT NULL REFERENCES flash_sales(id),
+    quantity INT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_fingerprint TEXT NOT NULL,
+    api_client_id UUID,
+    status TEXT NOT NULL DEFAULT 'pending',
+    error TEXT,
+    error_code TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_order_jobs_pending ON order_jobs (created_at)
+WHERE status = 'pending';
@@ -0,0 +1,9 @@
+-- Phase 8: Worker heartbeats
+-- Each worker process regularly stamps its row so API readiness can tell
+-- whether any worker is still consuming the order_jobs table
+
+CREATE TABLE worker_heartbeats (
+    worker_id UUID PRIMARY KEY,
+    started_at TIMESTAMPTZ NOT NULL,
+    last_seen_at TIMESTAMPTZ NOT NULL
+);
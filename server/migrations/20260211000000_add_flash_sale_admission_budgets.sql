@@ -0,0 +1,11 @@
+-- Phase 5: Per-sale admission budgets
+-- Bound how much of the order queue and how many orders per second one sale
+-- may take, and how the worker weighs its orders against other sales
+
+ALTER TABLE flash_sales
+    ADD COLUMN queue_share DOUBLE PRECISION
+        CONSTRAINT flash_sales_queue_share_range CHECK (queue_share > 0 AND queue_share <= 1),
+    ADD COLUMN admission_rate_per_second INT
+        CONSTRAINT flash_sales_admission_rate_positive CHECK (admission_rate_per_second > 0),
+    ADD COLUMN scheduling_weight INT NOT NULL DEFAULT 1
+        CONSTRAINT flash_sales_scheduling_weight_positive CHECK (scheduling_weight > 0);
@@ -0,0 +1,14 @@
+-- Phase 5: Fair claiming of order jobs
+-- Each job gets a virtual turn when queued: one over its sale's weight past
+-- the later of the sale's last pending job and the job now first in line.
+-- Workers claim by turn, so sales take turns by weight instead of strictly
+-- oldest first, and a sale that starts late doesn't wait behind the backlog
+
+ALTER TABLE order_jobs
+    ADD COLUMN turn DOUBLE PRECISION NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_order_jobs_pending_turn ON order_jobs (turn, created_at)
+WHERE status = 'pending';
+
+CREATE INDEX idx_order_jobs_pending_sale_turn ON order_jobs (flash_sale_id, turn)
+WHERE status = 'pending';
@@ -0,0 +1,11 @@
+-- Phase 5: Priority lanes for order jobs
+-- Jobs keep the lane they were admitted to; turns are now counted per sale
+-- and lane, weighted by both, so workers serve the lanes by their weights
+
+ALTER TABLE order_jobs
+    ADD COLUMN lane TEXT NOT NULL DEFAULT 'standard';
+
+DROP INDEX idx_order_jobs_pending_sale_turn;
+
+CREATE INDEX idx_order_jobs_pending_sale_lane_turn ON order_jobs (flash_sale_id, lane, turn)
+WHERE status = 'pending';
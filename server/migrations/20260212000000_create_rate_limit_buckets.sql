@@ -0,0 +1,11 @@
+-- Phase 7: Shared rate limit buckets
+-- One GCRA bucket per limited key, so every API instance draws on the same
+-- budget; tat is the theoretical arrival time of the next request
+
+CREATE TABLE rate_limit_buckets (
+    key TEXT PRIMARY KEY,
+    tat TIMESTAMPTZ NOT NULL
+);
+
+-- Buckets whose tat has passed are full again and can be dropped
+CREATE INDEX idx_rate_limit_buckets_tat ON rate_limit_buckets (tat);
@@ -0,0 +1,5 @@
+-- Phase 7: Order challenges
+-- Sales that require a solved proof-of-work challenge with every order
+
+ALTER TABLE flash_sales
+    ADD COLUMN challenge_required BOOLEAN NOT NULL DEFAULT FALSE;
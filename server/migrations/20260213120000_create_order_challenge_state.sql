@@ -0,0 +1,20 @@
+-- Phase 7: Shared order challenge state
+-- Used challenges are recorded so a solved challenge admits one order across
+-- all instances and restarts; rows outlive the challenge only until it expires
+
+CREATE TABLE used_order_challenges (
+    nonce TEXT PRIMARY KEY,
+    order_id UUID NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_used_order_challenges_expires_at ON used_order_challenges (expires_at);
+
+-- One row holding the difficulty every instance issues challenges at, and the
+-- queue overflows the instances reported since the current window started
+CREATE TABLE order_challenge_difficulty (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    difficulty INTEGER NOT NULL CHECK (difficulty > 0),
+    window_started TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    overflows BIGINT NOT NULL DEFAULT 0 CHECK (overflows >= 0)
+);
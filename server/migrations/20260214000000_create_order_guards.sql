@@ -0,0 +1,32 @@
+-- Phase 8: Order guards
+-- Eligibility rules a sale applies before admitting an order: a global user
+-- blocklist, a minimum account age, a cap on orders across sales and a
+-- per-sale allowlist
+
+ALTER TABLE flash_sales
+    ADD COLUMN guard_blocklist BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN min_account_age_secs BIGINT
+        CONSTRAINT flash_sales_min_account_age_positive CHECK (min_account_age_secs > 0),
+    ADD COLUMN velocity_max_orders INT
+        CONSTRAINT flash_sales_velocity_max_orders_positive CHECK (velocity_max_orders > 0),
+    ADD COLUMN velocity_window_secs BIGINT
+        CONSTRAINT flash_sales_velocity_window_positive CHECK (velocity_window_secs > 0),
+    ADD COLUMN allowlist_only BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD CONSTRAINT flash_sales_velocity_complete
+        CHECK ((velocity_max_orders IS NULL) = (velocity_window_secs IS NULL));
+
+CREATE TABLE blocked_users (
+    user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE flash_sale_allowlist (
+    flash_sale_id UUID NOT NULL REFERENCES flash_sales (id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (flash_sale_id, user_id)
+);
+
+-- Velocity counts a user's recent orders across all sales
+CREATE INDEX idx_orders_user_created_at ON orders (user_id, created_at);
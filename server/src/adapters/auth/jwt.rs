@@ -0,0 +1,194 @@
+use anyhow::Context;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    app::config::JwtConfig,
+    domain::auth::{AuthenticatedUser, Role, UserTier},
+    errors::ServiceError,
+    ports::TokenVerifier,
+};
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    /// Absent for regular customers
+    #[serde(default)]
+    role: Role,
+    /// Absent for standard tier customers
+    #[serde(default)]
+    tier: UserTier,
+}
+
+/// Verifies HS256 or RS256 signed JWTs
+///
+/// The key is read from `jwt.key_file`: the shared secret for HS256, or the
+/// PEM encoded public key for RS256.
+pub struct JwtTokenVerifier {
+    decoding_key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtTokenVerifier {
+    pub fn from_config(cfg: &JwtConfig) -> anyhow::Result<Self> {
+        let algorithm = match cfg.algorithm.as_str() {
+            "HS256" => Algorithm::HS256,
+            "RS256" => Algorithm::RS256,
+            other => anyhow::bail!(
+                "Unsupported JWT_ALGORITHM {}, expected HS256 or RS256",
+                other
+            ),
+        };
+
+        let key = std::fs::read(&cfg.key_file)
+            .with_context(|| format!("Failed to read JWT key file {}", cfg.key_file))?;
+
+        let decoding_key = match algorithm {
+            Algorithm::RS256 => DecodingKey::from_rsa_pem(&key)
+                .context("JWT key file is not a valid RSA PEM key")?,
+            _ => DecodingKey::from_secret(key.trim_ascii()),
+        };
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if let Some(issuer) = &cfg.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &cfg.audience {
+            validation.set_audience(&[audience]);
+        }
+
+        Ok(Self {
+            decoding_key,
+            validation,
+        })
+    }
+}
+
+impl TokenVerifier for JwtTokenVerifier {
+    fn verify(&self, token: &str) -> Result<AuthenticatedUser, ServiceError> {
+        let data = decode::<Claims>(token, &self.decoding_key, &self.validation).map_err(|e| {
+            tracing::debug!(error = %e, "Rejected bearer token");
+            ServiceError::Unauthenticated
+        })?;
+
+        let user_id = Uuid::parse_str(&data.claims.sub).map_err(|_| {
+            tracing::debug!("Rejected bearer token: subject is not a user id");
+            ServiceError::Unauthenticated
+        })?;
+
+        Ok(AuthenticatedUser {
+            user_id,
+            role: data.claims.role,
+            tier: data.claims.tier,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{EncodingKey, Header, encode};
+    use serde_json::{Value, json};
+
+    const SECRET: &[u8] = b"test-secret";
+
+    fn verifier() -> JwtTokenVerifier {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        JwtTokenVerifier {
+            decoding_key: DecodingKey::from_secret(SECRET),
+            validation,
+        }
+    }
+
+    fn token(claims: Value, secret: &[u8]) -> String {
+        encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    fn expires_in(seconds: i64) -> i64 {
+        chrono::Utc::now().timestamp() + seconds
+    }
+
+    #[test]
+    fn accepts_a_signed_token_for_a_user() {
+        let user_id = Uuid::from_u128(1);
+        let token = token(
+            json!({ "sub": user_id.to_string(), "exp": expires_in(300) }),
+            SECRET,
+        );
+
+        assert_eq!(verifier().verify(&token).unwrap().user_id, user_id);
+    }
+
+    #[test]
+    fn rejects_expired_tokens() {
+        let token = token(
+            json!({ "sub": Uuid::from_u128(1).to_string(), "exp": expires_in(-300) }),
+            SECRET,
+        );
+
+        assert!(matches!(
+            verifier().verify(&token),
+            Err(ServiceError::Unauthenticated)
+        ));
+    }
+
+    #[test]
+    fn rejects_tokens_signed_with_another_key() {
+        let token = token(
+            json!({ "sub": Uuid::from_u128(1).to_string(), "exp": expires_in(300) }),
+            b"another-secret",
+        );
+
+        assert!(matches!(
+            verifier().verify(&token),
+            Err(ServiceError::Unauthenticated)
+        ));
+    }
+
+    #[test]
+    fn rejects_tokens_without_an_expiry_or_a_user_subject() {
+        let no_expiry = token(json!({ "sub": Uuid::from_u128(1).to_string() }), SECRET);
+        let not_a_user = token(json!({ "sub": "alice", "exp": expires_in(300) }), SECRET);
+
+        for token in [no_expiry, not_a_user, "not-a-token".to_string()] {
+            assert!(matches!(
+                verifier().verify(&token),
+                Err(ServiceError::Unauthenticated)
+            ));
+        }
+    }
+
+    #[test]
+    fn reads_the_role_claim_and_defaults_to_customer() {
+        let sub = Uuid::from_u128(1).to_string();
+        let admin = token(
+            json!({ "sub": sub, "exp": expires_in(300), "role": "admin" }),
+            SECRET,
+        );
+        let customer = token(json!({ "sub": sub, "exp": expires_in(300) }), SECRET);
+
+        assert_eq!(verifier().verify(&admin).unwrap().role, Role::Admin);
+        assert_eq!(verifier().verify(&customer).unwrap().role, Role::Customer);
+    }
+
+    #[test]
+    fn rejects_unknown_roles() {
+        let token = token(
+            json!({ "sub": Uuid::from_u128(1).to_string(), "exp": expires_in(300), "role": "root" }),
+            SECRET,
+        );
+
+        assert!(matches!(
+            verifier().verify(&token),
+            Err(ServiceError::Unauthenticated)
+        ));
+    }
+}
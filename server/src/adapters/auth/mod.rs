@@ -0,0 +1,3 @@
+pub mod jwt;
+
+pub use jwt::JwtTokenVerifier;
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::api_key::ApiKey;
+
+#[derive(Debug, FromRow)]
+pub struct ApiKeyRecord {
+    pub id: Uuid,
+    pub client_id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub rate_limit_per_second: i32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<&ApiKey> for ApiKeyRecord {
+    fn from(value: &ApiKey) -> Self {
+        Self {
+            id: value.id,
+            client_id: value.client_id,
+            name: value.name.clone(),
+            key_prefix: value.key_prefix.clone(),
+            key_hash: value.key_hash.clone(),
+            scopes: value.scopes.iter().map(|s| s.as_str().to_owned()).collect(),
+            rate_limit_per_second: value.rate_limit_per_second as i32,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            revoked_at: value.revoked_at,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    adapters::db::{api_key::ApiKeyRecord, error_mapper::map_sqlx_error},
+    domain::api_key::ApiKey,
+    errors::RepoError,
+    ports::ApiKeyRepo,
+};
+
+#[derive(Default)]
+pub struct PostgresApiKeyRepo;
+
+impl PostgresApiKeyRepo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn to_domain(record: ApiKeyRecord) -> Result<ApiKey, RepoError> {
+    ApiKey::try_from(record).map_err(|e| RepoError::Database {
+        operation: "convert_api_key_record",
+        source: sqlx::Error::Decode(Box::new(e)),
+    })
+}
+
+#[async_trait]
+impl ApiKeyRepo for PostgresApiKeyRepo {
+    async fn save(&self, conn: &mut PgConnection, api_key: &ApiKey) -> Result<ApiKey, RepoError> {
+        let record = ApiKeyRecord::from(api_key);
+
+        let saved_record = sqlx::query_as!(
+            ApiKeyRecord,
+            r#"
+            INSERT INTO api_keys (id, client_id, name, key_prefix, key_hash, scopes,
+                                  rate_limit_per_second, created_at, expires_at, revoked_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, client_id, name, key_prefix, key_hash, scopes,
+                      rate_limit_per_second, created_at, expires_at, revoked_at
+            "#,
+            record.id,
+            record.client_id,
+            record.name,
+            record.key_prefix,
+            record.key_hash,
+            &record.scopes,
+            record.rate_limit_per_second,
+            record.created_at,
+            record.expires_at,
+            record.revoked_at
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "save_api_key", "api_key"))?;
+
+        to_domain(saved_record)
+    }
+
+    async fn find_by_id(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<ApiKey>, RepoError> {
+        let record = sqlx::query_as!(
+            ApiKeyRecord,
+            r#"
+            SELECT id, client_id, name, key_prefix, key_hash, scopes,
+                   rate_limit_per_second, created_at, expires_at, revoked_at
+            FROM api_keys
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "find_api_key_by_id", "api_key"))?;
+
+        record.map(to_domain).transpose()
+    }
+
+    async fn find_by_hash(
+        &self,
+        conn: &mut PgConnection,
+        key_hash: &str,
+    ) -> Result<Option<ApiKey>, RepoError> {
+        let record = sqlx::query_as!(
+            ApiKeyRecord,
+            r#"
+            SELECT id, client_id, name, key_prefix, key_hash, scopes,
+                   rate_limit_per_second, created_at, expires_at, revoked_at
+            FROM api_keys
+            WHERE key_hash = $1
+            "#,
+            key_hash
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "find_api_key_by_hash", "api_key"))?;
+
+        record.map(to_domain).transpose()
+    }
+
+    async fn get_all(&self, conn: &mut PgConnection) -> Result<Vec<ApiKey>, RepoError> {
+        let records = sqlx::query_as!(
+            ApiKeyRecord,
+            r#"
+            SELECT id, client_id, name, key_prefix, key_hash, scopes,
+                   rate_limit_per_second, created_at, expires_at, revoked_at
+            FROM api_keys
+            ORDER BY created_at
+            "#
+        )
+        .fetch_all(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "get_all_api_keys", "api_key"))?;
+
+        records.into_iter().map(to_domain).collect()
+    }
+
+    async fn update_validity(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+        revoked_at: Option<DateTime<Utc>>,
+    ) -> Result<ApiKey, RepoError> {
+        let record = sqlx::query_as!(
+            ApiKeyRecord,
+            r#"
+            UPDATE api_keys
+            SET expires_at = $2, revoked_at = $3
+            WHERE id = $1
+            RETURNING id, client_id, name, key_prefix, key_hash, scopes,
+                      rate_limit_per_second, created_at, expires_at, revoked_at
+            "#,
+            id,
+            expires_at,
+            revoked_at
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "update_api_key_validity", "api_key"))?;
+
+        to_domain(record)
+    }
+
+    async fn client_exists(
+        &self,
+        conn: &mut PgConnection,
+        client_id: Uuid,
+    ) -> Result<bool, RepoError> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM api_keys WHERE client_id = $1) AS "exists!""#,
+            client_id
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "api_client_exists", "api_client"))
+    }
+
+    async fn link_user(
+        &self,
+        conn: &mut PgConnection,
+        client_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO partner_users (client_id, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            client_id,
+            user_id
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "link_partner_user", "partner_user"))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn unlink_user(
+        &self,
+        conn: &mut PgConnection,
+        client_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            "DELETE FROM partner_users WHERE client_id = $1 AND user_id = $2",
+            client_id,
+            user_id
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "unlink_partner_user", "partner_user"))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn is_user_linked(
+        &self,
+        conn: &mut PgConnection,
+        client_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM partner_users WHERE client_id = $1 AND user_id = $2
+            ) AS "linked!"
+            "#,
+            client_id,
+            user_id
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "is_partner_user_linked", "partner_user"))
+    }
+}
@@ -1,4 +1,4 @@
 pub mod record;
 pub mod repository;
 
-pub use record::FlashSaleRecord;
+pub use record::{FlashSaleDetailsRecord, FlashSaleRecord};
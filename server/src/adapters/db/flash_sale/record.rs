@@ -13,6 +13,15 @@ pub struct FlashSaleRecord {
     pub total_inventory: i32,
     pub remaining_inventory: i32,
     pub per_user_limit: i32,
+    pub queue_share: Option<f64>,
+    pub admission_rate_per_second: Option<i32>,
+    pub scheduling_weight: i32,
+    pub challenge_required: bool,
+    pub guard_blocklist: bool,
+    pub min_account_age_secs: Option<i64>,
+    pub velocity_max_orders: Option<i32>,
+    pub velocity_window_secs: Option<i64>,
+    pub allowlist_only: bool,
     pub created_at: DateTime<Utc>,
 }
 
@@ -26,7 +35,44 @@ impl From<FlashSale> for FlashSaleRecord {
             total_inventory: value.total_inventory,
             remaining_inventory: value.remaining_inventory,
             per_user_limit: value.per_user_limit,
+            queue_share: value.admission.queue_share,
+            admission_rate_per_second: value.admission.rate_per_second,
+            scheduling_weight: value.admission.scheduling_weight,
+            challenge_required: value.admission.challenge_required,
+            guard_blocklist: value.guards.blocklist,
+            min_account_age_secs: value.guards.min_account_age_secs,
+            velocity_max_orders: value.guards.velocity.map(|limit| limit.max_orders),
+            velocity_window_secs: value.guards.velocity.map(|limit| limit.window_secs),
+            allowlist_only: value.guards.allowlist_only,
             created_at: value.created_at,
         }
     }
 }
+
+/// Flash sale row joined with its product
+#[derive(Debug, FromRow)]
+pub struct FlashSaleDetailsRecord {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub total_inventory: i32,
+    pub remaining_inventory: i32,
+    pub per_user_limit: i32,
+    pub queue_share: Option<f64>,
+    pub admission_rate_per_second: Option<i32>,
+    pub scheduling_weight: i32,
+    pub challenge_required: bool,
+    pub guard_blocklist: bool,
+    pub min_account_age_secs: Option<i64>,
+    pub velocity_max_orders: Option<i32>,
+    pub velocity_window_secs: Option<i64>,
+    pub allowlist_only: bool,
+    pub created_at: DateTime<Utc>,
+    pub product_name: String,
+    pub product_description: Option<String>,
+    pub product_sku: Option<String>,
+    pub product_image_urls: Vec<String>,
+    pub product_created_at: DateTime<Utc>,
+    pub product_archived_at: Option<DateTime<Utc>>,
+}
@@ -1,14 +1,22 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::PgConnection;
 use uuid::Uuid;
 
 use crate::{
-    adapters::db::{error_mapper::map_sqlx_error, flash_sale::FlashSaleRecord},
-    domain::flash_sale::FlashSale,
+    adapters::db::{
+        error_mapper::map_sqlx_error,
+        flash_sale::{FlashSaleDetailsRecord, FlashSaleRecord},
+    },
+    domain::{
+        flash_sale::{AdmissionBudget, FlashSale, FlashSaleDetails},
+        order_guard::OrderGuardSettings,
+    },
     errors::RepoError,
     ports::flash_sale_repo::FlashSaleRepo,
 };
 
+#[derive(Default)]
 pub struct PostgresFlashSaleRepo;
 
 impl PostgresFlashSaleRepo {
@@ -17,8 +25,66 @@ impl PostgresFlashSaleRepo {
     }
 }
 
+fn to_details(records: Vec<FlashSaleDetailsRecord>) -> Result<Vec<FlashSaleDetails>, RepoError> {
+    records
+        .into_iter()
+        .map(FlashSaleDetails::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| RepoError::Database {
+            operation: "convert_flash_sale_details_records",
+            source: sqlx::Error::Decode(Box::new(e)),
+        })
+}
+
 #[async_trait]
 impl FlashSaleRepo for PostgresFlashSaleRepo {
+    async fn save(
+        &self,
+        conn: &mut PgConnection,
+        flash_sale: &FlashSale,
+    ) -> Result<FlashSale, RepoError> {
+        let saved_record = sqlx::query_as!(
+            FlashSaleRecord,
+            r#"
+            INSERT INTO flash_sales (id, product_id, start_time, end_time, total_inventory,
+                                     remaining_inventory, per_user_limit, queue_share,
+                                     admission_rate_per_second, scheduling_weight,
+                                     challenge_required, guard_blocklist,
+                                     min_account_age_secs, velocity_max_orders,
+                                     velocity_window_secs, allowlist_only, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16,
+                    $17)
+            RETURNING id, product_id, start_time, end_time, total_inventory,
+                      remaining_inventory, per_user_limit, queue_share,
+                      admission_rate_per_second, scheduling_weight, challenge_required,
+                      guard_blocklist, min_account_age_secs, velocity_max_orders,
+                      velocity_window_secs, allowlist_only, created_at
+            "#,
+            flash_sale.id,
+            flash_sale.product_id,
+            flash_sale.start_time,
+            flash_sale.end_time,
+            flash_sale.total_inventory,
+            flash_sale.remaining_inventory,
+            flash_sale.per_user_limit,
+            flash_sale.admission.queue_share,
+            flash_sale.admission.rate_per_second,
+            flash_sale.admission.scheduling_weight,
+            flash_sale.admission.challenge_required,
+            flash_sale.guards.blocklist,
+            flash_sale.guards.min_account_age_secs,
+            flash_sale.guards.velocity.map(|limit| limit.max_orders),
+            flash_sale.guards.velocity.map(|limit| limit.window_secs),
+            flash_sale.guards.allowlist_only,
+            flash_sale.created_at
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "save_flash_sale", "flash_sale"))?;
+
+        Ok(saved_record.into())
+    }
+
     async fn find_by_id_with_lock(
         &self,
         conn: &mut PgConnection,
@@ -29,7 +95,10 @@ impl FlashSaleRepo for PostgresFlashSaleRepo {
             FlashSaleRecord,
             r#"
             SELECT id, product_id, start_time, end_time, total_inventory, 
-                   remaining_inventory, per_user_limit, created_at
+                   remaining_inventory, per_user_limit, queue_share,
+                      admission_rate_per_second, scheduling_weight, challenge_required,
+                      guard_blocklist, min_account_age_secs, velocity_max_orders,
+                      velocity_window_secs, allowlist_only, created_at
             FROM flash_sales
             WHERE id = $1
             FOR UPDATE
@@ -43,6 +112,107 @@ impl FlashSaleRepo for PostgresFlashSaleRepo {
         Ok(record.map(FlashSale::from))
     }
 
+    async fn find_details_by_id(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<FlashSaleDetails>, RepoError> {
+        let record = sqlx::query_as!(
+            FlashSaleDetailsRecord,
+            r#"
+            SELECT fs.id, fs.product_id, fs.start_time, fs.end_time, fs.total_inventory,
+                   fs.remaining_inventory, fs.per_user_limit, fs.queue_share,
+                   fs.admission_rate_per_second, fs.scheduling_weight, fs.challenge_required,
+                   fs.guard_blocklist, fs.min_account_age_secs, fs.velocity_max_orders,
+                   fs.velocity_window_secs, fs.allowlist_only, fs.created_at,
+                   p.name AS product_name, p.description AS product_description,
+                   p.sku AS product_sku, p.image_urls AS product_image_urls,
+                   p.created_at AS product_created_at, p.archived_at AS product_archived_at
+            FROM flash_sales fs
+            JOIN products p ON p.id = fs.product_id
+            WHERE fs.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "find_flash_sale_details", "flash_sale"))?;
+
+        record
+            .map(FlashSaleDetails::try_from)
+            .transpose()
+            .map_err(|e| RepoError::Database {
+                operation: "convert_flash_sale_details_record",
+                source: sqlx::Error::Decode(Box::new(e)),
+            })
+    }
+
+    async fn list_live(
+        &self,
+        conn: &mut PgConnection,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<FlashSaleDetails>, RepoError> {
+        let records = sqlx::query_as!(
+            FlashSaleDetailsRecord,
+            r#"
+            SELECT fs.id, fs.product_id, fs.start_time, fs.end_time, fs.total_inventory,
+                   fs.remaining_inventory, fs.per_user_limit, fs.queue_share,
+                   fs.admission_rate_per_second, fs.scheduling_weight, fs.challenge_required,
+                   fs.guard_blocklist, fs.min_account_age_secs, fs.velocity_max_orders,
+                   fs.velocity_window_secs, fs.allowlist_only, fs.created_at,
+                   p.name AS product_name, p.description AS product_description,
+                   p.sku AS product_sku, p.image_urls AS product_image_urls,
+                   p.created_at AS product_created_at, p.archived_at AS product_archived_at
+            FROM flash_sales fs
+            JOIN products p ON p.id = fs.product_id
+            WHERE fs.start_time <= $1 AND fs.end_time >= $1
+            ORDER BY fs.end_time, fs.id
+            LIMIT $2
+            "#,
+            now,
+            limit
+        )
+        .fetch_all(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "list_live_flash_sales", "flash_sale"))?;
+
+        to_details(records)
+    }
+
+    async fn list_upcoming(
+        &self,
+        conn: &mut PgConnection,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<FlashSaleDetails>, RepoError> {
+        let records = sqlx::query_as!(
+            FlashSaleDetailsRecord,
+            r#"
+            SELECT fs.id, fs.product_id, fs.start_time, fs.end_time, fs.total_inventory,
+                   fs.remaining_inventory, fs.per_user_limit, fs.queue_share,
+                   fs.admission_rate_per_second, fs.scheduling_weight, fs.challenge_required,
+                   fs.guard_blocklist, fs.min_account_age_secs, fs.velocity_max_orders,
+                   fs.velocity_window_secs, fs.allowlist_only, fs.created_at,
+                   p.name AS product_name, p.description AS product_description,
+                   p.sku AS product_sku, p.image_urls AS product_image_urls,
+                   p.created_at AS product_created_at, p.archived_at AS product_archived_at
+            FROM flash_sales fs
+            JOIN products p ON p.id = fs.product_id
+            WHERE fs.start_time > $1
+            ORDER BY fs.start_time, fs.id
+            LIMIT $2
+            "#,
+            now,
+            limit
+        )
+        .fetch_all(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "list_upcoming_flash_sales", "flash_sale"))?;
+
+        to_details(records)
+    }
+
     async fn update(
         &self,
         conn: &mut PgConnection,
@@ -55,7 +225,10 @@ impl FlashSaleRepo for PostgresFlashSaleRepo {
             SET remaining_inventory = $2
             WHERE id = $1
             RETURNING id, product_id, start_time, end_time, total_inventory, 
-                      remaining_inventory, per_user_limit, created_at
+                      remaining_inventory, per_user_limit, queue_share,
+                      admission_rate_per_second, scheduling_weight, challenge_required,
+                      guard_blocklist, min_account_age_secs, velocity_max_orders,
+                      velocity_window_secs, allowlist_only, created_at
             "#,
             flash_sale.id,
             flash_sale.remaining_inventory
@@ -66,4 +239,69 @@ impl FlashSaleRepo for PostgresFlashSaleRepo {
 
         Ok(saved_record.into())
     }
+
+    async fn update_admission(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        admission: &AdmissionBudget,
+    ) -> Result<Option<FlashSale>, RepoError> {
+        let record = sqlx::query_as!(
+            FlashSaleRecord,
+            r#"
+            UPDATE flash_sales
+            SET queue_share = $2, admission_rate_per_second = $3, scheduling_weight = $4,
+                challenge_required = $5
+            WHERE id = $1
+            RETURNING id, product_id, start_time, end_time, total_inventory,
+                      remaining_inventory, per_user_limit, queue_share,
+                      admission_rate_per_second, scheduling_weight, challenge_required,
+                      guard_blocklist, min_account_age_secs, velocity_max_orders,
+                      velocity_window_secs, allowlist_only, created_at
+            "#,
+            id,
+            admission.queue_share,
+            admission.rate_per_second,
+            admission.scheduling_weight,
+            admission.challenge_required
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "update_flash_sale_admission", "flash_sale"))?;
+
+        Ok(record.map(FlashSale::from))
+    }
+
+    async fn update_guards(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        guards: &OrderGuardSettings,
+    ) -> Result<Option<FlashSale>, RepoError> {
+        let record = sqlx::query_as!(
+            FlashSaleRecord,
+            r#"
+            UPDATE flash_sales
+            SET guard_blocklist = $2, min_account_age_secs = $3, velocity_max_orders = $4,
+                velocity_window_secs = $5, allowlist_only = $6
+            WHERE id = $1
+            RETURNING id, product_id, start_time, end_time, total_inventory,
+                      remaining_inventory, per_user_limit, queue_share,
+                      admission_rate_per_second, scheduling_weight, challenge_required,
+                      guard_blocklist, min_account_age_secs, velocity_max_orders,
+                      velocity_window_secs, allowlist_only, created_at
+            "#,
+            id,
+            guards.blocklist,
+            guards.min_account_age_secs,
+            guards.velocity.map(|limit| limit.max_orders),
+            guards.velocity.map(|limit| limit.window_secs),
+            guards.allowlist_only
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "update_flash_sale_guards", "flash_sale"))?;
+
+        Ok(record.map(FlashSale::from))
+    }
 }
@@ -0,0 +1,4 @@
+pub mod record;
+pub mod repository;
+
+pub use record::IdempotencyRow;
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::domain::idempotency::{IdempotencyRecord, StoredResponse};
+
+#[derive(Debug, FromRow)]
+pub struct IdempotencyRow {
+    pub scope: String,
+    pub idempotency_key: String,
+    pub request_fingerprint: String,
+    pub status_code: Option<i32>,
+    pub content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<IdempotencyRow> for IdempotencyRecord {
+    fn from(value: IdempotencyRow) -> Self {
+        let response = value.status_code.map(|status_code| StoredResponse {
+            status_code: status_code as u16,
+            content_type: value.content_type,
+            body: value.response_body.unwrap_or_default(),
+        });
+
+        Self {
+            scope: value.scope,
+            idempotency_key: value.idempotency_key,
+            request_fingerprint: value.request_fingerprint,
+            response,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+
+use crate::{
+    adapters::db::{error_mapper::map_sqlx_error, idempotency::IdempotencyRow},
+    domain::idempotency::{IdempotencyRecord, StoredResponse},
+    errors::RepoError,
+    ports::IdempotencyRepo,
+};
+
+#[derive(Default)]
+pub struct PostgresIdempotencyRepo;
+
+impl PostgresIdempotencyRepo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepo for PostgresIdempotencyRepo {
+    async fn insert_in_progress(
+        &self,
+        conn: &mut PgConnection,
+        record: &IdempotencyRecord,
+    ) -> Result<Option<DateTime<Utc>>, RepoError> {
+        // An expired record that the cleanup job has not removed yet is taken over
+        let claimed = sqlx::query_scalar!(
+            r#"
+            INSERT INTO idempotency_records (scope, idempotency_key, request_fingerprint, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (scope, idempotency_key) DO UPDATE
+            SET request_fingerprint = EXCLUDED.request_fingerprint,
+                status_code = NULL,
+                content_type = NULL,
+                response_body = NULL,
+                created_at = EXCLUDED.created_at,
+                expires_at = EXCLUDED.expires_at
+            WHERE idempotency_records.expires_at <= now()
+            RETURNING created_at
+            "#,
+            record.scope,
+            record.idempotency_key,
+            record.request_fingerprint,
+            record.created_at,
+            record.expires_at
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "insert_idempotency_record", "idempotency_record"))?;
+
+        Ok(claimed)
+    }
+
+    async fn find(
+        &self,
+        conn: &mut PgConnection,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<IdempotencyRecord>, RepoError> {
+        let record = sqlx::query_as!(
+            IdempotencyRow,
+            r#"
+            SELECT scope, idempotency_key, request_fingerprint, status_code,
+                   content_type, response_body, created_at, expires_at
+            FROM idempotency_records
+            WHERE scope = $1 AND idempotency_key = $2
+            "#,
+            scope,
+            key
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "find_idempotency_record", "idempotency_record"))?;
+
+        Ok(record.map(Into::into))
+    }
+
+    async fn complete(
+        &self,
+        conn: &mut PgConnection,
+        scope: &str,
+        key: &str,
+        claimed_at: DateTime<Utc>,
+        response: &StoredResponse,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, RepoError> {
+        // A request whose lease ran out may finish after another one took
+        // the key over, the claim time tells them apart
+        let result = sqlx::query!(
+            r#"
+            UPDATE idempotency_records
+            SET status_code = $4, content_type = $5, response_body = $6, expires_at = $7
+            WHERE scope = $1 AND idempotency_key = $2
+              AND status_code IS NULL AND created_at = $3
+            "#,
+            scope,
+            key,
+            claimed_at,
+            response.status_code as i32,
+            response.content_type,
+            response.body,
+            expires_at
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "complete_idempotency_record", "idempotency_record"))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete(
+        &self,
+        conn: &mut PgConnection,
+        scope: &str,
+        key: &str,
+        claimed_at: DateTime<Utc>,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_records
+            WHERE scope = $1 AND idempotency_key = $2
+              AND status_code IS NULL AND created_at = $3
+            "#,
+            scope,
+            key,
+            claimed_at
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "delete_idempotency_record", "idempotency_record"))?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self, conn: &mut PgConnection) -> Result<u64, RepoError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM idempotency_records
+            WHERE expires_at <= now()
+            "#
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| {
+            map_sqlx_error(
+                e,
+                "delete_expired_idempotency_records",
+                "idempotency_record",
+            )
+        })?;
+
+        Ok(result.rows_affected())
+    }
+}
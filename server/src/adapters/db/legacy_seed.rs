@@ -0,0 +1,104 @@
+use sqlx::PgPool;
+
+use crate::{adapters::db::error_mapper::map_sqlx_error, errors::RepoError};
+
+/// Sale created by the seed data migration
+const LEGACY_FLASH_SALE_ID: uuid::Uuid = uuid::uuid!("11111111-1111-1111-1111-111111111111");
+/// Product created by the seed data migration
+const LEGACY_PRODUCT_ID: uuid::Uuid = uuid::uuid!("00000000-0000-0000-0000-000000000001");
+
+/// Rows removed by [`remove_legacy_seed`]
+#[derive(Debug)]
+pub struct LegacySeedRemoval {
+    pub orders: u64,
+    pub order_jobs: u64,
+    pub users: u64,
+    pub flash_sales: u64,
+    pub products: u64,
+}
+
+/// Removes what the seed data migration inserted, in one transaction
+///
+/// Only run on request, the rows can't be told apart from real data with
+/// certainty. Takes the migration's sale with its orders and jobs, its
+/// product unless another sale uses it, and the users it created: those
+/// without a profile created in the product's transaction, which have no
+/// orders elsewhere.
+pub async fn remove_legacy_seed(pool: &PgPool) -> Result<LegacySeedRemoval, RepoError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    let order_jobs = sqlx::query!(
+        "DELETE FROM order_jobs WHERE flash_sale_id = $1",
+        LEGACY_FLASH_SALE_ID
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_sqlx_error(e, "remove_legacy_seed_order_jobs", "order_job"))?
+    .rows_affected();
+
+    let orders = sqlx::query!(
+        "DELETE FROM orders WHERE flash_sale_id = $1",
+        LEGACY_FLASH_SALE_ID
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_sqlx_error(e, "remove_legacy_seed_orders", "order"))?
+    .rows_affected();
+
+    // The migration ran in one transaction, so its users share the
+    // product's created_at exactly
+    let users = sqlx::query!(
+        r#"
+        DELETE FROM users u
+        USING products p
+        WHERE p.id = $1
+            AND u.created_at = p.created_at
+            AND u.email IS NULL
+            AND u.username IS NULL
+            AND NOT EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.id)
+            AND NOT EXISTS (SELECT 1 FROM order_jobs j WHERE j.user_id = u.id)
+        "#,
+        LEGACY_PRODUCT_ID
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_sqlx_error(e, "remove_legacy_seed_users", "user"))?
+    .rows_affected();
+
+    let flash_sales = sqlx::query!(
+        "DELETE FROM flash_sales WHERE id = $1",
+        LEGACY_FLASH_SALE_ID
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_sqlx_error(e, "remove_legacy_seed_flash_sale", "flash_sale"))?
+    .rows_affected();
+
+    let products = sqlx::query!(
+        r#"
+        DELETE FROM products p
+        WHERE p.id = $1
+            AND NOT EXISTS (SELECT 1 FROM flash_sales s WHERE s.product_id = p.id)
+        "#,
+        LEGACY_PRODUCT_ID
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| map_sqlx_error(e, "remove_legacy_seed_product", "product"))?
+    .rows_affected();
+
+    tx.commit()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    Ok(LegacySeedRemoval {
+        orders,
+        order_jobs,
+        users,
+        flash_sales,
+        products,
+    })
+}
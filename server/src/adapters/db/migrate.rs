@@ -0,0 +1,6 @@
+use sqlx::{PgPool, migrate::MigrateError};
+
+/// Applies the migrations embedded at build time
+pub async fn run_migrations(pool: &PgPool) -> Result<(), MigrateError> {
+    sqlx::migrate!("./migrations").run(pool).await
+}
@@ -1,6 +1,17 @@
+pub mod api_key;
 pub mod error_mapper;
 pub mod flash_sale;
+pub mod idempotency;
+pub mod legacy_seed;
+pub mod migrate;
 pub mod order;
+pub mod order_challenge;
+pub mod order_guard;
+pub mod order_job;
+pub mod pattern;
 pub mod pool;
 pub mod product;
+pub mod rate_limit;
+pub mod settings_audit;
 pub mod user;
+pub mod worker_heartbeat;
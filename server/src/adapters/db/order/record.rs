@@ -12,6 +12,8 @@ pub struct OrderRecord {
     pub quantity: i32,
     pub status: OrderStatus,
     pub idempotency_key: String,
+    pub request_fingerprint: String,
+    pub api_client_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -24,6 +26,8 @@ impl From<Order> for OrderRecord {
             quantity: order.quantity,
             status: order.status,
             idempotency_key: order.idempotency_key,
+            request_fingerprint: order.request_fingerprint,
+            api_client_id: order.api_client_id,
             created_at: order.created_at,
         }
     }
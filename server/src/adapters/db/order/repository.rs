@@ -1,13 +1,19 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::PgConnection;
+use uuid::Uuid;
 
 use crate::{
     adapters::db::{error_mapper::map_sqlx_error, order::OrderRecord},
-    domain::order::{Order, OrderStatus},
+    domain::{
+        order::{Order, OrderFilter, OrderStatus},
+        pagination::{Cursor, Page, PageRequest, SortOrder},
+    },
     errors::RepoError,
     ports::order_repo::OrderRepo,
 };
 
+#[derive(Default)]
 pub struct PostgresOrderRepo;
 
 impl PostgresOrderRepo {
@@ -22,9 +28,9 @@ impl OrderRepo for PostgresOrderRepo {
         let saved_record = sqlx::query_as!(
             OrderRecord,
             r#"
-            INSERT INTO orders (id, user_id, flash_sale_id, quantity, status, idempotency_key, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING id, user_id, flash_sale_id, quantity, status as "status: OrderStatus", idempotency_key, created_at
+            INSERT INTO orders (id, user_id, flash_sale_id, quantity, status, idempotency_key, request_fingerprint, api_client_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, user_id, flash_sale_id, quantity, status as "status: OrderStatus", idempotency_key, request_fingerprint, api_client_id, created_at
             "#,
             order.id,
             order.user_id,
@@ -32,6 +38,8 @@ impl OrderRepo for PostgresOrderRepo {
             order.quantity,
             order.status as OrderStatus,
             order.idempotency_key,
+            order.request_fingerprint,
+            order.api_client_id,
             order.created_at
         )
         .fetch_one(conn)
@@ -44,16 +52,17 @@ impl OrderRepo for PostgresOrderRepo {
     async fn find_by_idempotency_key(
         &self,
         conn: &mut PgConnection,
+        owner: Uuid,
         key: &str,
     ) -> Result<Option<Order>, RepoError> {
         let result = sqlx::query_as!(
             OrderRecord,
             r#"
-            SELECT id, user_id, flash_sale_id, quantity, status as "status: OrderStatus", idempotency_key, created_at
+            SELECT id, user_id, flash_sale_id, quantity, status as "status: OrderStatus", idempotency_key, request_fingerprint, api_client_id, created_at
             FROM orders
-            WHERE idempotency_key = $1
-            LIMIT 1
+            WHERE COALESCE(api_client_id, user_id) = $1 AND idempotency_key = $2
             "#,
+            owner,
             key
         )
         .fetch_optional(conn)
@@ -62,4 +71,136 @@ impl OrderRepo for PostgresOrderRepo {
 
         Ok(result.map(Into::into))
     }
+
+    async fn find_by_id(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<Order>, RepoError> {
+        let result = sqlx::query_as!(
+            OrderRecord,
+            r#"
+            SELECT id, user_id, flash_sale_id, quantity, status as "status: OrderStatus", idempotency_key, request_fingerprint, api_client_id, created_at
+            FROM orders
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "find_order_by_id", "order"))?;
+
+        Ok(result.map(Into::into))
+    }
+
+    async fn list_by_user(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        filter: &OrderFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Order>, RepoError> {
+        let after_created_at = page.after.map(|c| c.created_at);
+        let after_id = page.after.map(|c| c.id);
+
+        // Rows are located through idx_orders_user_flash_sale (user_id, flash_sale_id);
+        // a single user's orders are few enough to sort after the lookup
+        let records = match page.order {
+            SortOrder::Asc => {
+                sqlx::query_as!(
+                    OrderRecord,
+                    r#"
+                    SELECT id, user_id, flash_sale_id, quantity, status as "status: OrderStatus", idempotency_key, request_fingerprint, api_client_id, created_at
+                    FROM orders
+                    WHERE user_id = $1
+                      AND ($2::UUID IS NULL OR flash_sale_id = $2)
+                      AND ($3::order_status IS NULL OR status = $3)
+                      AND ($4::UUID IS NULL OR api_client_id = $4)
+                      AND ($5::TIMESTAMPTZ IS NULL OR (created_at, id) > ($5, $6::UUID))
+                    ORDER BY created_at, id
+                    LIMIT $7
+                    "#,
+                    user_id,
+                    filter.flash_sale_id,
+                    filter.status as Option<OrderStatus>,
+                    filter.api_client_id,
+                    after_created_at,
+                    after_id,
+                    page.fetch_limit(),
+                )
+                .fetch_all(conn)
+                .await
+            }
+            SortOrder::Desc => {
+                sqlx::query_as!(
+                    OrderRecord,
+                    r#"
+                    SELECT id, user_id, flash_sale_id, quantity, status as "status: OrderStatus", idempotency_key, request_fingerprint, api_client_id, created_at
+                    FROM orders
+                    WHERE user_id = $1
+                      AND ($2::UUID IS NULL OR flash_sale_id = $2)
+                      AND ($3::order_status IS NULL OR status = $3)
+                      AND ($4::UUID IS NULL OR api_client_id = $4)
+                      AND ($5::TIMESTAMPTZ IS NULL OR (created_at, id) < ($5, $6::UUID))
+                    ORDER BY created_at DESC, id DESC
+                    LIMIT $7
+                    "#,
+                    user_id,
+                    filter.flash_sale_id,
+                    filter.status as Option<OrderStatus>,
+                    filter.api_client_id,
+                    after_created_at,
+                    after_id,
+                    page.fetch_limit(),
+                )
+                .fetch_all(conn)
+                .await
+            }
+        }
+        .map_err(|e| map_sqlx_error(e, "list_orders_by_user", "order"))?;
+
+        let orders = records.into_iter().map(Order::from).collect();
+
+        Ok(Page::from_rows(orders, page, |order: &Order| Cursor {
+            created_at: order.created_at,
+            id: order.id,
+        }))
+    }
+
+    async fn count_by_user_since(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<i64, RepoError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM orders
+            WHERE user_id = $1 AND created_at >= $2 AND status <> 'FAILED'
+            "#,
+            user_id,
+            since
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "count_orders_by_user_since", "order"))
+    }
+
+    async fn lock_user_orders(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+    ) -> Result<(), RepoError> {
+        // A transaction level advisory lock, users may not have a row to lock
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock(hashtextextended($1::TEXT, 0))",
+            user_id.to_string()
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "lock_user_orders", "order"))?;
+
+        Ok(())
+    }
 }
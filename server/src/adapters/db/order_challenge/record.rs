@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::domain::order_challenge::ChallengeDifficulty;
+
+#[derive(Debug, FromRow)]
+pub struct ChallengeDifficultyRecord {
+    pub difficulty: i32,
+    pub window_started: DateTime<Utc>,
+    pub overflows: i64,
+}
+
+impl From<ChallengeDifficultyRecord> for ChallengeDifficulty {
+    fn from(value: ChallengeDifficultyRecord) -> Self {
+        Self {
+            difficulty: value.difficulty.max(1) as u32,
+            window_started: value.window_started,
+            overflows: value.overflows.max(0) as u64,
+        }
+    }
+}
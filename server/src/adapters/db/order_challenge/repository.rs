@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    adapters::db::{error_mapper::map_sqlx_error, order_challenge::ChallengeDifficultyRecord},
+    domain::order_challenge::ChallengeDifficulty,
+    errors::RepoError,
+    ports::OrderChallengeRepo,
+};
+
+#[derive(Default)]
+pub struct PostgresOrderChallengeRepo;
+
+impl PostgresOrderChallengeRepo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl OrderChallengeRepo for PostgresOrderChallengeRepo {
+    async fn use_nonce(
+        &self,
+        conn: &mut PgConnection,
+        nonce: &str,
+        order_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, RepoError> {
+        // The no-op update returns the row only when this order already used
+        // the nonce, and waits for a concurrent insert of it to settle
+        let used = sqlx::query_scalar!(
+            r#"
+            INSERT INTO used_order_challenges AS c (nonce, order_id, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (nonce) DO UPDATE SET order_id = c.order_id
+            WHERE c.order_id = EXCLUDED.order_id
+            RETURNING order_id
+            "#,
+            nonce,
+            order_id,
+            expires_at
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "use_order_challenge", "order_challenge"))?;
+
+        Ok(used.is_some())
+    }
+
+    async fn delete_expired(&self, conn: &mut PgConnection) -> Result<u64, RepoError> {
+        let result = sqlx::query!("DELETE FROM used_order_challenges WHERE expires_at < now()")
+            .execute(conn)
+            .await
+            .map_err(|e| map_sqlx_error(e, "delete_expired_order_challenges", "order_challenge"))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn lock_difficulty(
+        &self,
+        conn: &mut PgConnection,
+        base_difficulty: u32,
+    ) -> Result<ChallengeDifficulty, RepoError> {
+        // The conflict update takes the row lock, like SELECT ... FOR UPDATE
+        let record = sqlx::query_as!(
+            ChallengeDifficultyRecord,
+            r#"
+            INSERT INTO order_challenge_difficulty AS d (id, difficulty)
+            VALUES (TRUE, $1)
+            ON CONFLICT (id) DO UPDATE SET id = d.id
+            RETURNING difficulty, window_started, overflows
+            "#,
+            base_difficulty as i32
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "lock_challenge_difficulty", "order_challenge"))?;
+
+        Ok(record.into())
+    }
+
+    async fn save_difficulty(
+        &self,
+        conn: &mut PgConnection,
+        difficulty: &ChallengeDifficulty,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"
+            UPDATE order_challenge_difficulty
+            SET difficulty = $1, window_started = $2, overflows = $3
+            WHERE id
+            "#,
+            difficulty.difficulty as i32,
+            difficulty.window_started,
+            difficulty.overflows.min(i64::MAX as u64) as i64
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "save_challenge_difficulty", "order_challenge"))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,10 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, FromRow)]
+pub struct BlockedUserRecord {
+    pub user_id: Uuid,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
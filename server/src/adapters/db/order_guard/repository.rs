@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    adapters::db::{error_mapper::map_sqlx_error, order_guard::BlockedUserRecord},
+    domain::order_guard::BlockedUser,
+    errors::RepoError,
+    ports::OrderGuardRepo,
+};
+
+#[derive(Default)]
+pub struct PostgresOrderGuardRepo;
+
+impl PostgresOrderGuardRepo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl OrderGuardRepo for PostgresOrderGuardRepo {
+    async fn block_user(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<BlockedUser, RepoError> {
+        let record = sqlx::query_as!(
+            BlockedUserRecord,
+            r#"
+            INSERT INTO blocked_users (user_id, reason)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET reason = EXCLUDED.reason
+            RETURNING user_id, reason, created_at
+            "#,
+            user_id,
+            reason
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "block_user", "user"))?;
+
+        Ok(record.into())
+    }
+
+    async fn unblock_user(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError> {
+        let result = sqlx::query!("DELETE FROM blocked_users WHERE user_id = $1", user_id)
+            .execute(conn)
+            .await
+            .map_err(|e| map_sqlx_error(e, "unblock_user", "user"))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn is_blocked(&self, conn: &mut PgConnection, user_id: Uuid) -> Result<bool, RepoError> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM blocked_users WHERE user_id = $1) AS "blocked!""#,
+            user_id
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "is_user_blocked", "user"))
+    }
+
+    async fn allow_users(
+        &self,
+        conn: &mut PgConnection,
+        flash_sale_id: Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<u64, RepoError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO flash_sale_allowlist (flash_sale_id, user_id)
+            SELECT $1, user_id FROM UNNEST($2::UUID[]) AS u (user_id)
+            ON CONFLICT DO NOTHING
+            "#,
+            flash_sale_id,
+            user_ids
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "allow_users", "flash_sale_allowlist"))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn disallow_user(
+        &self,
+        conn: &mut PgConnection,
+        flash_sale_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError> {
+        let result = sqlx::query!(
+            "DELETE FROM flash_sale_allowlist WHERE flash_sale_id = $1 AND user_id = $2",
+            flash_sale_id,
+            user_id
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "disallow_user", "flash_sale_allowlist"))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn is_allowed(
+        &self,
+        conn: &mut PgConnection,
+        flash_sale_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM flash_sale_allowlist WHERE flash_sale_id = $1 AND user_id = $2
+            ) AS "allowed!"
+            "#,
+            flash_sale_id,
+            user_id
+        )
+        .fetch_one(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "is_user_allowed", "flash_sale_allowlist"))
+    }
+}
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, FromRow)]
+pub struct OrderJobRecord {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub flash_sale_id: Uuid,
+    pub quantity: i32,
+    pub idempotency_key: String,
+    pub request_fingerprint: String,
+    pub api_client_id: Option<Uuid>,
+    pub status: String,
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
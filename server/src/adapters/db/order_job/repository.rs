@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    adapters::db::{error_mapper::map_sqlx_error, order_job::OrderJobRecord},
+    domain::order_job::{OrderJob, OrderJobErrorCode, OrderJobStatus, OrderJobTurn},
+    errors::RepoError,
+    logic::order_logic::CreateOrderCommand,
+    ports::OrderJobRepo,
+};
+
+#[derive(Default)]
+pub struct PostgresOrderJobRepo;
+
+impl PostgresOrderJobRepo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn to_domain(record: OrderJobRecord) -> Result<OrderJob, RepoError> {
+    OrderJob::try_from(record).map_err(|e| RepoError::Database {
+        operation: "convert_order_job_record",
+        source: sqlx::Error::Decode(Box::new(e)),
+    })
+}
+
+#[async_trait]
+impl OrderJobRepo for PostgresOrderJobRepo {
+    async fn enqueue(
+        &self,
+        conn: &mut PgConnection,
+        command: &CreateOrderCommand,
+        turn: OrderJobTurn,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO order_jobs (id, user_id, flash_sale_id, quantity, idempotency_key,
+                                    request_fingerprint, api_client_id, status, lane, turn)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9,
+                    GREATEST(
+                        (SELECT MAX(turn) FROM order_jobs
+                         WHERE flash_sale_id = $3 AND lane = $9 AND status = 'pending'),
+                        (SELECT MIN(turn) FROM order_jobs WHERE status = 'pending'),
+                        0
+                    ) + 1.0 / $10::FLOAT8)
+            ON CONFLICT (id) DO UPDATE
+            SET status = EXCLUDED.status, error = NULL, error_code = NULL,
+                lane = EXCLUDED.lane, turn = EXCLUDED.turn, updated_at = NOW()
+            WHERE order_jobs.status = 'failed'
+            "#,
+            command.order_id,
+            command.user_id,
+            command.flash_sale_id,
+            command.quantity,
+            command.idempotency_key,
+            command.request_fingerprint,
+            command.api_client_id,
+            OrderJobStatus::Pending.as_str(),
+            turn.lane,
+            f64::from(turn.weight.max(1))
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "enqueue_order_job", "order_job"))?;
+
+        Ok(())
+    }
+
+    async fn claim_next(&self, conn: &mut PgConnection) -> Result<Option<OrderJob>, RepoError> {
+        let record = sqlx::query_as!(
+            OrderJobRecord,
+            r#"
+            SELECT id, user_id, flash_sale_id, quantity, idempotency_key, request_fingerprint,
+                   api_client_id, status, error, error_code, created_at, updated_at
+            FROM order_jobs
+            WHERE status = 'pending'
+            ORDER BY turn, created_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "claim_order_job", "order_job"))?;
+
+        record.map(to_domain).transpose()
+    }
+
+    async fn complete(&self, conn: &mut PgConnection, id: Uuid) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"
+            UPDATE order_jobs
+            SET status = $2, error = NULL, error_code = NULL, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            id,
+            OrderJobStatus::Completed.as_str()
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "complete_order_job", "order_job"))?;
+
+        Ok(())
+    }
+
+    async fn fail(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        error: &str,
+        error_code: Option<OrderJobErrorCode>,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"
+            UPDATE order_jobs
+            SET status = $2, error = $3, error_code = $4, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            id,
+            OrderJobStatus::Failed.as_str(),
+            error,
+            error_code.map(|code| code.as_str())
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "fail_order_job", "order_job"))?;
+
+        Ok(())
+    }
+
+    async fn find_finished(
+        &self,
+        conn: &mut PgConnection,
+        ids: &[Uuid],
+    ) -> Result<Vec<OrderJob>, RepoError> {
+        let records = sqlx::query_as!(
+            OrderJobRecord,
+            r#"
+            SELECT id, user_id, flash_sale_id, quantity, idempotency_key, request_fingerprint,
+                   api_client_id, status, error, error_code, created_at, updated_at
+            FROM order_jobs
+            WHERE id = ANY($1) AND status <> 'pending'
+            "#,
+            ids
+        )
+        .fetch_all(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "find_finished_order_jobs", "order_job"))?;
+
+        records.into_iter().map(to_domain).collect()
+    }
+}
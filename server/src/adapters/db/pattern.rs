@@ -0,0 +1,32 @@
+/// `LIKE` pattern matching values that start with `prefix`
+pub fn prefix_pattern(prefix: &str) -> String {
+    format!("{}%", escape_like(prefix))
+}
+
+/// `LIKE` pattern matching values that contain `needle`
+pub fn contains_pattern(needle: &str) -> String {
+    format!("%{}%", escape_like(needle))
+}
+
+/// Escapes the wildcard characters so user input is matched literally
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_in_user_input_match_literally() {
+        assert_eq!(prefix_pattern("50%_off\\"), "50\\%\\_off\\\\%");
+        assert_eq!(contains_pattern("a_b"), "%a\\_b%");
+    }
+}
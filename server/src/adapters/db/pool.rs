@@ -1,14 +1,22 @@
+use std::time::Duration;
+
 use sqlx::{PgPool, postgres::PgPoolOptions};
 
-use crate::app::config::Config;
+use crate::app::config::DatabaseConfig;
 
-pub async fn create_pool(cfg: &Config) -> Result<PgPool, sqlx::Error> {
+pub async fn create_pool(cfg: &DatabaseConfig) -> Result<PgPool, sqlx::Error> {
     let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&cfg.database_url)
+        .max_connections(cfg.max_connections)
+        .min_connections(cfg.min_connections)
+        .acquire_timeout(Duration::from_millis(cfg.acquire_timeout_ms))
+        .idle_timeout(Duration::from_secs(cfg.idle_timeout_secs))
+        .connect(&cfg.url)
         .await?;
 
-    tracing::info!("Connection pool created with max_connections: 10");
+    tracing::info!(
+        "Connection pool created with max_connections: {}",
+        cfg.max_connections
+    );
 
     Ok(pool)
 }
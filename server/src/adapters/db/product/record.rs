@@ -8,7 +8,11 @@ use crate::domain::Product;
 pub struct ProductRecord {
     pub id: Uuid,
     pub name: String,
+    pub description: Option<String>,
+    pub sku: Option<String>,
+    pub image_urls: Vec<String>,
     pub created_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
 }
 
 impl From<Product> for ProductRecord {
@@ -16,7 +20,17 @@ impl From<Product> for ProductRecord {
         Self {
             id: value.id,
             name: value.name.as_str().to_owned(),
+            description: value
+                .description
+                .map(|description| description.as_str().to_owned()),
+            sku: value.sku.map(|sku| sku.as_str().to_owned()),
+            image_urls: value
+                .image_urls
+                .iter()
+                .map(|url| url.as_str().to_owned())
+                .collect(),
             created_at: value.created_at,
+            archived_at: value.archived_at,
         }
     }
 }
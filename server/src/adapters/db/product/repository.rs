@@ -1,13 +1,22 @@
 use async_trait::async_trait;
 use sqlx::PgConnection;
+use uuid::Uuid;
 
 use crate::{
-    adapters::db::{error_mapper::map_sqlx_error, product::ProductRecord},
-    domain::product::Product,
+    adapters::db::{
+        error_mapper::map_sqlx_error,
+        pattern::{contains_pattern, prefix_pattern},
+        product::ProductRecord,
+    },
+    domain::{
+        pagination::{Cursor, Page, PageRequest, SortOrder},
+        product::{Product, ProductFilter},
+    },
     errors::RepoError,
     ports::ProductRepo,
 };
 
+#[derive(Default)]
 pub struct PostgresProductRepo;
 
 impl PostgresProductRepo {
@@ -16,6 +25,13 @@ impl PostgresProductRepo {
     }
 }
 
+fn to_product(record: ProductRecord) -> Result<Product, RepoError> {
+    Product::try_from(record).map_err(|e| RepoError::Database {
+        operation: "convert_product_record",
+        source: sqlx::Error::Decode(Box::new(e)),
+    })
+}
+
 #[async_trait]
 impl ProductRepo for PostgresProductRepo {
     async fn save(&self, conn: &mut PgConnection, product: Product) -> Result<Product, RepoError> {
@@ -24,68 +40,241 @@ impl ProductRepo for PostgresProductRepo {
         let saved_record = sqlx::query_as!(
             ProductRecord,
             r#"
-            INSERT INTO products (name)
-            VALUES ($1)
-            RETURNING id, name, created_at
+            INSERT INTO products (id, name, description, sku, image_urls)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, description, sku, image_urls, created_at, archived_at
             "#,
+            record.id,
             record.name,
+            record.description,
+            record.sku,
+            &record.image_urls,
         )
         .fetch_one(conn)
         .await
         .map_err(|e| map_sqlx_error(e, "save_product", "product"))?;
 
-        Product::try_from(saved_record).map_err(|e| RepoError::Database {
-            operation: "convert_product_record",
-            source: sqlx::Error::Decode(Box::new(e)),
-        })
+        to_product(saved_record)
     }
 
-    async fn get_all(&self, conn: &mut PgConnection) -> Result<Vec<Product>, RepoError> {
-        let records = sqlx::query_as!(
+    async fn list(
+        &self,
+        conn: &mut PgConnection,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, RepoError> {
+        let name_pattern = filter.name_prefix.as_deref().map(prefix_pattern);
+        let after_created_at = page.after.map(|c| c.created_at);
+        let after_id = page.after.map(|c| c.id);
+
+        // Separate statements per direction so both can walk the (created_at, id) index
+        let records = match page.order {
+            SortOrder::Asc => {
+                sqlx::query_as!(
+                    ProductRecord,
+                    r#"
+                    SELECT id, name, description, sku, image_urls, created_at, archived_at
+                    FROM products
+                    WHERE archived_at IS NULL
+                      AND ($1::TEXT IS NULL OR name ILIKE $1)
+                      AND ($2::TIMESTAMPTZ IS NULL OR created_at > $2)
+                      AND ($3::TIMESTAMPTZ IS NULL OR (created_at, id) > ($3, $4::UUID))
+                    ORDER BY created_at, id
+                    LIMIT $5
+                    "#,
+                    name_pattern,
+                    filter.created_after,
+                    after_created_at,
+                    after_id,
+                    page.fetch_limit(),
+                )
+                .fetch_all(conn)
+                .await
+            }
+            SortOrder::Desc => {
+                sqlx::query_as!(
+                    ProductRecord,
+                    r#"
+                    SELECT id, name, description, sku, image_urls, created_at, archived_at
+                    FROM products
+                    WHERE archived_at IS NULL
+                      AND ($1::TEXT IS NULL OR name ILIKE $1)
+                      AND ($2::TIMESTAMPTZ IS NULL OR created_at > $2)
+                      AND ($3::TIMESTAMPTZ IS NULL OR (created_at, id) < ($3, $4::UUID))
+                    ORDER BY created_at DESC, id DESC
+                    LIMIT $5
+                    "#,
+                    name_pattern,
+                    filter.created_after,
+                    after_created_at,
+                    after_id,
+                    page.fetch_limit(),
+                )
+                .fetch_all(conn)
+                .await
+            }
+        }
+        .map_err(|e| map_sqlx_error(e, "list_products", "product"))?;
+
+        let products = records
+            .into_iter()
+            .map(to_product)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Page::from_rows(products, page, |product| Cursor {
+            created_at: product.created_at,
+            id: product.id,
+        }))
+    }
+
+    async fn find_by_id(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<Product>, RepoError> {
+        let record = sqlx::query_as!(
             ProductRecord,
             r#"
-            SELECT id, name, created_at
+            SELECT id, name, description, sku, image_urls, created_at, archived_at
             FROM products
-            "#
+            WHERE id = $1
+            "#,
+            id
         )
-        .fetch_all(conn)
+        .fetch_optional(conn)
         .await
-        .map_err(|e| map_sqlx_error(e, "get_all_products", "product"))?;
+        .map_err(|e| map_sqlx_error(e, "find_product_by_id", "product"))?;
 
-        records
-            .into_iter()
-            .map(Product::try_from)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| RepoError::Database {
-                operation: "convert_product_records",
-                source: sqlx::Error::Decode(Box::new(e)),
-            })
+        record.map(to_product).transpose()
     }
 
-    async fn find_by_id(
+    async fn find_by_id_with_lock(
         &self,
         conn: &mut PgConnection,
-        id: uuid::Uuid,
+        id: Uuid,
     ) -> Result<Option<Product>, RepoError> {
         let record = sqlx::query_as!(
             ProductRecord,
             r#"
-            SELECT id, name, created_at
+            SELECT id, name, description, sku, image_urls, created_at, archived_at
             FROM products
             WHERE id = $1
+            FOR SHARE
             "#,
             id
         )
         .fetch_optional(conn)
         .await
-        .map_err(|e| map_sqlx_error(e, "find_product_by_id", "product"))?;
+        .map_err(|e| map_sqlx_error(e, "find_product_with_lock", "product"))?;
+
+        record.map(to_product).transpose()
+    }
+
+    async fn find_by_id_for_update(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<Product>, RepoError> {
+        let record = sqlx::query_as!(
+            ProductRecord,
+            r#"
+            SELECT id, name, description, sku, image_urls, created_at, archived_at
+            FROM products
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "find_product_for_update", "product"))?;
+
+        record.map(to_product).transpose()
+    }
+
+    async fn update(
+        &self,
+        conn: &mut PgConnection,
+        product: Product,
+    ) -> Result<Product, RepoError> {
+        let record = ProductRecord::from(product);
+
+        let saved_record = sqlx::query_as!(
+            ProductRecord,
+            r#"
+            UPDATE products
+            SET name = $2, description = $3, sku = $4, image_urls = $5
+            WHERE id = $1
+            RETURNING id, name, description, sku, image_urls, created_at, archived_at
+            "#,
+            record.id,
+            record.name,
+            record.description,
+            record.sku,
+            &record.image_urls,
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "update_product", "product"))?;
+
+        match saved_record {
+            Some(r) => to_product(r),
+            None => Err(RepoError::NotFound {
+                entity_type: "product",
+            }),
+        }
+    }
+
+    async fn archive(&self, conn: &mut PgConnection, id: Uuid) -> Result<Product, RepoError> {
+        let saved_record = sqlx::query_as!(
+            ProductRecord,
+            r#"
+            UPDATE products
+            SET archived_at = COALESCE(archived_at, now())
+            WHERE id = $1
+            RETURNING id, name, description, sku, image_urls, created_at, archived_at
+            "#,
+            id
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "archive_product", "product"))?;
+
+        match saved_record {
+            Some(r) => to_product(r),
+            None => Err(RepoError::NotFound {
+                entity_type: "product",
+            }),
+        }
+    }
+
+    async fn search(
+        &self,
+        conn: &mut PgConnection,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<Product>, RepoError> {
+        // `<%` matches names containing a word close to the query, which
+        // tolerates misspellings; ILIKE catches exact substrings too short to
+        // be similar. The trigram index serves both
+        let records = sqlx::query_as!(
+            ProductRecord,
+            r#"
+            SELECT id, name, description, sku, image_urls, created_at, archived_at
+            FROM products
+            WHERE archived_at IS NULL
+              AND ($1 <% name OR name ILIKE $2)
+            ORDER BY word_similarity($1, name) DESC, created_at, id
+            LIMIT $3
+            "#,
+            query,
+            contains_pattern(query),
+            limit
+        )
+        .fetch_all(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "search_products", "product"))?;
 
-        record
-            .map(Product::try_from)
-            .transpose()
-            .map_err(|e| RepoError::Database {
-                operation: "convert_product_record",
-                source: sqlx::Error::Decode(Box::new(e)),
-            })
+        records.into_iter().map(to_product).collect()
     }
 }
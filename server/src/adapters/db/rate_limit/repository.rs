@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use std::time::Duration;
+
+use crate::{
+    adapters::db::error_mapper::map_sqlx_error, domain::rate_limit::GcraBucket, errors::RepoError,
+    ports::RateLimitRepo,
+};
+
+#[derive(Default)]
+pub struct PostgresRateLimitRepo;
+
+impl PostgresRateLimitRepo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl RateLimitRepo for PostgresRateLimitRepo {
+    async fn acquire(
+        &self,
+        conn: &mut PgConnection,
+        key: &str,
+        emission_interval: Duration,
+        window: Duration,
+    ) -> Result<GcraBucket, RepoError> {
+        // The conflict update locks the row, so concurrent instances see each
+        // other's requests; a refused request leaves the bucket untouched
+        let allowed = sqlx::query_scalar!(
+            r#"
+            INSERT INTO rate_limit_buckets AS b (key, tat)
+            VALUES ($1, now() + make_interval(secs => $2))
+            ON CONFLICT (key) DO UPDATE
+            SET tat = GREATEST(b.tat, now()) + make_interval(secs => $2)
+            WHERE GREATEST(b.tat, now()) + make_interval(secs => $2)
+                <= now() + make_interval(secs => $3)
+            RETURNING EXTRACT(EPOCH FROM (tat - now()))::FLOAT8 AS "ahead!"
+            "#,
+            key,
+            emission_interval.as_secs_f64(),
+            window.as_secs_f64()
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "acquire_rate_limit", "rate_limit_bucket"))?;
+
+        if let Some(ahead) = allowed {
+            return Ok(GcraBucket {
+                allowed: true,
+                ahead: Duration::from_secs_f64(ahead.max(0.0)),
+            });
+        }
+
+        let ahead = sqlx::query_scalar!(
+            r#"
+            SELECT EXTRACT(EPOCH FROM (tat - now()))::FLOAT8 AS "ahead!"
+            FROM rate_limit_buckets
+            WHERE key = $1
+            "#,
+            key
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "acquire_rate_limit", "rate_limit_bucket"))?;
+
+        Ok(GcraBucket {
+            allowed: false,
+            ahead: Duration::from_secs_f64(ahead.unwrap_or(0.0).max(0.0)),
+        })
+    }
+
+    async fn delete_expired(&self, conn: &mut PgConnection) -> Result<u64, RepoError> {
+        let result = sqlx::query!("DELETE FROM rate_limit_buckets WHERE tat < now()")
+            .execute(conn)
+            .await
+            .map_err(|e| map_sqlx_error(e, "delete_expired_rate_limits", "rate_limit_bucket"))?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::runtime_settings::SettingsChange;
+
+#[derive(Debug, FromRow)]
+pub struct SettingsChangeRecord {
+    pub id: Uuid,
+    pub source: String,
+    pub changed_by: Option<Uuid>,
+    pub previous: serde_json::Value,
+    pub current: serde_json::Value,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl TryFrom<&SettingsChange> for SettingsChangeRecord {
+    type Error = serde_json::Error;
+
+    fn try_from(value: &SettingsChange) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            source: value.source.as_str().to_owned(),
+            changed_by: value.changed_by,
+            previous: serde_json::to_value(&value.previous)?,
+            current: serde_json::to_value(&value.current)?,
+            changed_at: value.changed_at,
+        })
+    }
+}
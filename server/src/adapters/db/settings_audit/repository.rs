@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+
+use crate::{
+    adapters::db::{error_mapper::map_sqlx_error, settings_audit::SettingsChangeRecord},
+    domain::runtime_settings::SettingsChange,
+    errors::RepoError,
+    ports::SettingsAuditRepo,
+};
+
+#[derive(Default)]
+pub struct PostgresSettingsAuditRepo;
+
+impl PostgresSettingsAuditRepo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn to_domain(record: SettingsChangeRecord) -> Result<SettingsChange, RepoError> {
+    SettingsChange::try_from(record).map_err(|e| RepoError::Database {
+        operation: "convert_settings_change_record",
+        source: sqlx::Error::Decode(Box::new(e)),
+    })
+}
+
+#[async_trait]
+impl SettingsAuditRepo for PostgresSettingsAuditRepo {
+    async fn record(
+        &self,
+        conn: &mut PgConnection,
+        change: &SettingsChange,
+    ) -> Result<(), RepoError> {
+        let record = SettingsChangeRecord::try_from(change).map_err(|e| RepoError::Database {
+            operation: "encode_settings_change_record",
+            source: sqlx::Error::Encode(Box::new(e)),
+        })?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO runtime_settings_audit (id, source, changed_by, previous, current, changed_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            record.id,
+            record.source,
+            record.changed_by,
+            record.previous,
+            record.current,
+            record.changed_at
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "record_settings_change", "settings_change"))?;
+
+        Ok(())
+    }
+
+    async fn list_recent(
+        &self,
+        conn: &mut PgConnection,
+        limit: i64,
+    ) -> Result<Vec<SettingsChange>, RepoError> {
+        let records = sqlx::query_as!(
+            SettingsChangeRecord,
+            r#"
+            SELECT id, source, changed_by, previous, current, changed_at
+            FROM runtime_settings_audit
+            ORDER BY changed_at DESC, id
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "list_settings_changes", "settings_change"))?;
+
+        records.into_iter().map(to_domain).collect()
+    }
+}
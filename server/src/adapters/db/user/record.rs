@@ -2,8 +2,23 @@ use chrono::{DateTime, Utc};
 use sqlx::prelude::FromRow;
 use uuid::Uuid;
 
+use crate::domain::User;
+
 #[derive(Debug, FromRow)]
 pub struct UserRecord {
     pub id: Uuid,
+    pub email: Option<String>,
+    pub username: Option<String>,
     pub created_at: DateTime<Utc>,
 }
+
+impl From<User> for UserRecord {
+    fn from(value: User) -> Self {
+        Self {
+            id: value.id,
+            email: value.email.map(|email| email.as_str().to_owned()),
+            username: value.username.map(|username| username.as_str().to_owned()),
+            created_at: value.created_at,
+        }
+    }
+}
@@ -4,11 +4,15 @@ use uuid::Uuid;
 
 use crate::{
     adapters::db::{error_mapper::map_sqlx_error, user::UserRecord},
-    domain::user::User,
+    domain::{
+        pagination::{Cursor, Page, PageRequest, SortOrder},
+        user::{Email, User, UserFilter},
+    },
     errors::RepoError,
     ports::UserRepo,
 };
 
+#[derive(Default)]
 pub struct PostgresUserRepo;
 
 impl PostgresUserRepo {
@@ -17,57 +21,185 @@ impl PostgresUserRepo {
     }
 }
 
+fn to_user(record: UserRecord) -> Result<User, RepoError> {
+    User::try_from(record).map_err(|e| RepoError::Database {
+        operation: "convert_user_record",
+        source: sqlx::Error::Decode(Box::new(e)),
+    })
+}
+
 #[async_trait]
 impl UserRepo for PostgresUserRepo {
     async fn save(&self, conn: &mut PgConnection, user: User) -> Result<User, RepoError> {
-        let record = sqlx::query_as!(
+        let record = UserRecord::from(user);
+
+        let saved_record = sqlx::query_as!(
             UserRecord,
             r#"
-            INSERT INTO users (id, created_at)
-            VALUES ($1, $2)
-            RETURNING *
+            INSERT INTO users (id, email, username)
+            VALUES ($1, $2, $3)
+            RETURNING id, email, username, created_at
             "#,
-            user.id,
-            user.created_at
+            record.id,
+            record.email,
+            record.username,
         )
         .fetch_one(conn)
         .await
         .map_err(|e| map_sqlx_error(e, "save_user", "user"))?;
 
-        Ok(User::from(record))
+        to_user(saved_record)
+    }
+
+    async fn list(
+        &self,
+        conn: &mut PgConnection,
+        filter: &UserFilter,
+        page: &PageRequest,
+    ) -> Result<Page<User>, RepoError> {
+        let after_created_at = page.after.map(|c| c.created_at);
+        let after_id = page.after.map(|c| c.id);
+
+        // Separate statements per direction so both can walk the (created_at, id) index
+        let rows = match page.order {
+            SortOrder::Asc => {
+                sqlx::query_as!(
+                    UserRecord,
+                    r#"
+                    SELECT id, email, username, created_at
+                    FROM users
+                    WHERE ($1::TIMESTAMPTZ IS NULL OR created_at > $1)
+                      AND ($2::TIMESTAMPTZ IS NULL OR (created_at, id) > ($2, $3::UUID))
+                    ORDER BY created_at, id
+                    LIMIT $4
+                    "#,
+                    filter.created_after,
+                    after_created_at,
+                    after_id,
+                    page.fetch_limit(),
+                )
+                .fetch_all(conn)
+                .await
+            }
+            SortOrder::Desc => {
+                sqlx::query_as!(
+                    UserRecord,
+                    r#"
+                    SELECT id, email, username, created_at
+                    FROM users
+                    WHERE ($1::TIMESTAMPTZ IS NULL OR created_at > $1)
+                      AND ($2::TIMESTAMPTZ IS NULL OR (created_at, id) < ($2, $3::UUID))
+                    ORDER BY created_at DESC, id DESC
+                    LIMIT $4
+                    "#,
+                    filter.created_after,
+                    after_created_at,
+                    after_id,
+                    page.fetch_limit(),
+                )
+                .fetch_all(conn)
+                .await
+            }
+        }
+        .map_err(|e| map_sqlx_error(e, "list_users", "user"))?;
+
+        let users = rows
+            .into_iter()
+            .map(to_user)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Page::from_rows(users, page, |user| Cursor {
+            created_at: user.created_at,
+            id: user.id,
+        }))
     }
 
-    async fn get_all(&self, conn: &mut PgConnection) -> Result<Vec<User>, RepoError> {
-        let rows = sqlx::query_as!(
+    async fn get_by_id(&self, conn: &mut PgConnection, id: Uuid) -> Result<User, RepoError> {
+        let row = sqlx::query_as!(
             UserRecord,
             r#"
-            SELECT id, created_at
+            SELECT id, email, username, created_at
             FROM users
-            "#
+            WHERE id = $1
+            "#,
+            id
         )
-        .fetch_all(conn)
+        .fetch_optional(conn)
         .await
-        .map_err(|e| map_sqlx_error(e, "get_all_users", "user"))?;
+        .map_err(|e| map_sqlx_error(e, "get_user_by_id", "user"))?;
 
-        Ok(rows.into_iter().map(|r| User::from(r)).collect())
+        match row {
+            Some(r) => to_user(r),
+            None => Err(RepoError::NotFound {
+                entity_type: "user",
+            }),
+        }
     }
 
-    async fn get_by_id(&self, conn: &mut PgConnection, id: Uuid) -> Result<User, RepoError> {
+    async fn find_by_id_for_update(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<User>, RepoError> {
         let row = sqlx::query_as!(
             UserRecord,
             r#"
-            SELECT id, created_at
+            SELECT id, email, username, created_at
             FROM users
             WHERE id = $1
+            FOR UPDATE
             "#,
             id
         )
         .fetch_optional(conn)
         .await
-        .map_err(|e| map_sqlx_error(e, "get_user_by_id", "user"))?;
+        .map_err(|e| map_sqlx_error(e, "find_user_for_update", "user"))?;
+
+        row.map(to_user).transpose()
+    }
+
+    async fn find_by_email(
+        &self,
+        conn: &mut PgConnection,
+        email: &Email,
+    ) -> Result<Option<User>, RepoError> {
+        let row = sqlx::query_as!(
+            UserRecord,
+            r#"
+            SELECT id, email, username, created_at
+            FROM users
+            WHERE email = $1
+            "#,
+            email.as_str()
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "find_user_by_email", "user"))?;
+
+        row.map(to_user).transpose()
+    }
+
+    async fn update(&self, conn: &mut PgConnection, user: User) -> Result<User, RepoError> {
+        let record = UserRecord::from(user);
+
+        let row = sqlx::query_as!(
+            UserRecord,
+            r#"
+            UPDATE users
+            SET email = $2, username = $3
+            WHERE id = $1
+            RETURNING id, email, username, created_at
+            "#,
+            record.id,
+            record.email,
+            record.username,
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "update_user", "user"))?;
 
         match row {
-            Some(r) => Ok(User::from(r)),
+            Some(r) => to_user(r),
             None => Err(RepoError::NotFound {
                 entity_type: "user",
             }),
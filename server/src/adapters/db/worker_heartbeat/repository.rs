@@ -0,0 +1,64 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    adapters::db::error_mapper::map_sqlx_error, errors::RepoError, ports::WorkerHeartbeatRepo,
+};
+
+#[derive(Default)]
+pub struct PostgresWorkerHeartbeatRepo;
+
+impl PostgresWorkerHeartbeatRepo {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl WorkerHeartbeatRepo for PostgresWorkerHeartbeatRepo {
+    async fn beat(
+        &self,
+        conn: &mut PgConnection,
+        worker_id: Uuid,
+        started_at: DateTime<Utc>,
+        seen_at: DateTime<Utc>,
+    ) -> Result<(), RepoError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO worker_heartbeats (worker_id, started_at, last_seen_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (worker_id) DO UPDATE
+            SET last_seen_at = EXCLUDED.last_seen_at
+            "#,
+            worker_id,
+            started_at,
+            seen_at
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "record_worker_heartbeat", "worker_heartbeat"))?;
+
+        Ok(())
+    }
+
+    async fn latest(&self, conn: &mut PgConnection) -> Result<Option<DateTime<Utc>>, RepoError> {
+        sqlx::query_scalar!("SELECT MAX(last_seen_at) FROM worker_heartbeats")
+            .fetch_one(conn)
+            .await
+            .map_err(|e| map_sqlx_error(e, "latest_worker_heartbeat", "worker_heartbeat"))
+    }
+
+    async fn remove(&self, conn: &mut PgConnection, worker_id: Uuid) -> Result<(), RepoError> {
+        sqlx::query!(
+            "DELETE FROM worker_heartbeats WHERE worker_id = $1",
+            worker_id
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| map_sqlx_error(e, "remove_worker_heartbeat", "worker_heartbeat"))?;
+
+        Ok(())
+    }
+}
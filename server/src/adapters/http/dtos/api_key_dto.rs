@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{domain::api_key::ApiKey, logic::api_key_logic::IssuedApiKey};
+
+#[derive(Debug, Deserialize)]
+pub struct IssueApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub rate_limit_per_second: i64,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateApiKeyRequest {
+    /// How long the old key keeps working, defaults to one day
+    pub overlap_secs: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponse {
+    pub id: Uuid,
+    pub client_id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<&'static str>,
+    pub rate_limit_per_second: u32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            client_id: api_key.client_id,
+            name: api_key.name,
+            key_prefix: api_key.key_prefix,
+            scopes: api_key.scopes.iter().map(|s| s.as_str()).collect(),
+            rate_limit_per_second: api_key.rate_limit_per_second,
+            created_at: api_key.created_at,
+            expires_at: api_key.expires_at,
+            revoked_at: api_key.revoked_at,
+        }
+    }
+}
+
+/// Response for a newly issued key, the only time the plaintext is shown
+#[derive(Debug, Serialize)]
+pub struct IssuedApiKeyResponse {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+}
+
+impl From<IssuedApiKey> for IssuedApiKeyResponse {
+    fn from(issued: IssuedApiKey) -> Self {
+        Self {
+            key: issued.secret.as_str().to_owned(),
+            api_key: issued.api_key.into(),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    adapters::http::dtos::product_dto::ProductResponse,
+    app::order_challenge::Challenge,
+    domain::{
+        flash_sale::{
+            AdmissionBudget, FlashSaleDetails, FlashSalePhase, FlashSaleStatusFilter, StockDisplay,
+            StockLevel,
+        },
+        order_guard::{OrderGuardSettings, VelocityLimit},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateFlashSaleRequest {
+    pub product_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub total_inventory: i32,
+    pub per_user_limit: i32,
+    /// Omitted for the default budget
+    pub admission: Option<FlashSaleAdmissionRequest>,
+    /// Omitted for no guards
+    pub guards: Option<FlashSaleGuardsRequest>,
+}
+
+/// Admission budget of a sale, omitted fields take their defaults
+#[derive(Debug, Default, Deserialize)]
+pub struct FlashSaleAdmissionRequest {
+    /// Share of the order queue the sale may fill, 0 to 1
+    pub queue_share: Option<f64>,
+    /// Orders admitted per second across all users
+    pub rate_per_second: Option<i32>,
+    /// Orders the worker takes per turn when sales compete, default 1
+    pub scheduling_weight: Option<i32>,
+    /// Orders must carry a solved challenge, default false
+    pub challenge_required: Option<bool>,
+}
+
+/// Operator view of a sale's admission budget
+#[derive(Debug, Serialize)]
+pub struct FlashSaleAdmissionResponse {
+    pub queue_share: Option<f64>,
+    pub rate_per_second: Option<i32>,
+    pub scheduling_weight: i32,
+    pub challenge_required: bool,
+}
+
+impl From<AdmissionBudget> for FlashSaleAdmissionResponse {
+    fn from(value: AdmissionBudget) -> Self {
+        Self {
+            queue_share: value.queue_share,
+            rate_per_second: value.rate_per_second,
+            scheduling_weight: value.scheduling_weight,
+            challenge_required: value.challenge_required,
+        }
+    }
+}
+
+/// Order guards of a sale, omitted fields take their defaults
+#[derive(Debug, Default, Deserialize)]
+pub struct FlashSaleGuardsRequest {
+    /// Refuse blocklisted users, default false
+    pub blocklist: Option<bool>,
+    /// Youngest account that may order
+    pub min_account_age_secs: Option<i64>,
+    /// Cap on a user's orders across all sales
+    pub velocity: Option<VelocityLimit>,
+    /// Only users on the sale's allowlist may order, default false
+    pub allowlist_only: Option<bool>,
+}
+
+/// Operator view of a sale's order guards
+#[derive(Debug, Serialize)]
+pub struct FlashSaleGuardsResponse {
+    pub blocklist: bool,
+    pub min_account_age_secs: Option<i64>,
+    pub velocity: Option<VelocityLimit>,
+    pub allowlist_only: bool,
+}
+
+impl From<OrderGuardSettings> for FlashSaleGuardsResponse {
+    fn from(value: OrderGuardSettings) -> Self {
+        Self {
+            blocklist: value.blocklist,
+            min_account_age_secs: value.min_account_age_secs,
+            velocity: value.velocity,
+            allowlist_only: value.allowlist_only,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlashSaleListQuery {
+    pub status: FlashSaleStatusFilter,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StockResponse {
+    pub level: StockLevel,
+    /// Only present when the exact count is disclosed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct FlashSaleResponse {
+    pub id: Uuid,
+    pub status: FlashSalePhase,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub per_user_limit: i32,
+    pub stock: StockResponse,
+    pub product: ProductResponse,
+    /// Only shown to operators
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admission: Option<FlashSaleAdmissionResponse>,
+    /// Only shown to operators
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guards: Option<FlashSaleGuardsResponse>,
+}
+
+impl FlashSaleResponse {
+    /// `now` decides the sale status, so a cached sale still flips to live on time
+    pub fn new(details: FlashSaleDetails, now: DateTime<Utc>, display: StockDisplay) -> Self {
+        let flash_sale = details.flash_sale;
+
+        Self {
+            id: flash_sale.id,
+            status: flash_sale.phase_at(now),
+            start_time: flash_sale.start_time,
+            end_time: flash_sale.end_time,
+            per_user_limit: flash_sale.per_user_limit,
+            stock: StockResponse {
+                level: flash_sale.stock_level(),
+                remaining: match display {
+                    StockDisplay::Exact => Some(flash_sale.remaining_inventory.max(0)),
+                    StockDisplay::Bucketed => None,
+                },
+            },
+            product: details.product.into(),
+            admission: None,
+            guards: None,
+        }
+    }
+
+    /// Operator view, with the exact stock, the admission budget and the guards
+    pub fn for_operator(details: FlashSaleDetails, now: DateTime<Utc>) -> Self {
+        let admission = details.flash_sale.admission.into();
+        let guards = details.flash_sale.guards.into();
+
+        Self {
+            admission: Some(admission),
+            guards: Some(guards),
+            ..Self::new(details, now, StockDisplay::Exact)
+        }
+    }
+}
+
+/// Server time is included so clients can correct their countdown clocks
+#[derive(Serialize)]
+pub struct FlashSaleDetailResponse {
+    pub server_time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub flash_sale: FlashSaleResponse,
+}
+
+#[derive(Serialize)]
+pub struct FlashSaleListResponse {
+    pub server_time: DateTime<Utc>,
+    pub items: Vec<FlashSaleResponse>,
+}
+
+/// Proof-of-work challenge for one order of a sale
+///
+/// Solved by finding a `solution` such that
+/// `SHA-256("<challenge>:<user_id>:<solution>")` starts with `difficulty`
+/// zero bits; both are sent with the order as `X-Challenge` and
+/// `X-Challenge-Solution`
+#[derive(Debug, Serialize)]
+pub struct ChallengeResponse {
+    pub challenge: String,
+    pub algorithm: &'static str,
+    pub difficulty: u32,
+    pub expires_at: DateTime<Utc>,
+    /// Whether the sale currently requires a solution with orders
+    pub required: bool,
+}
+
+impl ChallengeResponse {
+    pub fn new(challenge: Challenge, required: bool) -> Self {
+        Self {
+            challenge: challenge.token,
+            algorithm: "sha256",
+            difficulty: challenge.difficulty,
+            expires_at: challenge.expires_at,
+            required,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::domain::health::{ComponentHealth, ReadinessReport};
+
+#[derive(Debug, Serialize)]
+pub struct LivenessResponse {
+    /// `ok` or `unresponsive`
+    pub status: &'static str,
+    pub uptime_secs: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentHealthResponse {
+    /// `up` or `down`
+    pub status: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<f64>,
+}
+
+impl From<ComponentHealth> for ComponentHealthResponse {
+    fn from(value: ComponentHealth) -> Self {
+        Self {
+            status: value.status.as_str(),
+            message: value.message,
+            value: value.value,
+            limit: value.limit,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    /// `ready` or `not_ready`
+    pub status: &'static str,
+    pub components: BTreeMap<&'static str, ComponentHealthResponse>,
+}
+
+impl From<ReadinessReport> for ReadinessResponse {
+    fn from(value: ReadinessReport) -> Self {
+        Self {
+            status: if value.is_ready() {
+                "ready"
+            } else {
+                "not_ready"
+            },
+            components: value
+                .components
+                .into_iter()
+                .map(|(name, health)| (name, health.into()))
+                .collect(),
+        }
+    }
+}
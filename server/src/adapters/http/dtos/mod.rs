@@ -1,5 +1,14 @@
+pub mod api_key_dto;
+pub mod flash_sale_dto;
+pub mod health_dto;
 pub mod order_dto;
+pub mod order_guard_dto;
+pub mod page_dto;
 pub mod product_dto;
+pub mod settings_dto;
 pub mod user_dto;
 
-pub use crate::adapters::http::dtos::{order_dto::*, product_dto::*, user_dto::*};
+pub use crate::adapters::http::dtos::{
+    api_key_dto::*, flash_sale_dto::*, health_dto::*, order_dto::*, order_guard_dto::*,
+    page_dto::*, product_dto::*, settings_dto::*, user_dto::*,
+};
@@ -1,11 +1,11 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::order::Order;
+use crate::domain::order::{Order, OrderFilter, OrderStatus, QueueEstimate};
 
 #[derive(Debug, Deserialize)]
 pub struct CreateOrderRequest {
-    pub user_id: Uuid,
     pub flash_sale_id: Uuid,
     pub quantity: i32,
 }
@@ -25,12 +25,75 @@ impl From<Order> for OrderResponse {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct OrderListQuery {
+    pub status: Option<OrderStatus>,
+    pub flash_sale_id: Option<Uuid>,
+}
+
+impl From<OrderListQuery> for OrderFilter {
+    fn from(value: OrderListQuery) -> Self {
+        Self {
+            status: value.status,
+            flash_sale_id: value.flash_sale_id,
+            api_client_id: None,
+        }
+    }
+}
+
+/// Persisted order as returned by `GET /orders/{id}` and order history
+#[derive(Debug, Serialize)]
+pub struct OrderDetailResponse {
+    pub order_id: Uuid,
+    pub user_id: Uuid,
+    pub flash_sale_id: Uuid,
+    pub quantity: i32,
+    pub status: OrderStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_client_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Order> for OrderDetailResponse {
+    fn from(order: Order) -> Self {
+        Self {
+            order_id: order.id,
+            user_id: order.user_id,
+            flash_sale_id: order.flash_sale_id,
+            quantity: order.quantity,
+            status: order.status,
+            api_client_id: order.api_client_id,
+            created_at: order.created_at,
+        }
+    }
+}
+
 /// Response when an order is accepted for async processing
 #[derive(Debug, Serialize)]
 pub struct OrderAcceptedResponse {
     pub order_id: Uuid,
     pub status: String,
     pub status_url: String,
+    #[serde(flatten)]
+    pub queue: Option<QueueEstimateResponse>,
+}
+
+/// Where a pending order stands in the queue, roughly
+#[derive(Debug, Serialize)]
+pub struct QueueEstimateResponse {
+    /// Orders ahead plus this one
+    pub queue_position: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_wait_ms: Option<u64>,
+}
+
+impl From<QueueEstimate> for QueueEstimateResponse {
+    fn from(estimate: QueueEstimate) -> Self {
+        Self {
+            queue_position: estimate.position,
+            estimated_wait_ms: estimate.estimated_wait.map(|wait| wait.as_millis() as u64),
+        }
+    }
 }
 
 /// Response for order status polling
@@ -40,6 +103,8 @@ pub struct OrderStatusResponse {
     pub status: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<OrderResult>,
+    #[serde(flatten)]
+    pub queue: Option<QueueEstimateResponse>,
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::order_guard::BlockedUser;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BlockUserRequest {
+    /// Note for other admins, never shown to the user
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlockedUserResponse {
+    pub user_id: Uuid,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<BlockedUser> for BlockedUserResponse {
+    fn from(value: BlockedUser) -> Self {
+        Self {
+            user_id: value.user_id,
+            reason: value.reason,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllowlistRequest {
+    pub user_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AllowlistResponse {
+    /// Users newly added, those already on the list are not counted
+    pub added: u64,
+}
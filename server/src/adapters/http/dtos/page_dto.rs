@@ -0,0 +1,20 @@
+use crate::{
+    domain::pagination::{PageRequest, SortOrder},
+    errors::AppError,
+};
+
+/// `?limit=&cursor=&order=asc|desc`, shared by every list endpoint
+#[derive(Debug, serde::Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+}
+
+impl TryFrom<PageQuery> for PageRequest {
+    type Error = AppError;
+
+    fn try_from(value: PageQuery) -> Result<Self, Self::Error> {
+        PageRequest::new(value.limit, value.cursor.as_deref(), value.order)
+    }
+}
@@ -1,17 +1,57 @@
 use chrono::{DateTime, Utc};
 
-use crate::domain::Product;
+use crate::domain::{Product, ProductFilter};
 
 #[derive(Debug, serde::Deserialize)]
 pub struct CreateProductRequest {
     pub name: String,
+    pub description: Option<String>,
+    pub sku: Option<String>,
+    #[serde(default)]
+    pub image_urls: Vec<String>,
+}
+
+/// Omitted fields are left unchanged, an empty `description` or `sku` clears it
+#[derive(Debug, serde::Deserialize)]
+pub struct UpdateProductRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub sku: Option<String>,
+    /// Replaces the whole list
+    pub image_urls: Option<Vec<String>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ProductListQuery {
+    pub name_prefix: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+}
+
+impl From<ProductListQuery> for ProductFilter {
+    fn from(value: ProductListQuery) -> Self {
+        Self {
+            name_prefix: value.name_prefix,
+            created_after: value.created_after,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ProductSearchQuery {
+    pub q: String,
+    pub limit: Option<u32>,
 }
 
 #[derive(serde::Serialize)]
 pub struct ProductResponse {
     pub id: String,
     pub name: String,
+    pub description: Option<String>,
+    pub sku: Option<String>,
+    pub image_urls: Vec<String>,
     pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
 }
 
 impl From<Product> for ProductResponse {
@@ -19,7 +59,17 @@ impl From<Product> for ProductResponse {
         Self {
             id: product.id.to_string(),
             name: product.name.as_str().to_owned(),
+            description: product
+                .description
+                .map(|description| description.as_str().to_owned()),
+            sku: product.sku.map(|sku| sku.as_str().to_owned()),
+            image_urls: product
+                .image_urls
+                .iter()
+                .map(|url| url.as_str().to_owned())
+                .collect(),
             created_at: product.created_at,
+            archived_at: product.archived_at,
         }
     }
 }
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::runtime_settings::{
+    RuntimeSettings, SettingsChange, SettingsChangeSource, SoldOutGate,
+};
+
+/// Partial update, omitted fields keep their current value
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateRuntimeSettingsRequest {
+    pub rate_limit_per_user: Option<u32>,
+    pub queue_admission_limit: Option<usize>,
+    pub log_level: Option<String>,
+    /// `reject` or `off`
+    pub sold_out_gate: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SettingsAuditQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuntimeSettingsResponse {
+    pub rate_limit_per_user: u32,
+    pub queue_admission_limit: usize,
+    /// Fixed at startup, the upper bound for `queue_admission_limit`
+    pub queue_capacity: usize,
+    pub log_level: String,
+    pub sold_out_gate: SoldOutGate,
+}
+
+impl RuntimeSettingsResponse {
+    pub fn new(settings: &RuntimeSettings, queue_capacity: usize) -> Self {
+        Self {
+            rate_limit_per_user: settings.rate_limit_per_user,
+            queue_admission_limit: settings.queue_admission_limit,
+            queue_capacity,
+            log_level: settings.log_level.clone(),
+            sold_out_gate: settings.sold_out_gate,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SettingsChangeResponse {
+    pub id: Uuid,
+    pub source: SettingsChangeSource,
+    pub changed_by: Option<Uuid>,
+    pub previous: RuntimeSettings,
+    pub current: RuntimeSettings,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl From<SettingsChange> for SettingsChangeResponse {
+    fn from(change: SettingsChange) -> Self {
+        Self {
+            id: change.id,
+            source: change.source,
+            changed_by: change.changed_by,
+            previous: change.previous,
+            current: change.current,
+            changed_at: change.changed_at,
+        }
+    }
+}
@@ -1,11 +1,44 @@
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::domain::User;
+use crate::domain::{User, UserFilter};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateUserRequest {
+    pub email: String,
+    pub username: String,
+}
+
+/// Omitted fields are left unchanged
+#[derive(Debug, serde::Deserialize)]
+pub struct UpdateUserRequest {
+    pub email: Option<String>,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct UserLookupQuery {
+    pub email: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct UserListQuery {
+    pub created_after: Option<DateTime<Utc>>,
+}
+
+impl From<UserListQuery> for UserFilter {
+    fn from(value: UserListQuery) -> Self {
+        Self {
+            created_after: value.created_after,
+        }
+    }
+}
 
 #[derive(serde::Serialize)]
 pub struct UserResponse {
     pub id: Uuid,
+    pub email: Option<String>,
+    pub username: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -13,6 +46,8 @@ impl From<User> for UserResponse {
     fn from(user: User) -> Self {
         Self {
             id: user.id,
+            email: user.email.map(|email| email.as_str().to_owned()),
+            username: user.username.map(|username| username.as_str().to_owned()),
             created_at: user.created_at,
         }
     }
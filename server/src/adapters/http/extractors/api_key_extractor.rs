@@ -0,0 +1,40 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+
+use crate::{
+    app::state::AppState,
+    domain::api_key::ApiKey,
+    errors::{ApiError, AppError, ServiceError},
+    logic::api_key_logic,
+};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Resolves a partner client from the `X-Api-Key` header
+impl FromRequestParts<AppState> for ApiKey {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let presented = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .ok_or(AppError::Service(ServiceError::Unauthenticated))?;
+
+        let mut conn = state
+            .db_pool
+            .acquire()
+            .await
+            .map_err(ApiError::connection_error)?;
+
+        api_key_logic::authenticate_api_key(&mut conn, &*state.api_key_repo, presented)
+            .await
+            .map_err(|e| {
+                metrics::counter!("api_key_authentication_failures_total").increment(1);
+                ApiError::from(e)
+            })
+    }
+}
@@ -0,0 +1,110 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts},
+};
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    adapters::http::extractors::api_key_extractor::API_KEY_HEADER,
+    app::state::AppState,
+    domain::{
+        api_key::ApiKey,
+        auth::{AuthenticatedUser, Caller},
+    },
+    errors::{ApiError, AppError, ServiceError},
+    logic::api_key_logic,
+    ports::TokenVerifier,
+};
+
+pub const ON_BEHALF_OF_HEADER: &str = "x-on-behalf-of";
+
+/// Resolves the caller from the `Authorization: Bearer <token>` header
+///
+/// Reuses the identity already verified by the authorization layer when present
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+    Arc<dyn TokenVerifier>: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(user) = parts.extensions.get::<AuthenticatedUser>() {
+            return Ok(user.clone());
+        }
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or(AppError::Service(ServiceError::Unauthenticated))?;
+
+        Arc::<dyn TokenVerifier>::from_ref(state)
+            .verify(token)
+            .map_err(|e| ApiError::from(AppError::Service(e)))
+    }
+}
+
+/// Resolves an end user from a bearer token, or a partner from `X-Api-Key`
+///
+/// Partners name the user they act for in `X-On-Behalf-Of`; a header that is
+/// present must hold the id of a user linked to the partner's client
+impl FromRequestParts<AppState> for Caller {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if !parts.headers.contains_key(API_KEY_HEADER) {
+            return AuthenticatedUser::from_request_parts(parts, state)
+                .await
+                .map(Caller::User);
+        }
+
+        let api_key = ApiKey::from_request_parts(parts, state).await?;
+
+        let on_behalf_of = parts
+            .headers
+            .get(ON_BEHALF_OF_HEADER)
+            .map(|value| {
+                value
+                    .to_str()
+                    .ok()
+                    .and_then(|value| Uuid::parse_str(value.trim()).ok())
+                    .ok_or_else(|| {
+                        ApiError::bad_request("X-On-Behalf-Of must be a valid user id".to_string())
+                    })
+            })
+            .transpose()?;
+
+        if let Some(user_id) = on_behalf_of {
+            let mut conn = state
+                .db_pool
+                .acquire()
+                .await
+                .map_err(ApiError::connection_error)?;
+
+            api_key_logic::ensure_partner_user(
+                &mut conn,
+                &*state.api_key_repo,
+                api_key.client_id,
+                user_id,
+            )
+            .await
+            .map_err(|e| {
+                metrics::counter!("partner_user_rejections_total").increment(1);
+                ApiError::from(e)
+            })?;
+        }
+
+        Ok(Caller::Partner {
+            api_key,
+            on_behalf_of,
+        })
+    }
+}
@@ -0,0 +1,2 @@
+pub mod api_key_extractor;
+pub mod auth_extractor;
@@ -0,0 +1,147 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    adapters::http::dtos::api_key_dto::{
+        ApiKeyResponse, IssueApiKeyRequest, IssuedApiKeyResponse, RotateApiKeyRequest,
+    },
+    app::state::AppState,
+    errors::ApiError,
+    logic::api_key_logic,
+};
+
+pub async fn issue_api_key(
+    State(state): State<AppState>,
+    Json(req): Json<IssueApiKeyRequest>,
+) -> Result<(StatusCode, Json<IssuedApiKeyResponse>), ApiError> {
+    let command = api_key_logic::IssueApiKeyCommand::try_from(req).map_err(ApiError::from)?;
+
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let issued = api_key_logic::issue_api_key(&mut conn, &*state.api_key_repo, command)
+        .await
+        .map_err(ApiError::from)?;
+
+    tracing::info!(api_key_id = %issued.api_key.id, client_id = %issued.api_key.client_id, "API key issued");
+
+    Ok((StatusCode::CREATED, Json(issued.into())))
+}
+
+pub async fn get_api_keys(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ApiKeyResponse>>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let api_keys = api_key_logic::get_api_keys(&mut conn, &*state.api_key_repo)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(api_keys.into_iter().map(Into::into).collect()))
+}
+
+pub async fn rotate_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    req: Option<Json<RotateApiKeyRequest>>,
+) -> Result<(StatusCode, Json<IssuedApiKeyResponse>), ApiError> {
+    let overlap_secs = req
+        .and_then(|Json(req)| req.overlap_secs)
+        .unwrap_or(api_key_logic::DEFAULT_ROTATION_OVERLAP_SECS);
+
+    if overlap_secs < 0 {
+        return Err(ApiError::bad_request(
+            "overlap_secs cannot be negative".to_string(),
+        ));
+    }
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(ApiError::transaction_error)?;
+
+    let issued = api_key_logic::rotate_api_key(
+        &mut tx,
+        &*state.api_key_repo,
+        id,
+        chrono::Duration::seconds(overlap_secs),
+    )
+    .await
+    .map_err(ApiError::from)?;
+
+    tx.commit().await.map_err(ApiError::transaction_error)?;
+
+    tracing::info!(old_api_key_id = %id, api_key_id = %issued.api_key.id, "API key rotated");
+
+    Ok((StatusCode::CREATED, Json(issued.into())))
+}
+
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiKeyResponse>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let api_key = api_key_logic::revoke_api_key(&mut conn, &*state.api_key_repo, id)
+        .await
+        .map_err(ApiError::from)?;
+
+    tracing::info!(api_key_id = %id, "API key revoked");
+
+    Ok(Json(api_key.into()))
+}
+
+/// Lets the client's keys place and read orders on behalf of the user
+pub async fn link_partner_user(
+    State(state): State<AppState>,
+    Path((client_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    api_key_logic::link_partner_user(&mut conn, &*state.api_key_repo, client_id, user_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    tracing::info!(client_id = %client_id, user_id = %user_id, "User linked to API client");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unlink_partner_user(
+    State(state): State<AppState>,
+    Path((client_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    api_key_logic::unlink_partner_user(&mut conn, &*state.api_key_repo, client_id, user_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    tracing::info!(client_id = %client_id, user_id = %user_id, "User unlinked from API client");
+
+    Ok(StatusCode::NO_CONTENT)
+}
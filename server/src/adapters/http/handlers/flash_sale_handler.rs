@@ -0,0 +1,222 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    adapters::http::dtos::flash_sale_dto::{
+        ChallengeResponse, CreateFlashSaleRequest, FlashSaleAdmissionRequest,
+        FlashSaleAdmissionResponse, FlashSaleDetailResponse, FlashSaleGuardsRequest,
+        FlashSaleGuardsResponse, FlashSaleListQuery, FlashSaleListResponse, FlashSaleResponse,
+    },
+    app::state::AppState,
+    domain::{
+        flash_sale::{AdmissionBudget, FlashSaleDetails},
+        order_guard::OrderGuardSettings,
+    },
+    errors::ApiError,
+    logic::flash_sale_logic,
+};
+
+/// Operators always see the exact stock and admission budget of the sale they created
+pub async fn create_flash_sale(
+    State(state): State<AppState>,
+    Json(req): Json<CreateFlashSaleRequest>,
+) -> Result<(StatusCode, Json<FlashSaleDetailResponse>), ApiError> {
+    let command =
+        flash_sale_logic::CreateFlashSaleCommand::try_from(req).map_err(ApiError::from)?;
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(ApiError::transaction_error)?;
+
+    let details = flash_sale_logic::create_flash_sale(
+        &mut tx,
+        &*state.flash_sale_repo,
+        &*state.product_repo,
+        command,
+    )
+    .await
+    .map_err(ApiError::from)?;
+
+    tx.commit().await.map_err(ApiError::transaction_error)?;
+
+    tracing::info!(
+        flash_sale_id = %details.flash_sale.id,
+        product_id = %details.product.id,
+        "Flash sale created"
+    );
+
+    let now = Utc::now();
+    Ok((
+        StatusCode::CREATED,
+        Json(FlashSaleDetailResponse {
+            server_time: now,
+            flash_sale: FlashSaleResponse::for_operator(details, now),
+        }),
+    ))
+}
+
+pub async fn get_flash_sale(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FlashSaleDetailResponse>, ApiError> {
+    let details = cached_flash_sale(&state, id).await?;
+
+    let now = Utc::now();
+    Ok(Json(FlashSaleDetailResponse {
+        server_time: now,
+        flash_sale: FlashSaleResponse::new(details, now, state.stock_display),
+    }))
+}
+
+/// Issues a challenge for one order of the sale
+///
+/// Issued for every sale, so clients can solve ahead of a sale switching
+/// challenges on
+pub async fn get_flash_sale_challenge(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ChallengeResponse>, ApiError> {
+    let details = cached_flash_sale(&state, id).await?;
+    let challenge = state.order_challenges.issue(id);
+    metrics::counter!("order_challenges_issued_total").increment(1);
+
+    Ok(Json(ChallengeResponse::new(
+        challenge,
+        details.flash_sale.admission.challenge_required,
+    )))
+}
+
+async fn cached_flash_sale(state: &AppState, id: Uuid) -> Result<FlashSaleDetails, ApiError> {
+    if let Some(details) = state.flash_sale_cache.get_sale(id) {
+        return Ok(details);
+    }
+
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let details = flash_sale_logic::get_flash_sale_details(&mut conn, &*state.flash_sale_repo, id)
+        .await
+        .map_err(ApiError::from)?;
+
+    state.flash_sale_cache.put_sale(details.clone());
+    Ok(details)
+}
+
+pub async fn get_flash_sales(
+    State(state): State<AppState>,
+    Query(query): Query<FlashSaleListQuery>,
+) -> Result<Json<FlashSaleListResponse>, ApiError> {
+    let sales = match state.flash_sale_cache.get_listing(query.status) {
+        Some(sales) => sales,
+        None => {
+            let mut conn = state
+                .db_pool
+                .acquire()
+                .await
+                .map_err(ApiError::connection_error)?;
+
+            let sales = flash_sale_logic::list_flash_sales(
+                &mut conn,
+                &*state.flash_sale_repo,
+                query.status,
+                Utc::now(),
+            )
+            .await
+            .map_err(ApiError::from)?;
+
+            state
+                .flash_sale_cache
+                .put_listing(query.status, sales.clone());
+            sales
+        }
+    };
+
+    let now = Utc::now();
+    Ok(Json(FlashSaleListResponse {
+        server_time: now,
+        items: sales
+            .into_iter()
+            .map(|details| FlashSaleResponse::new(details, now, state.stock_display))
+            .collect(),
+    }))
+}
+
+/// Replaces a sale's admission budget, omitted fields reset to their defaults
+///
+/// Applies to orders admitted from now on, while the sale may already be live
+pub async fn update_flash_sale_admission(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<FlashSaleAdmissionRequest>,
+) -> Result<Json<FlashSaleAdmissionResponse>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let flash_sale = flash_sale_logic::update_flash_sale_admission(
+        &mut conn,
+        &*state.flash_sale_repo,
+        id,
+        AdmissionBudget::from(req),
+    )
+    .await
+    .map_err(ApiError::from)?;
+
+    // Admission reads budgets through the cache, drop the stale copy
+    state.flash_sale_cache.evict_sale(id);
+
+    tracing::info!(
+        flash_sale_id = %id,
+        admission = ?flash_sale.admission,
+        "Flash sale admission budget updated"
+    );
+
+    Ok(Json(flash_sale.admission.into()))
+}
+
+/// Replaces a sale's order guards, omitted fields turn their guard off
+///
+/// Applies to orders admitted from now on, while the sale may already be live
+pub async fn update_flash_sale_guards(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<FlashSaleGuardsRequest>,
+) -> Result<Json<FlashSaleGuardsResponse>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let flash_sale = flash_sale_logic::update_flash_sale_guards(
+        &mut conn,
+        &*state.flash_sale_repo,
+        id,
+        OrderGuardSettings::from(req),
+    )
+    .await
+    .map_err(ApiError::from)?;
+
+    // Guards read the sale through the cache, drop the stale copy
+    state.flash_sale_cache.evict_sale(id);
+
+    tracing::info!(
+        flash_sale_id = %id,
+        guards = ?flash_sale.guards,
+        "Flash sale order guards updated"
+    );
+
+    Ok(Json(flash_sale.guards.into()))
+}
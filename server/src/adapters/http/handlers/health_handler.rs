@@ -1,11 +1,37 @@
-use axum::{Json, http::StatusCode, response::IntoResponse};
-use serde_json::json;
-
-pub async fn health_check() -> impl IntoResponse {
-    (
-        StatusCode::OK,
-        Json(json!({ "status": "ok", "message": "Service is healthy" })),
-    )
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use std::sync::Arc;
+
+use crate::{
+    adapters::http::dtos::health_dto::{LivenessResponse, ReadinessResponse},
+    app::health::HealthChecker,
+};
+
+/// 200 while the process runs and its runtime schedules tasks
+pub async fn liveness(State(health): State<Arc<HealthChecker>>) -> impl IntoResponse {
+    let live = health.is_live().await;
+    let response = LivenessResponse {
+        status: if live { "ok" } else { "unresponsive" },
+        uptime_secs: health.uptime().as_secs(),
+    };
+
+    let status = if live {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(response))
+}
+
+/// 200 when every dependency is usable, 503 with the failing components otherwise
+pub async fn readiness(State(health): State<Arc<HealthChecker>>) -> impl IntoResponse {
+    let report = health.readiness().await;
+
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(ReadinessResponse::from(report)))
 }
 
 pub async fn hello_world() -> &'static str {
@@ -1,4 +1,8 @@
+pub mod api_key_handler;
+pub mod flash_sale_handler;
 pub mod health_handler;
+pub mod order_guard_handler;
 pub mod order_handler;
 pub mod product_handler;
+pub mod settings_handler;
 pub mod user_handler;
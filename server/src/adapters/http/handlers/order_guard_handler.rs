@@ -0,0 +1,116 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    adapters::http::dtos::order_guard_dto::{
+        AllowlistRequest, AllowlistResponse, BlockUserRequest, BlockedUserResponse,
+    },
+    app::state::AppState,
+    errors::ApiError,
+    logic::order_guard_logic,
+};
+
+/// Most users added to an allowlist in one request
+const MAX_ALLOWLIST_BATCH: usize = 10_000;
+
+/// Blocks a user from sales with the blocklist guard on, or updates the reason
+pub async fn block_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+    req: Option<Json<BlockUserRequest>>,
+) -> Result<Json<BlockedUserResponse>, ApiError> {
+    let reason = req.and_then(|Json(req)| req.reason);
+
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let blocked = order_guard_logic::block_user(
+        &mut conn,
+        &*state.order_guard_repo,
+        user_id,
+        reason.as_deref(),
+    )
+    .await
+    .map_err(ApiError::from)?;
+
+    tracing::info!(user_id = %user_id, "User blocked from ordering");
+
+    Ok(Json(blocked.into()))
+}
+
+pub async fn unblock_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    order_guard_logic::unblock_user(&mut conn, &*state.order_guard_repo, user_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    tracing::info!(user_id = %user_id, "User unblocked");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Adds users to a sale's allowlist, only checked while `allowlist_only` is on
+pub async fn allow_flash_sale_users(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<AllowlistRequest>,
+) -> Result<Json<AllowlistResponse>, ApiError> {
+    if req.user_ids.len() > MAX_ALLOWLIST_BATCH {
+        return Err(ApiError::bad_request(format!(
+            "at most {MAX_ALLOWLIST_BATCH} users can be added at once"
+        )));
+    }
+
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let added = order_guard_logic::allow_flash_sale_users(
+        &mut conn,
+        &*state.order_guard_repo,
+        id,
+        &req.user_ids,
+    )
+    .await
+    .map_err(ApiError::from)?;
+
+    tracing::info!(flash_sale_id = %id, added, "Users added to flash sale allowlist");
+
+    Ok(Json(AllowlistResponse { added }))
+}
+
+pub async fn disallow_flash_sale_user(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    order_guard_logic::disallow_flash_sale_user(&mut conn, &*state.order_guard_repo, id, user_id)
+        .await
+        .map_err(ApiError::from)?;
+
+    tracing::info!(flash_sale_id = %id, user_id = %user_id, "User removed from flash sale allowlist");
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,117 +1,517 @@
 use axum::{
     Json,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
 };
+use chrono::Utc;
+use dashmap::mapref::entry::Entry;
 use uuid::Uuid;
 
 use crate::{
-    adapters::http::dtos::order_dto::{
-        CreateOrderRequest, OrderAcceptedResponse, OrderResult, OrderStatusResponse,
+    adapters::http::{
+        dtos::{
+            order_dto::{
+                CreateOrderRequest, OrderAcceptedResponse, OrderDetailResponse, OrderListQuery,
+                OrderResult, OrderStatusResponse, QueueEstimateResponse,
+            },
+            page_dto::PageQuery,
+        },
+        middleware::idempotency_middleware::idempotency_key,
     },
-    app::{order_queue::OrderQueueMessage, state::AppState},
-    domain::order::OrderProcessingStatus,
-    errors::ApiError,
-    logic::order_logic,
+    app::{
+        circuit_breaker::is_database_failure,
+        fair_queue::{QueueRejection, Reservation},
+        order_challenge::ChallengeRejection,
+        order_guards::GuardedOrder,
+        order_lanes::LaneRequest,
+        order_queue::OrderQueueMessage,
+        state::AppState,
+    },
+    domain::{
+        api_key::ApiKeyScope,
+        auth::Caller,
+        flash_sale::FlashSale,
+        order::{Order, OrderFilter, OrderProcessingStatus, OrderStatusEntry},
+        pagination::{Page, PageRequest},
+        rate_limit::RateQuota,
+        runtime_settings::{RuntimeSettings, SoldOutGate},
+    },
+    errors::{ApiError, AppError, RepoError, ServiceError},
+    logic::{flash_sale_logic, order_challenge_logic, order_logic},
 };
 
+/// Challenge issued by `GET /flash-sales/{id}/challenge`
+pub const CHALLENGE_HEADER: &str = "x-challenge";
+/// Solution found for [`CHALLENGE_HEADER`]
+pub const CHALLENGE_SOLUTION_HEADER: &str = "x-challenge-solution";
+
 pub async fn create_order(
     State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
+    caller: Caller,
+    headers: HeaderMap,
     Json(payload): Json<CreateOrderRequest>,
 ) -> Result<(StatusCode, Json<OrderAcceptedResponse>), ApiError> {
+    caller
+        .require_scope(ApiKeyScope::OrdersWrite)
+        .map_err(|e| ApiError::from(AppError::Service(e)))?;
+    let user_id = caller.user_id().ok_or_else(|| {
+        ApiError::bad_request(
+            "X-On-Behalf-Of must name the user when placing orders with an API key".to_string(),
+        )
+    })?;
+
     // Extract and validate Idempotency-Key header
-    let idempotency_key = match headers.get("idempotency-key") {
-        Some(value) => {
-            let s = value.to_str().map_err(|_| {
-                ApiError::bad_request("Idempotency-Key must be valid UTF-8".to_string())
-            })?;
-
-            uuid::Uuid::parse_str(s.trim())
-                .map(|u| u.to_string())
-                .map_err(|_| {
-                    ApiError::bad_request(
-                        "Invalid Idempotency-Key format. Expected UUID.".to_string(),
-                    )
-                })?
-        }
-        None => {
-            return Err(ApiError::bad_request(
-                "Idempotency-Key header is required for all order requests".to_string(),
-            ));
-        }
-    };
+    let idempotency_key = idempotency_key(&headers)?.ok_or_else(|| {
+        ApiError::bad_request(
+            "Idempotency-Key header is required for all order requests".to_string(),
+        )
+    })?;
 
-    // 1. Generate deterministic ID from idempotency key
+    // 1. Generate deterministic ID from the caller and idempotency key
     // This allows us to return the same order_id even before it hits the DB
-    let namespace = Uuid::from_u128(0x6ba7b810_9dad_11d1_80b4_00c04fd430c8);
-    let order_id = Uuid::new_v5(&namespace, idempotency_key.as_bytes());
+    let owner = Order::idempotency_owner(user_id, caller.api_client_id());
+    let order_id = Order::id_for_key(owner, &idempotency_key);
 
     // 2. Construct command
     let command = order_logic::CreateOrderCommand {
         order_id,
-        user_id: payload.user_id,
+        user_id,
         flash_sale_id: payload.flash_sale_id,
         quantity: payload.quantity,
         idempotency_key, // Key is moved here
+        request_fingerprint: Order::fingerprint(user_id, payload.flash_sale_id, payload.quantity),
+        api_client_id: caller.api_client_id(),
     };
 
-    // 3. Check rate limit
-    if !state.rate_limiter.check(command.user_id) {
+    // 3. Check rate limits: the partner's own quota, then the per-user limit
+    // (keyed by the token subject or the user the partner acts for)
+    if let Caller::Partner { api_key, .. } = &caller {
+        let decision = state
+            .rate_limiter
+            .check(
+                &format!("api_client:{}", api_key.client_id),
+                RateQuota::per_second(api_key.rate_limit_per_second),
+            )
+            .await;
+        decision.into_result().map_err(|e| {
+            metrics::counter!("api_key_rate_limit_rejections_total").increment(1);
+            ApiError::from(e).with_rate_limit(decision)
+        })?;
+    }
+
+    let settings = state.runtime_settings.current();
+    let decision = state
+        .rate_limiter
+        .check(
+            &format!("orders:user:{user_id}"),
+            RateQuota::per_second(settings.rate_limit_per_user),
+        )
+        .await;
+    decision.into_result().map_err(|e| {
         metrics::counter!("rate_limit_rejections_total").increment(1);
-        return Err(ApiError::from(crate::errors::AppError::Service(
-            crate::errors::ServiceError::RateLimitExceeded,
-        )));
+        ApiError::from(e).with_rate_limit(decision)
+    })?;
+
+    // 4. Report retries of accepted orders before admitting anything
+    // They neither spend the sale's budget nor run its challenge and guards,
+    // which could refuse an order that was already accepted
+    let existing = match state.order_status_store.get(&order_id) {
+        Some(entry) => existing_order(&state, order_id, &entry, &command)?,
+        None => None,
+    };
+    if let Some(response) = existing {
+        return Ok(response);
     }
 
-    // 4. Try to reserve a slot in the queue
-    let permit = match state.order_queue_tx.try_reserve() {
-        Ok(permit) => permit,
-        Err(_) => {
-            metrics::counter!("order_queue_overflow_total").increment(1);
-            return Err(ApiError::service_unavailable(
-                "Order queue is full. Please try again later.".to_string(),
-            ));
+    // Nothing below may reach the database while its breaker is open
+    state.db_breaker.reject_if_open().map_err(ApiError::from)?;
+
+    // Looked up before claiming the order id, the claim holds a map shard
+    let flash_sale = admission_flash_sale(&state, command.flash_sale_id).await?;
+    if flash_sale
+        .as_ref()
+        .is_some_and(|sale| sale.admission.challenge_required)
+    {
+        verify_challenge(&state, &headers, &command).await?;
+    }
+    if let Some(flash_sale) = &flash_sale {
+        // A full queue turns the order away anyway, spare the guards' queries
+        if state.order_queue.len() >= admission_limit(&state, &settings) {
+            state.order_challenges.record_overflow();
+            return Err(queue_full());
         }
+        let order = GuardedOrder {
+            command: &command,
+            flash_sale,
+            now: Utc::now(),
+        };
+        state
+            .db_breaker
+            .call(
+                state.order_guards.evaluate(&state.db_pool, &order),
+                is_database_failure,
+            )
+            .await
+            .map_err(ApiError::from)?;
+    }
+    check_sale_rate(&state, flash_sale.as_ref()).await?;
+    let admission = Admission {
+        settings: &settings,
+        caller: &caller,
+        flash_sale: flash_sale.as_ref(),
     };
 
-    // 5. Update status store
-    state
-        .order_status_store
-        .insert(order_id, OrderProcessingStatus::Pending);
+    // 5. Claim the order id in the status store
+    // Checked again under the claim, a concurrent request may have won it
+    let reservation = match state.order_status_store.entry(order_id) {
+        Entry::Occupied(mut existing) => {
+            if let Some(response) = existing_order(&state, order_id, existing.get(), &command)? {
+                return Ok(response);
+            }
+            let reservation = admit(&state, &admission, true, &command)?;
+            existing.insert(pending_entry(&command));
+            reservation
+        }
+        Entry::Vacant(vacant) => {
+            let reservation = admit(&state, &admission, false, &command)?;
+            vacant.insert(pending_entry(&command));
+            reservation
+        }
+    };
 
-    // 6. Send to worker
-    permit.send(OrderQueueMessage { order_id, command });
+    // 6. Send to worker and note where the order got in line
+    let sequence = reservation.send(OrderQueueMessage { order_id, command });
+    if let Some(mut entry) = state.order_status_store.get_mut(&order_id) {
+        entry.queue_sequence = Some(sequence);
+    }
 
     // 7. Return 202 Accepted
+    let queue = state.order_queue.estimate(sequence).map(Into::into);
     Ok((
         StatusCode::ACCEPTED,
-        Json(OrderAcceptedResponse {
+        Json(accepted_response(
             order_id,
-            status: "pending".to_string(),
-            status_url: format!("/orders/{}/status", order_id),
-        }),
+            &OrderProcessingStatus::Pending,
+            queue,
+        )),
     ))
 }
 
+/// The order's sale, from the cache where possible
+///
+/// `None` for unknown sales, which are admitted with the default budget and
+/// reported as not found by the worker
+async fn admission_flash_sale(
+    state: &AppState,
+    flash_sale_id: Uuid,
+) -> Result<Option<FlashSale>, ApiError> {
+    if let Some(details) = state.flash_sale_cache.get_sale(flash_sale_id) {
+        return Ok(Some(details.flash_sale));
+    }
+    if state.flash_sale_cache.is_missing(flash_sale_id) {
+        return Ok(None);
+    }
+
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    match flash_sale_logic::get_flash_sale_details(
+        &mut conn,
+        &*state.flash_sale_repo,
+        flash_sale_id,
+    )
+    .await
+    {
+        Ok(details) => {
+            let flash_sale = details.flash_sale.clone();
+            state.flash_sale_cache.put_sale(details);
+            Ok(Some(flash_sale))
+        }
+        Err(AppError::Repo(RepoError::NotFound { .. })) => {
+            state.flash_sale_cache.put_missing(flash_sale_id);
+            Ok(None)
+        }
+        Err(e) => Err(ApiError::from(e)),
+    }
+}
+
+/// The current status of an order id that was already claimed
+///
+/// A retry of an in-flight or completed request reports that status instead of
+/// being queued again; `None` when the earlier attempt failed and may be retried
+fn existing_order(
+    state: &AppState,
+    order_id: Uuid,
+    entry: &OrderStatusEntry,
+    command: &order_logic::CreateOrderCommand,
+) -> Result<Option<(StatusCode, Json<OrderAcceptedResponse>)>, ApiError> {
+    if entry.request_fingerprint != command.request_fingerprint {
+        metrics::counter!("idempotency_key_reuse_rejections_total").increment(1);
+        return Err(ApiError::from(AppError::Service(
+            ServiceError::IdempotencyKeyReused,
+        )));
+    }
+
+    let status_code = match &entry.status {
+        OrderProcessingStatus::Failed(_) => return Ok(None),
+        OrderProcessingStatus::Pending => StatusCode::ACCEPTED,
+        OrderProcessingStatus::Completed(_) => StatusCode::OK,
+    };
+    metrics::counter!("order_duplicate_requests_total").increment(1);
+    let queue = queue_estimate(state, entry);
+    Ok(Some((
+        status_code,
+        Json(accepted_response(order_id, &entry.status, queue)),
+    )))
+}
+
+/// Holds a new order to its sale's admission rate, shared by all instances
+async fn check_sale_rate(state: &AppState, flash_sale: Option<&FlashSale>) -> Result<(), ApiError> {
+    let Some((flash_sale, rate)) =
+        flash_sale.and_then(|sale| sale.admission.rate_per_second.map(|rate| (sale, rate)))
+    else {
+        return Ok(());
+    };
+    let decision = state
+        .rate_limiter
+        .check(
+            &format!("sale:{}", flash_sale.id),
+            RateQuota::per_second(rate as u32),
+        )
+        .await;
+    if decision.is_allowed() {
+        Ok(())
+    } else {
+        Err(sale_overflow(Some(flash_sale), "rate"))
+    }
+}
+
+/// Decide whether an order may enter the queue and reserve its slot
+///
+/// Nothing is admitted while the database breaker is open. Orders for sales
+/// known to be sold out are turned away when the gate is on, and the queue
+/// stops admitting at the configured or the adaptive depth, whichever is lower.
+/// Within that, each sale is held to its queue share (its admission rate is
+/// checked by [`check_sale_rate`] first), and the order waits in the lane the
+/// classifier picks
+fn admit<'a>(
+    state: &'a AppState,
+    admission: &Admission<'_>,
+    is_retry: bool,
+    command: &order_logic::CreateOrderCommand,
+) -> Result<Reservation<'a>, ApiError> {
+    let settings = admission.settings;
+    state.db_breaker.reject_if_open().map_err(ApiError::from)?;
+
+    if settings.sold_out_gate == SoldOutGate::Reject
+        && state.sold_out_sales.contains(command.flash_sale_id)
+    {
+        metrics::counter!("sold_out_gate_rejections_total").increment(1);
+        return Err(ApiError::from(AppError::Service(ServiceError::SoldOut)));
+    }
+
+    let flash_sale_id = command.flash_sale_id;
+    let budget = admission
+        .flash_sale
+        .map(|sale| sale.admission)
+        .unwrap_or_default();
+    let limit = admission_limit(state, settings);
+    let sale_slots = budget.queue_slots(limit, state.default_sale_queue_share);
+    let lane = state.lane_classifier.classify(&LaneRequest {
+        command,
+        caller: admission.caller,
+        is_retry,
+        flash_sale: admission.flash_sale,
+        now: Utc::now(),
+    });
+
+    let reservation = state
+        .order_queue
+        .try_reserve(
+            flash_sale_id,
+            lane,
+            limit,
+            sale_slots,
+            budget.scheduling_weight as u32,
+        )
+        .map_err(|rejection| {
+            state.order_challenges.record_overflow();
+            match rejection {
+                QueueRejection::Full => queue_full(),
+                QueueRejection::SaleShareExhausted => {
+                    sale_overflow(admission.flash_sale, "queue_share")
+                }
+            }
+        })?;
+
+    metrics::counter!("order_queue_lane_admitted_total", "lane" => lane.as_str()).increment(1);
+    Ok(reservation)
+}
+
+/// Queue depth at which orders stop being admitted, the configured or the
+/// adaptive limit, whichever is lower
+fn admission_limit(state: &AppState, settings: &RuntimeSettings) -> usize {
+    settings.queue_admission_limit.min(state.admission.limit())
+}
+
+/// Checks the solved challenge sent with an order for a sale that requires one
+/// and spends it on the order
+async fn verify_challenge(
+    state: &AppState,
+    headers: &HeaderMap,
+    command: &order_logic::CreateOrderCommand,
+) -> Result<(), ApiError> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let (Some(token), Some(solution)) =
+        (header(CHALLENGE_HEADER), header(CHALLENGE_SOLUTION_HEADER))
+    else {
+        metrics::counter!("order_challenge_rejections_total", "reason" => "missing").increment(1);
+        return Err(ApiError::from(AppError::Service(
+            ServiceError::ChallengeRequired,
+        )));
+    };
+
+    let reject = |rejection: ChallengeRejection| {
+        metrics::counter!("order_challenge_rejections_total", "reason" => rejection.as_str())
+            .increment(1);
+        ApiError::from(AppError::Service(ServiceError::ChallengeRejected(
+            rejection.as_str(),
+        )))
+    };
+    let challenge = state
+        .order_challenges
+        .verify(
+            token.trim(),
+            solution.trim(),
+            command.flash_sale_id,
+            command.user_id,
+        )
+        .map_err(reject)?;
+
+    let spent = state
+        .db_breaker
+        .call(
+            async {
+                let mut conn = state
+                    .db_pool
+                    .acquire()
+                    .await
+                    .map_err(|e| RepoError::ConnectionPool(e.to_string()))?;
+                order_challenge_logic::use_challenge(
+                    &mut conn,
+                    &*state.order_challenge_repo,
+                    &challenge.nonce,
+                    command.order_id,
+                    challenge.expires_at,
+                )
+                .await
+            },
+            is_database_failure,
+        )
+        .await
+        .map_err(ApiError::from)?;
+    if !spent {
+        return Err(reject(ChallengeRejection::Replayed));
+    }
+
+    Ok(())
+}
+
+/// Request context shared by both admission paths of [`create_order`]
+struct Admission<'a> {
+    settings: &'a RuntimeSettings,
+    caller: &'a Caller,
+    flash_sale: Option<&'a FlashSale>,
+}
+
+fn queue_full() -> ApiError {
+    metrics::counter!("order_queue_overflow_total").increment(1);
+    ApiError::service_unavailable("Order queue is full. Please try again later.".to_string())
+}
+
+/// The sale used up its own budget while the queue may still have room
+/// Orders for sales that don't exist are counted under `unknown`, their ids
+/// come from the client
+fn sale_overflow(flash_sale: Option<&FlashSale>, reason: &'static str) -> ApiError {
+    let flash_sale_id =
+        flash_sale.map_or_else(|| "unknown".to_string(), |sale| sale.id.to_string());
+    metrics::counter!(
+        "order_queue_sale_overflow_total",
+        "flash_sale_id" => flash_sale_id,
+        "reason" => reason
+    )
+    .increment(1);
+    ApiError::service_unavailable(
+        "Too many orders for this flash sale right now. Please try again later.".to_string(),
+    )
+}
+
+fn pending_entry(command: &order_logic::CreateOrderCommand) -> OrderStatusEntry {
+    OrderStatusEntry {
+        user_id: command.user_id,
+        api_client_id: command.api_client_id,
+        request_fingerprint: command.request_fingerprint.clone(),
+        status: OrderProcessingStatus::Pending,
+        queue_sequence: None,
+    }
+}
+
+/// Position and wait of an order still waiting in this process's queue
+fn queue_estimate(state: &AppState, entry: &OrderStatusEntry) -> Option<QueueEstimateResponse> {
+    match (&entry.status, entry.queue_sequence) {
+        (OrderProcessingStatus::Pending, Some(sequence)) => {
+            state.order_queue.estimate(sequence).map(Into::into)
+        }
+        _ => None,
+    }
+}
+
+fn accepted_response(
+    order_id: Uuid,
+    status: &OrderProcessingStatus,
+    queue: Option<QueueEstimateResponse>,
+) -> OrderAcceptedResponse {
+    OrderAcceptedResponse {
+        order_id,
+        status: status.as_str().to_string(),
+        status_url: format!("/orders/{}/status", order_id),
+        queue,
+    }
+}
+
 pub async fn get_order_status(
     State(state): State<AppState>,
+    caller: Caller,
     Path(order_id): Path<Uuid>,
 ) -> Result<Json<OrderStatusResponse>, ApiError> {
+    caller
+        .require_scope(ApiKeyScope::OrdersRead)
+        .map_err(|e| ApiError::from(AppError::Service(e)))?;
+
     // DashMap::get returns a read-only view, no .read().await needed
-    match state.order_status_store.get(&order_id) {
+    // Orders of other users are reported as missing so ids can't be probed
+    match state
+        .order_status_store
+        .get(&order_id)
+        .filter(|entry| caller.can_access_order(entry.user_id, entry.api_client_id))
+    {
         Some(entry) => {
-            let status = entry.value();
+            let status = &entry.value().status;
             match status {
                 OrderProcessingStatus::Pending => Ok(Json(OrderStatusResponse {
                     order_id,
                     status: "pending".to_string(),
                     result: None,
+                    queue: queue_estimate(&state, entry.value()),
                 })),
                 OrderProcessingStatus::Completed(order) => Ok(Json(OrderStatusResponse {
                     order_id,
                     status: "completed".to_string(),
                     result: Some(OrderResult::Success(order.clone().into())),
+                    queue: None,
                 })),
                 OrderProcessingStatus::Failed(error) => Ok(Json(OrderStatusResponse {
                     order_id,
@@ -119,13 +519,373 @@ pub async fn get_order_status(
                     result: Some(OrderResult::Error {
                         message: error.clone(),
                     }),
+                    queue: None,
                 })),
             }
         }
-        None => Err(ApiError {
-            status: StatusCode::NOT_FOUND,
-            code: "ORDER_NOT_FOUND",
-            message: "Order not found".to_string(),
-        }),
+        None => Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "ORDER_NOT_FOUND",
+            "Order not found".to_string(),
+        )),
+    }
+}
+
+pub async fn get_order(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<OrderDetailResponse>, ApiError> {
+    caller
+        .require_scope(ApiKeyScope::OrdersRead)
+        .map_err(|e| ApiError::from(AppError::Service(e)))?;
+
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    // Orders of other users are reported as missing so ids can't be probed
+    match order_logic::get_order(&mut conn, &*state.order_repo, order_id).await {
+        Ok(order) if caller.can_access_order(order.user_id, order.api_client_id) => {
+            Ok(Json(order.into()))
+        }
+        Ok(_) | Err(AppError::Repo(RepoError::NotFound { .. })) => Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "ORDER_NOT_FOUND",
+            "Order not found".to_string(),
+        )),
+        Err(e) => Err(ApiError::from(e)),
+    }
+}
+
+pub async fn get_user_orders(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(user_id): Path<Uuid>,
+    Query(page): Query<PageQuery>,
+    Query(filter): Query<OrderListQuery>,
+) -> Result<Json<Page<OrderDetailResponse>>, ApiError> {
+    caller
+        .require_scope(ApiKeyScope::OrdersRead)
+        .map_err(|e| ApiError::from(AppError::Service(e)))?;
+
+    if !caller.can_view_orders_of(user_id) {
+        return Err(ApiError::from(AppError::Service(ServiceError::Forbidden(
+            "cannot access another user's orders".into(),
+        ))));
+    }
+
+    let page = PageRequest::try_from(page).map_err(ApiError::from)?;
+    let filter = OrderFilter {
+        api_client_id: caller.api_client_id(),
+        ..filter.into()
+    };
+
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let orders = order_logic::get_user_orders(&mut conn, &*state.order_repo, user_id, filter, page)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(orders.map(Into::into)))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use axum::http::HeaderValue;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use sqlx::PgConnection;
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+    use crate::{
+        adapters::{
+            db::{
+                api_key::repository::PostgresApiKeyRepo,
+                flash_sale::repository::PostgresFlashSaleRepo,
+                idempotency::repository::PostgresIdempotencyRepo,
+                order::repository::PostgresOrderRepo,
+                order_challenge::repository::PostgresOrderChallengeRepo,
+                order_guard::repository::PostgresOrderGuardRepo,
+                product::repository::PostgresProductRepo,
+                settings_audit::repository::PostgresSettingsAuditRepo,
+                user::repository::PostgresUserRepo,
+            },
+            http::middleware::RouteRateLimits,
+        },
+        app::{
+            adaptive_admission::AdaptiveAdmission,
+            circuit_breaker::CircuitBreaker,
+            config::Config,
+            fair_queue::FairOrderQueue,
+            flash_sale_cache::FlashSaleCache,
+            health::{HealthChecker, WorkerLiveness},
+            heartbeat::Heartbeat,
+            order_challenge::OrderChallenges,
+            order_guards::{OrderGuard, OrderGuards},
+            order_lanes::{LaneWeights, OrderLane, RuleLaneClassifier},
+            rate_limiter::InMemoryRateLimiter,
+            runtime_settings::RuntimeSettingsStore,
+            sold_out_sales::SoldOutSales,
+        },
+        domain::{
+            auth::{AuthenticatedUser, Role, UserTier},
+            flash_sale::{AdmissionBudget, FlashSaleDetails},
+            order_guard::{GuardRejection, OrderGuardSettings, VelocityLimit},
+            product::{Product, ProductName},
+        },
+        ports::TokenVerifier,
+    };
+
+    const ACCEPTED_KEY: &str = "6f1c2d3e-0000-4000-8000-000000000001";
+    const NEW_KEY: &str = "6f1c2d3e-0000-4000-8000-000000000002";
+
+    /// Stands in for a velocity guard the user is already over
+    struct OverLimitGuard;
+
+    #[async_trait]
+    impl OrderGuard for OverLimitGuard {
+        fn name(&self) -> &'static str {
+            "velocity"
+        }
+
+        fn enabled(&self, settings: &OrderGuardSettings) -> bool {
+            settings.velocity.is_some()
+        }
+
+        async fn check(
+            &self,
+            _conn: &mut PgConnection,
+            _order: &GuardedOrder<'_>,
+        ) -> Result<Option<GuardRejection>, AppError> {
+            Ok(Some(GuardRejection::VelocityExceeded {
+                max_orders: 1,
+                window_secs: 60,
+            }))
+        }
+    }
+
+    struct NoTokens;
+
+    impl TokenVerifier for NoTokens {
+        fn verify(&self, _token: &str) -> Result<AuthenticatedUser, ServiceError> {
+            Err(ServiceError::Unauthenticated)
+        }
+    }
+
+    /// State whose database is never reached: the pool connects lazily to
+    /// nothing, and the sale comes from the cache
+    fn state_with_sale(queue_capacity: usize, flash_sale: &FlashSale) -> AppState {
+        let config = Config::default();
+        let db_pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(50))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .expect("lazy pool");
+        let order_queue = Arc::new(FairOrderQueue::new(
+            queue_capacity,
+            LaneWeights::from(&config.priority_lanes),
+        ));
+        let db_breaker = Arc::new(CircuitBreaker::new(
+            "database",
+            config.circuit_breaker.clone(),
+        ));
+        let flash_sale_cache = Arc::new(FlashSaleCache::new(Duration::from_secs(60)));
+        flash_sale_cache.put_sale(FlashSaleDetails {
+            flash_sale: flash_sale.clone(),
+            product: Product {
+                id: flash_sale.product_id,
+                name: ProductName::new("Test product".to_string()).unwrap(),
+                description: None,
+                sku: None,
+                image_urls: Vec::new(),
+                created_at: Utc::now(),
+                archived_at: None,
+            },
+        });
+
+        AppState {
+            user_repo: Arc::new(PostgresUserRepo::new()),
+            product_repo: Arc::new(PostgresProductRepo::new()),
+            flash_sale_repo: Arc::new(PostgresFlashSaleRepo::new()),
+            order_repo: Arc::new(PostgresOrderRepo::new()),
+            idempotency_repo: Arc::new(PostgresIdempotencyRepo::new()),
+            api_key_repo: Arc::new(PostgresApiKeyRepo::new()),
+            settings_audit_repo: Arc::new(PostgresSettingsAuditRepo::new()),
+            order_guard_repo: Arc::new(PostgresOrderGuardRepo::new()),
+            order_challenge_repo: Arc::new(PostgresOrderChallengeRepo::new()),
+            db_pool: db_pool.clone(),
+            prometheus_handle: PrometheusBuilder::new().build_recorder().handle(),
+            order_queue: order_queue.clone(),
+            rate_limiter: Arc::new(InMemoryRateLimiter::new()),
+            route_rate_limits: Arc::new(RouteRateLimits::new(&config.rate_limits)),
+            default_sale_queue_share: 1.0,
+            lane_classifier: Arc::new(RuleLaneClassifier::new(&config.priority_lanes)),
+            order_challenges: Arc::new(OrderChallenges::new(
+                config.challenge.clone(),
+                b"test key".to_vec(),
+            )),
+            order_guards: Arc::new(OrderGuards::new(vec![Arc::new(OverLimitGuard)])),
+            token_verifier: Arc::new(NoTokens),
+            order_status_store: Arc::new(dashmap::DashMap::new()),
+            idempotency_ttl: chrono::Duration::hours(1),
+            idempotency_lease: chrono::Duration::minutes(1),
+            flash_sale_cache,
+            stock_display: config.flash_sales.stock_display,
+            runtime_settings: Arc::new(RuntimeSettingsStore::new(
+                config.runtime_settings(),
+                queue_capacity,
+                None,
+            )),
+            sold_out_sales: Arc::new(SoldOutSales::new()),
+            health: Arc::new(HealthChecker::new(
+                db_pool,
+                config.health.clone(),
+                Some(order_queue),
+                WorkerLiveness::Local(Arc::new(Heartbeat::new())),
+                db_breaker.clone(),
+            )),
+            db_breaker,
+            admission: Arc::new(AdaptiveAdmission::new(
+                config.adaptive_admission.clone(),
+                queue_capacity,
+            )),
+        }
+    }
+
+    /// A running sale with a velocity guard and a required challenge
+    fn guarded_sale() -> FlashSale {
+        let now = Utc::now();
+        FlashSale {
+            id: Uuid::new_v4(),
+            product_id: Uuid::new_v4(),
+            start_time: now - chrono::Duration::minutes(1),
+            end_time: now + chrono::Duration::hours(1),
+            total_inventory: 100,
+            remaining_inventory: 100,
+            per_user_limit: 10,
+            admission: AdmissionBudget {
+                challenge_required: true,
+                ..AdmissionBudget::default()
+            },
+            guards: OrderGuardSettings {
+                velocity: Some(VelocityLimit {
+                    max_orders: 1,
+                    window_secs: 60,
+                }),
+                ..OrderGuardSettings::default()
+            },
+            created_at: now,
+        }
+    }
+
+    fn customer(user_id: Uuid) -> Caller {
+        Caller::User(AuthenticatedUser {
+            user_id,
+            role: Role::Customer,
+            tier: UserTier::Standard,
+        })
+    }
+
+    async fn post(
+        state: &AppState,
+        user_id: Uuid,
+        flash_sale_id: Uuid,
+        key: &str,
+    ) -> Result<(StatusCode, Json<OrderAcceptedResponse>), ApiError> {
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", HeaderValue::from_str(key).unwrap());
+        create_order(
+            State(state.clone()),
+            customer(user_id),
+            headers,
+            Json(CreateOrderRequest {
+                flash_sale_id,
+                quantity: 1,
+            }),
+        )
+        .await
+    }
+
+    /// Records `key` as accepted for the user, as its first request did
+    fn accept(state: &AppState, user_id: Uuid, flash_sale_id: Uuid, key: &str) {
+        let order_id = Order::id_for_key(Order::idempotency_owner(user_id, None), key);
+        let entry = pending_entry(&order_logic::CreateOrderCommand {
+            order_id,
+            user_id,
+            flash_sale_id,
+            quantity: 1,
+            idempotency_key: key.to_string(),
+            request_fingerprint: Order::fingerprint(user_id, flash_sale_id, 1),
+            api_client_id: None,
+        });
+        state.order_status_store.insert(order_id, entry);
+    }
+
+    fn fill_queue(state: &AppState) {
+        let reservation = state
+            .order_queue
+            .try_reserve(Uuid::new_v4(), OrderLane::Standard, 1, 1, 1)
+            .expect("empty queue");
+        let command = order_logic::CreateOrderCommand {
+            order_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            flash_sale_id: Uuid::new_v4(),
+            quantity: 1,
+            idempotency_key: "other".to_string(),
+            request_fingerprint: String::new(),
+            api_client_id: None,
+        };
+        reservation.send(OrderQueueMessage {
+            order_id: command.order_id,
+            command,
+        });
+    }
+
+    #[tokio::test]
+    async fn retry_of_accepted_order_skips_admission() {
+        let sale = guarded_sale();
+        let state = state_with_sale(1, &sale);
+        let user_id = Uuid::new_v4();
+        accept(&state, user_id, sale.id, ACCEPTED_KEY);
+        fill_queue(&state);
+
+        // No challenge headers, a full queue and a guard that refuses the user
+        let (status, Json(response)) = post(&state, user_id, sale.id, ACCEPTED_KEY)
+            .await
+            .expect("retry reports its status");
+
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(response.status, "pending");
+        assert_eq!(state.order_challenges.difficulty(), 16);
+    }
+
+    #[tokio::test]
+    async fn new_order_goes_through_admission() {
+        let sale = guarded_sale();
+        let state = state_with_sale(1, &sale);
+        let user_id = Uuid::new_v4();
+        accept(&state, user_id, sale.id, ACCEPTED_KEY);
+        fill_queue(&state);
+
+        // The same request under a new key needs the challenge
+        let error = post(&state, user_id, sale.id, NEW_KEY).await.unwrap_err();
+        assert_eq!(error.code, "CHALLENGE_REQUIRED");
+
+        // and without one required, is turned away by the full queue
+        let mut open_sale = sale.clone();
+        open_sale.admission.challenge_required = false;
+        let state = state_with_sale(1, &open_sale);
+        fill_queue(&state);
+        let error = post(&state, user_id, open_sale.id, NEW_KEY)
+            .await
+            .unwrap_err();
+        assert_eq!(error.status, StatusCode::SERVICE_UNAVAILABLE);
     }
 }
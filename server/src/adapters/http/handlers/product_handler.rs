@@ -1,8 +1,16 @@
-use axum::{Json, extract::State};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use uuid::Uuid;
 
 use crate::{
-    adapters::http::dtos::{CreateProductRequest, ProductResponse},
+    adapters::http::dtos::{
+        CreateProductRequest, PageQuery, ProductListQuery, ProductResponse, ProductSearchQuery,
+        UpdateProductRequest,
+    },
     app::state::AppState,
+    domain::pagination::{Page, PageRequest},
     errors::ApiError,
     logic::product_logic,
 };
@@ -19,7 +27,7 @@ pub async fn create_product(
 
     let command = product_logic::CreateProductCommand::try_from(req).map_err(ApiError::from)?;
 
-    let product = product_logic::save_product(&mut *tx, &*state.product_repo, command)
+    let product = product_logic::save_product(&mut tx, &*state.product_repo, command)
         .await
         .map_err(ApiError::from)?;
 
@@ -30,16 +38,97 @@ pub async fn create_product(
 
 pub async fn get_products(
     State(state): State<AppState>,
-) -> Result<Json<Vec<ProductResponse>>, ApiError> {
+    Query(page): Query<PageQuery>,
+    Query(filter): Query<ProductListQuery>,
+) -> Result<Json<Page<ProductResponse>>, ApiError> {
+    let page = PageRequest::try_from(page).map_err(ApiError::from)?;
+
     let mut conn = state
         .db_pool
         .acquire()
         .await
         .map_err(ApiError::connection_error)?;
 
-    let products = product_logic::get_products(&mut *conn, &*state.product_repo)
+    let products =
+        product_logic::get_products(&mut conn, &*state.product_repo, filter.into(), page)
+            .await
+            .map_err(ApiError::from)?;
+
+    Ok(Json(products.map(Into::into)))
+}
+
+pub async fn get_product(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProductResponse>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let product = product_logic::get_product(&mut conn, &*state.product_repo, id)
         .await
         .map_err(ApiError::from)?;
 
+    Ok(Json(product.into()))
+}
+
+pub async fn search_products(
+    State(state): State<AppState>,
+    Query(query): Query<ProductSearchQuery>,
+) -> Result<Json<Vec<ProductResponse>>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let products =
+        product_logic::search_products(&mut conn, &*state.product_repo, &query.q, query.limit)
+            .await
+            .map_err(ApiError::from)?;
+
     Ok(Json(products.into_iter().map(Into::into).collect()))
 }
+
+pub async fn update_product(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateProductRequest>,
+) -> Result<Json<ProductResponse>, ApiError> {
+    let command = product_logic::UpdateProductCommand::try_from(req).map_err(ApiError::from)?;
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(ApiError::transaction_error)?;
+
+    let product = product_logic::update_product(&mut tx, &*state.product_repo, id, command)
+        .await
+        .map_err(ApiError::from)?;
+
+    tx.commit().await.map_err(ApiError::transaction_error)?;
+
+    Ok(Json(product.into()))
+}
+
+pub async fn archive_product(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProductResponse>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let product = product_logic::archive_product(&mut conn, &*state.product_repo, id)
+        .await
+        .map_err(ApiError::from)?;
+
+    tracing::info!(product_id = %id, "Product archived");
+
+    Ok(Json(product.into()))
+}
@@ -0,0 +1,77 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+
+use crate::{
+    adapters::http::dtos::settings_dto::{
+        RuntimeSettingsResponse, SettingsAuditQuery, SettingsChangeResponse,
+        UpdateRuntimeSettingsRequest,
+    },
+    app::state::AppState,
+    domain::{
+        auth::AuthenticatedUser,
+        runtime_settings::{RuntimeSettingsPatch, SettingsChangeSource},
+    },
+    errors::ApiError,
+    logic::settings_logic,
+};
+
+pub async fn get_settings(State(state): State<AppState>) -> Json<RuntimeSettingsResponse> {
+    let settings = state.runtime_settings.current();
+
+    Json(RuntimeSettingsResponse::new(
+        &settings,
+        state.runtime_settings.queue_capacity(),
+    ))
+}
+
+/// Applies a partial update immediately, without restarting or losing the queue
+pub async fn update_settings(
+    State(state): State<AppState>,
+    caller: AuthenticatedUser,
+    Json(req): Json<UpdateRuntimeSettingsRequest>,
+) -> Result<Json<RuntimeSettingsResponse>, ApiError> {
+    let patch = RuntimeSettingsPatch::try_from(req).map_err(ApiError::from)?;
+
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let settings = state
+        .runtime_settings
+        .update(
+            &mut conn,
+            &*state.settings_audit_repo,
+            patch,
+            SettingsChangeSource::AdminApi,
+            Some(caller.user_id),
+        )
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(RuntimeSettingsResponse::new(
+        &settings,
+        state.runtime_settings.queue_capacity(),
+    )))
+}
+
+pub async fn get_settings_audit(
+    State(state): State<AppState>,
+    Query(query): Query<SettingsAuditQuery>,
+) -> Result<Json<Vec<SettingsChangeResponse>>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let changes =
+        settings_logic::get_settings_changes(&mut conn, &*state.settings_audit_repo, query.limit)
+            .await
+            .map_err(ApiError::from)?;
+
+    Ok(Json(changes.into_iter().map(Into::into).collect()))
+}
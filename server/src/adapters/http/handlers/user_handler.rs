@@ -1,25 +1,39 @@
-use axum::{Json, extract::State, http::StatusCode};
-use chrono::DateTime;
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
 use uuid::Uuid;
 
 use crate::{
-    adapters::http::dtos::user_dto::UserResponse, app::state::AppState, domain::user::User,
-    errors::ApiError, logic::user_logic,
+    adapters::http::dtos::{
+        page_dto::PageQuery,
+        user_dto::{
+            CreateUserRequest, UpdateUserRequest, UserListQuery, UserLookupQuery, UserResponse,
+        },
+    },
+    app::state::AppState,
+    domain::{
+        auth::AuthenticatedUser,
+        pagination::{Page, PageRequest},
+    },
+    errors::{ApiError, AppError, ServiceError},
+    logic::user_logic,
 };
 
-pub async fn create_user(State(state): State<AppState>) -> Result<Json<UserResponse>, ApiError> {
+pub async fn create_user(
+    State(state): State<AppState>,
+    Json(req): Json<CreateUserRequest>,
+) -> Result<Json<UserResponse>, ApiError> {
+    let command = user_logic::CreateUserCommand::try_from(req).map_err(ApiError::from)?;
+
     let mut tx = state
         .db_pool
         .begin()
         .await
         .map_err(ApiError::transaction_error)?;
 
-    let user = User {
-        id: Uuid::new_v4(),
-        created_at: DateTime::default(),
-    };
-
-    let saved_user = user_logic::save_user(&mut *tx, &*state.user_repo, user)
+    let saved_user = user_logic::save_user(&mut tx, &*state.user_repo, command)
         .await
         .map_err(ApiError::from)?;
 
@@ -28,29 +42,33 @@ pub async fn create_user(State(state): State<AppState>) -> Result<Json<UserRespo
     Ok(Json(saved_user.into()))
 }
 
-pub async fn get_users(State(state): State<AppState>) -> Result<Json<Vec<UserResponse>>, ApiError> {
+pub async fn get_users(
+    State(state): State<AppState>,
+    Query(page): Query<PageQuery>,
+    Query(filter): Query<UserListQuery>,
+) -> Result<Json<Page<UserResponse>>, ApiError> {
+    let page = PageRequest::try_from(page).map_err(ApiError::from)?;
+
     let mut conn = state
         .db_pool
         .acquire()
         .await
         .map_err(ApiError::connection_error)?;
 
-    let users = user_logic::get_users(&mut *conn, &*state.user_repo)
+    let users = user_logic::get_users(&mut conn, &*state.user_repo, filter.into(), page)
         .await
         .map_err(ApiError::from)?;
 
-    Ok(Json(users.into_iter().map(|u| u.into()).collect()))
+    Ok(Json(users.map(Into::into)))
 }
 
 pub async fn get_user_by_id(
     State(state): State<AppState>,
+    caller: AuthenticatedUser,
     params: axum::extract::Path<String>,
 ) -> Result<Json<UserResponse>, ApiError> {
-    let uuid = Uuid::parse_str(&params.0).map_err(|_| ApiError {
-        status: StatusCode::BAD_REQUEST,
-        code: "INVALID_UUID",
-        message: "Invalid UUID format".into(),
-    })?;
+    let uuid = parse_user_id(&params.0)?;
+    ensure_profile_access(&caller, uuid)?;
 
     let mut conn = state
         .db_pool
@@ -58,9 +76,74 @@ pub async fn get_user_by_id(
         .await
         .map_err(ApiError::connection_error)?;
 
-    let user = user_logic::get_user_by_id(&mut *conn, &*state.user_repo, uuid)
+    let user = user_logic::get_user_by_id(&mut conn, &*state.user_repo, uuid)
         .await
         .map_err(ApiError::from)?;
 
     Ok(Json(user.into()))
 }
+
+/// Lets customer service find a buyer by email (`GET /users/lookup?email=`)
+pub async fn get_user_by_email(
+    State(state): State<AppState>,
+    Query(query): Query<UserLookupQuery>,
+) -> Result<Json<UserResponse>, ApiError> {
+    let mut conn = state
+        .db_pool
+        .acquire()
+        .await
+        .map_err(ApiError::connection_error)?;
+
+    let user = user_logic::get_user_by_email(&mut conn, &*state.user_repo, query.email)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(user.into()))
+}
+
+pub async fn update_user(
+    State(state): State<AppState>,
+    caller: AuthenticatedUser,
+    params: axum::extract::Path<String>,
+    Json(req): Json<UpdateUserRequest>,
+) -> Result<Json<UserResponse>, ApiError> {
+    let uuid = parse_user_id(&params.0)?;
+    ensure_profile_access(&caller, uuid)?;
+
+    let command = user_logic::UpdateUserCommand::try_from(req).map_err(ApiError::from)?;
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(ApiError::transaction_error)?;
+
+    let user = user_logic::update_user(&mut tx, &*state.user_repo, uuid, command)
+        .await
+        .map_err(ApiError::from)?;
+
+    tx.commit().await.map_err(ApiError::transaction_error)?;
+
+    Ok(Json(user.into()))
+}
+
+fn parse_user_id(value: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(value).map_err(|_| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "INVALID_UUID",
+            "Invalid UUID format".into(),
+        )
+    })
+}
+
+/// Customers may only see and edit their own profile
+fn ensure_profile_access(caller: &AuthenticatedUser, user_id: Uuid) -> Result<(), ApiError> {
+    if !caller.can_see_data_of(user_id) {
+        return Err(ApiError::from(AppError::Service(ServiceError::Forbidden(
+            "cannot access another user's profile".into(),
+        ))));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use axum::{body::Body, http::Request, middleware::Next, response::Response};
+
+use crate::{
+    domain::auth::{AuthenticatedUser, Role},
+    errors::{ApiError, AppError, ServiceError},
+};
+
+/// Restricts a route to operators and admins
+pub async fn require_operator(
+    user: AuthenticatedUser,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    authorize(user, &[Role::Operator, Role::Admin], req, next).await
+}
+
+/// Restricts a route to admins
+pub async fn require_admin(
+    user: AuthenticatedUser,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    authorize(user, &[Role::Admin], req, next).await
+}
+
+async fn authorize(
+    user: AuthenticatedUser,
+    allowed: &[Role],
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if !user.has_any_role(allowed) {
+        metrics::counter!("authorization_rejections_total", "role" => user.role.as_str())
+            .increment(1);
+        return Err(ApiError::from(AppError::Service(ServiceError::Forbidden(
+            format!("role {} cannot access this resource", user.role.as_str()),
+        ))));
+    }
+
+    // Handlers extracting the caller reuse the verified identity
+    req.extensions_mut().insert(user);
+    Ok(next.run(req).await)
+}
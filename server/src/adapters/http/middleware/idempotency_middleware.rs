@@ -0,0 +1,329 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::{FromRequestParts, State},
+    http::{HeaderMap, HeaderValue, Request, StatusCode, header, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    adapters::http::extractors::api_key_extractor::API_KEY_HEADER,
+    app::state::AppState,
+    domain::{
+        api_key::ApiKey,
+        auth::AuthenticatedUser,
+        idempotency::{IdempotentRequest, StoredResponse, fingerprint},
+    },
+    errors::{ApiError, AppError, ServiceError},
+    logic::idempotency_logic::{self, BeginIdempotentRequestCommand},
+};
+
+/// Largest request body buffered to fingerprint an idempotent request
+const MAX_REQUEST_BODY_BYTES: usize = 1024 * 1024;
+
+/// Extract and validate the Idempotency-Key header
+///
+/// Returns `None` when the header is absent
+pub fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let Some(value) = headers.get("idempotency-key") else {
+        return Ok(None);
+    };
+
+    let s = value
+        .to_str()
+        .map_err(|_| ApiError::bad_request("Idempotency-Key must be valid UTF-8".to_string()))?;
+
+    Uuid::parse_str(s.trim())
+        .map(|u| Some(u.to_string()))
+        .map_err(|_| {
+            ApiError::bad_request("Invalid Idempotency-Key format. Expected UUID.".to_string())
+        })
+}
+
+/// Whom an Idempotency-Key belongs to: the partner client of an API key, the
+/// user of a bearer token, or nobody for anonymous requests
+///
+/// Credentials that don't verify count as anonymous, the route itself decides
+/// whether the request is allowed.
+async fn key_owner(parts: &mut Parts, state: &AppState) -> String {
+    if parts.headers.contains_key(API_KEY_HEADER) {
+        if let Ok(api_key) = ApiKey::from_request_parts(parts, state).await {
+            return format!("api_client:{}", api_key.client_id);
+        }
+    } else if let Ok(user) = AuthenticatedUser::from_request_parts(parts, state).await {
+        return format!("user:{}", user.user_id);
+    }
+
+    "anonymous".to_string()
+}
+
+/// Makes a mutating route safe to retry when the client sends an Idempotency-Key
+///
+/// Keys are scoped to the route and the caller, so callers who happen to pick
+/// the same key never see each other's responses. The first response for a
+/// key is stored and replayed for repeats of the same request; a repeat with
+/// a different body is rejected and a repeat that arrives while the original
+/// is still running gets 409. Server errors are not stored so the client can
+/// retry them, and a request that is cancelled or whose response can't be
+/// stored releases its key. A key left claimed by a process that died is
+/// taken over once its short in-progress lease ends.
+pub async fn idempotency(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    match handle_idempotent_request(state, req, next).await {
+        Ok(response) => response,
+        Err(err) => err.into_response(),
+    }
+}
+
+async fn handle_idempotent_request(
+    state: AppState,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(key) = idempotency_key(req.headers())? else {
+        return Ok(next.run(req).await);
+    };
+
+    let (mut parts, body) = req.into_parts();
+    let body = to_bytes(body, MAX_REQUEST_BODY_BYTES)
+        .await
+        .map_err(|_| ApiError::bad_request("Request body is too large".to_string()))?;
+
+    let route = format!("{} {}", parts.method, parts.uri.path());
+    let scope = format!("{route} {}", key_owner(&mut parts, &state).await);
+    let mut canonical = scope.clone().into_bytes();
+    canonical.push(b'\n');
+    canonical.extend_from_slice(&body);
+
+    let command = BeginIdempotentRequestCommand {
+        scope: scope.clone(),
+        idempotency_key: key.clone(),
+        request_fingerprint: fingerprint(&canonical),
+        lease: state.idempotency_lease,
+    };
+
+    // The connection is released before running the handler so a slow
+    // request doesn't hold a pool slot twice
+    let claimed_at = {
+        let mut conn = state
+            .db_pool
+            .acquire()
+            .await
+            .map_err(ApiError::connection_error)?;
+
+        match idempotency_logic::begin_idempotent_request(
+            &mut conn,
+            &*state.idempotency_repo,
+            command,
+        )
+        .await
+        .map_err(ApiError::from)?
+        {
+            IdempotentRequest::Claimed(claimed_at) => claimed_at,
+            IdempotentRequest::Replay(stored) => {
+                metrics::counter!("idempotency_replays_total", "scope" => route).increment(1);
+                return Ok(replay_response(stored));
+            }
+        }
+    };
+
+    let claim = KeyClaim::new(state, scope, key, claimed_at);
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    let (parts, body) = response.into_parts();
+
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!(error = ?e, scope = %claim.scope, "Failed to buffer response body");
+            claim.release().await;
+            return Err(ApiError::internal("Failed to read response".to_string()));
+        }
+    };
+
+    if parts.status.is_server_error() {
+        claim.release().await;
+    } else {
+        let stored = StoredResponse {
+            status_code: parts.status.as_u16(),
+            content_type: parts
+                .headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_owned),
+            body: body.to_vec(),
+        };
+        claim.store(&stored).await;
+    }
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+fn replay_response(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status_code).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+
+    let headers = response.headers_mut();
+    if let Some(content_type) = stored
+        .content_type
+        .and_then(|v| HeaderValue::from_str(&v).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert("idempotent-replayed", HeaderValue::from_static("true"));
+
+    response
+}
+
+/// A claimed key, released if the request is dropped before it settles
+///
+/// The handler future is dropped when the client disconnects, so without the
+/// guard the key would stay claimed until its lease ends.
+struct KeyClaim {
+    state: AppState,
+    scope: String,
+    key: String,
+    claimed_at: DateTime<Utc>,
+    settled: bool,
+}
+
+impl KeyClaim {
+    fn new(state: AppState, scope: String, key: String, claimed_at: DateTime<Utc>) -> Self {
+        Self {
+            state,
+            scope,
+            key,
+            claimed_at,
+            settled: false,
+        }
+    }
+
+    /// Stores the response for replays, or releases the key if that fails
+    async fn store(mut self, stored: &StoredResponse) {
+        let result = match self.state.db_pool.acquire().await {
+            Ok(mut conn) => {
+                idempotency_logic::complete_idempotent_request(
+                    &mut conn,
+                    &*self.state.idempotency_repo,
+                    &self.scope,
+                    &self.key,
+                    self.claimed_at,
+                    stored,
+                    self.state.idempotency_ttl,
+                )
+                .await
+            }
+            Err(e) => Err(crate::errors::RepoError::ConnectionPool(e.to_string()).into()),
+        };
+
+        match result {
+            Ok(()) => self.settled = true,
+            Err(AppError::Service(ServiceError::IdempotencyClaimLost)) => {
+                // The request outlived its lease, the key belongs to the
+                // request that took it over now
+                tracing::warn!(scope = %self.scope, "Idempotency key was taken over before the response was stored");
+                metrics::counter!("idempotency_claims_lost_total").increment(1);
+                self.settled = true;
+            }
+            Err(e) => {
+                tracing::error!(error = ?e, scope = %self.scope, "Failed to store idempotent response");
+                self.release().await;
+            }
+        }
+    }
+
+    async fn release(mut self) {
+        release_key(&self.state, &self.scope, &self.key, self.claimed_at).await;
+        self.settled = true;
+    }
+}
+
+impl Drop for KeyClaim {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+
+        let state = self.state.clone();
+        let scope = std::mem::take(&mut self.scope);
+        let key = std::mem::take(&mut self.key);
+        let claimed_at = self.claimed_at;
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                tracing::debug!(scope = %scope, "Releasing idempotency key of a cancelled request");
+                release_key(&state, &scope, &key, claimed_at).await;
+            });
+        }
+    }
+}
+
+async fn release_key(state: &AppState, scope: &str, key: &str, claimed_at: DateTime<Utc>) {
+    let result = match state.db_pool.acquire().await {
+        Ok(mut conn) => {
+            idempotency_logic::abandon_idempotent_request(
+                &mut conn,
+                &*state.idempotency_repo,
+                scope,
+                key,
+                claimed_at,
+            )
+            .await
+        }
+        Err(e) => Err(crate::errors::RepoError::ConnectionPool(e.to_string()).into()),
+    };
+
+    if let Err(e) = result {
+        tracing::error!(error = ?e, scope = %scope, "Failed to release idempotency key");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with_key(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn missing_key_is_not_an_error() {
+        assert_eq!(idempotency_key(&HeaderMap::new()).unwrap(), None);
+    }
+
+    #[test]
+    fn key_is_normalized_to_a_lowercase_uuid() {
+        let key = idempotency_key(&headers_with_key(" 6F1C2D3E-0000-4000-8000-00000000000A "));
+
+        assert_eq!(
+            key.unwrap().as_deref(),
+            Some("6f1c2d3e-0000-4000-8000-00000000000a")
+        );
+    }
+
+    #[test]
+    fn key_must_be_a_uuid() {
+        let err = idempotency_key(&headers_with_key("order-1")).unwrap_err();
+
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn replay_restores_status_and_content_type() {
+        let response = replay_response(StoredResponse {
+            status_code: 201,
+            content_type: Some("application/json".to_string()),
+            body: b"{}".to_vec(),
+        });
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(response.headers()["idempotent-replayed"], "true");
+    }
+}
@@ -1,7 +1,11 @@
+pub mod authorization_middleware;
+pub mod idempotency_middleware;
 pub mod logging_middleware;
 pub mod metrics_middleware;
 pub mod rate_limit_middleware;
 
+pub use authorization_middleware::{require_admin, require_operator};
+pub use idempotency_middleware::idempotency;
 pub use logging_middleware::logging;
 pub use metrics_middleware::track_metrics;
-pub use rate_limit_middleware::UserRateLimiter;
+pub use rate_limit_middleware::{RouteRateLimits, rate_limit};
@@ -1,35 +1,337 @@
-use governor::{Quota, RateLimiter, clock::DefaultClock, state::keyed::DashMapStateStore};
-use std::{num::NonZeroU32, sync::Arc};
-use uuid::Uuid;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, MatchedPath, State},
+    http::{HeaderMap, HeaderValue, Method, Request, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
 
-/// Rate limiter for user-based request limiting
-pub struct UserRateLimiter {
-    limiter: Arc<RateLimiter<Uuid, DashMapStateStore<Uuid>, DefaultClock>>,
+use crate::{
+    adapters::http::extractors::api_key_extractor::API_KEY_HEADER,
+    app::{
+        config::{RateLimitKey, RateLimitsConfig},
+        state::AppState,
+    },
+    domain::rate_limit::{RateLimitDecision, RateQuota},
+    errors::ApiError,
+    logic::api_key_logic,
+};
+
+/// Sets `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset`, the
+/// latter in whole seconds rounded up
+pub fn apply_rate_limit_headers(decision: &RateLimitDecision, headers: &mut HeaderMap) {
+    let reset = decision.reset;
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert(
+        "ratelimit-reset",
+        HeaderValue::from(reset.as_secs() + u64::from(reset.subsec_nanos() > 0)),
+    );
+}
+
+struct RouteLimit {
+    /// `METHOD /path`, the metric label
+    route: String,
+    key: RateLimitKey,
+    quota: RateQuota,
 }
 
-impl UserRateLimiter {
-    /// Create a new user rate limiter
+/// Request limits per route, from `[rate_limits]`
+pub struct RouteRateLimits {
+    trusted_proxies: Vec<IpAddr>,
+    routes: HashMap<(Method, String), RouteLimit>,
+}
+
+impl RouteRateLimits {
+    /// Routes are expected to be validated with the rest of the config
+    pub fn new(config: &RateLimitsConfig) -> Self {
+        let routes = config
+            .routes
+            .iter()
+            .filter_map(|limit| {
+                let (method, path) = limit.route.split_once(' ')?;
+                let method = Method::from_bytes(method.as_bytes()).ok()?;
+                let quota = RateQuota {
+                    per_second: limit.requests_per_second,
+                    burst: limit.burst.unwrap_or(limit.requests_per_second),
+                };
+
+                Some((
+                    (method, path.to_string()),
+                    RouteLimit {
+                        route: limit.route.clone(),
+                        key: limit.key,
+                        quota,
+                    },
+                ))
+            })
+            .collect();
+
+        Self {
+            trusted_proxies: config.trusted_proxies.clone(),
+            routes,
+        }
+    }
+
+    /// Address of the client, looking past trusted proxies
     ///
-    /// # Arguments
-    /// * `requests_per_second` - Maximum requests per second per user
-    pub fn new(requests_per_second: u32) -> Self {
-        let quota = Quota::per_second(NonZeroU32::new(requests_per_second).unwrap());
-        let limiter = Arc::new(RateLimiter::keyed(quota));
-        Self { limiter }
+    /// `X-Forwarded-For` is only believed when the peer is a trusted proxy,
+    /// and is read from the right, skipping further trusted proxies, so a
+    /// client can't pick its own address by sending the header itself
+    fn client_ip(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let peer = peer?;
+        if !self.trusted_proxies.contains(&peer) {
+            return Some(peer);
+        }
+
+        let forwarded = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+            .collect::<Vec<_>>();
+
+        Some(
+            forwarded
+                .into_iter()
+                .rev()
+                .find(|hop| !self.trusted_proxies.contains(hop))
+                .unwrap_or(peer),
+        )
     }
 
-    /// Check if a user can make a request
+    fn ip_key(&self, req: &Request<Body>) -> String {
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        match self.client_ip(peer, req.headers()) {
+            Some(ip) => format!("ip:{ip}"),
+            None => "ip:unknown".to_string(),
+        }
+    }
+
+    /// Bucket subject for requests counted by `key`
     ///
-    /// Returns true if the request is allowed, false if rate limit is exceeded
-    pub fn check(&self, user_id: Uuid) -> bool {
-        self.limiter.check_key(&user_id).is_ok()
+    /// API keys are resolved to their client by [`rate_limit`] first, this
+    /// gives the fallback for requests without a usable one.
+    fn key(&self, key: RateLimitKey, req: &Request<Body>, state: &AppState) -> String {
+        match key {
+            RateLimitKey::Ip | RateLimitKey::ApiKey => self.ip_key(req),
+            RateLimitKey::User => req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .and_then(|token| state.token_verifier.verify(token.trim()).ok())
+                .map(|user| format!("user:{}", user.user_id))
+                .unwrap_or_else(|| self.ip_key(req)),
+        }
     }
 }
 
-impl Clone for UserRateLimiter {
-    fn clone(&self) -> Self {
-        Self {
-            limiter: Arc::clone(&self.limiter),
+/// Client of a usable `X-Api-Key`, so rotating made-up keys can't dodge the
+/// limit; `None` for missing, unknown and unusable keys, or when the lookup
+/// fails
+async fn api_client_id(presented: Option<String>, state: &AppState) -> Option<uuid::Uuid> {
+    let presented = presented?;
+    let mut conn = state.db_pool.acquire().await.ok()?;
+
+    api_key_logic::authenticate_api_key(&mut conn, &*state.api_key_repo, &presented)
+        .await
+        .ok()
+        .map(|api_key| api_key.client_id)
+}
+
+/// Applies the limit configured for the matched route, if any
+///
+/// Limited routes report their budget in `RateLimit-*` headers; refused
+/// requests get 429 with `Retry-After` before reaching the handler
+pub async fn rate_limit(State(state): State<AppState>, req: Request<Body>, next: Next) -> Response {
+    let limits = Arc::clone(&state.route_rate_limits);
+    let Some(limit) = req.extensions().get::<MatchedPath>().and_then(|path| {
+        limits
+            .routes
+            .get(&(req.method().clone(), path.as_str().to_string()))
+    }) else {
+        return next.run(req).await;
+    };
+
+    let fallback = limits.key(limit.key, &req, &state);
+    let subject = match limit.key {
+        RateLimitKey::ApiKey => {
+            let presented = req
+                .headers()
+                .get(API_KEY_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string());
+            match api_client_id(presented, &state).await {
+                Some(client_id) => format!("api_client:{client_id}"),
+                None => fallback,
+            }
+        }
+        _ => fallback,
+    };
+    // Keys are per route, so limited routes don't share a budget
+    let key = format!("route:{}:{}", limit.route, subject);
+    let decision = state.rate_limiter.check(&key, limit.quota).await;
+
+    let mut response = match decision.into_result() {
+        Ok(()) => next.run(req).await,
+        Err(e) => {
+            metrics::counter!(
+                "route_rate_limit_rejections_total",
+                "route" => limit.route.clone(),
+                "key" => limit.key.as_str()
+            )
+            .increment(1);
+            ApiError::from(e).into_response()
         }
+    };
+    apply_rate_limit_headers(&decision, response.headers_mut());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::config::RouteRateLimitConfig;
+
+    const PROXY: &str = "10.0.0.1";
+    const EDGE_PROXY: &str = "10.0.0.2";
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn limits(trusted_proxies: &[&str], routes: Vec<RouteRateLimitConfig>) -> RouteRateLimits {
+        RouteRateLimits::new(&RateLimitsConfig {
+            trusted_proxies: trusted_proxies.iter().map(|proxy| ip(proxy)).collect(),
+            routes,
+            ..RateLimitsConfig::default()
+        })
+    }
+
+    fn forwarded_for(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn untrusted_peers_cannot_pick_their_address() {
+        let limits = limits(&[PROXY], Vec::new());
+
+        assert_eq!(
+            limits.client_ip(Some(ip("203.0.113.7")), &forwarded_for(&["198.51.100.1"])),
+            Some(ip("203.0.113.7"))
+        );
+        assert_eq!(
+            limits.client_ip(None, &forwarded_for(&["198.51.100.1"])),
+            None
+        );
+    }
+
+    #[test]
+    fn trusted_proxy_hands_over_the_last_hop_it_saw() {
+        let limits = limits(&[PROXY], Vec::new());
+
+        // The client prepended a made-up address, the proxy appended the real one
+        assert_eq!(
+            limits.client_ip(
+                Some(ip(PROXY)),
+                &forwarded_for(&["198.51.100.1, 203.0.113.7"])
+            ),
+            Some(ip("203.0.113.7"))
+        );
+    }
+
+    #[test]
+    fn chained_trusted_proxies_are_skipped() {
+        let limits = limits(&[PROXY, EDGE_PROXY], Vec::new());
+
+        assert_eq!(
+            limits.client_ip(
+                Some(ip(PROXY)),
+                &forwarded_for(&["198.51.100.1, 203.0.113.7", EDGE_PROXY])
+            ),
+            Some(ip("203.0.113.7"))
+        );
+    }
+
+    #[test]
+    fn unusable_hops_fall_back_to_the_proxy() {
+        let limits = limits(&[PROXY], Vec::new());
+
+        assert_eq!(
+            limits.client_ip(Some(ip(PROXY)), &forwarded_for(&["unknown, not-an-ip"])),
+            Some(ip(PROXY))
+        );
+        assert_eq!(
+            limits.client_ip(Some(ip(PROXY)), &HeaderMap::new()),
+            Some(ip(PROXY))
+        );
+        assert_eq!(
+            limits.client_ip(Some(ip(PROXY)), &forwarded_for(&[PROXY])),
+            Some(ip(PROXY))
+        );
+    }
+
+    #[test]
+    fn ipv6_hops_are_understood() {
+        let limits = limits(&[PROXY], Vec::new());
+
+        assert_eq!(
+            limits.client_ip(Some(ip(PROXY)), &forwarded_for(&[" 2001:db8::7 "])),
+            Some(ip("2001:db8::7"))
+        );
+    }
+
+    #[test]
+    fn routes_are_keyed_by_method_and_path() {
+        let limits = limits(
+            &[],
+            vec![
+                RouteRateLimitConfig {
+                    route: "POST /users".to_string(),
+                    key: RateLimitKey::Ip,
+                    requests_per_second: 2,
+                    burst: None,
+                },
+                RouteRateLimitConfig {
+                    route: "GET /orders/{order_id}".to_string(),
+                    key: RateLimitKey::User,
+                    requests_per_second: 10,
+                    burst: Some(30),
+                },
+            ],
+        );
+
+        let users = &limits.routes[&(Method::POST, "/users".to_string())];
+        assert_eq!(users.key, RateLimitKey::Ip);
+        assert_eq!(users.quota, RateQuota::per_second(2));
+
+        let order = &limits.routes[&(Method::GET, "/orders/{order_id}".to_string())];
+        assert_eq!(order.route, "GET /orders/{order_id}");
+        assert_eq!(
+            order.quota,
+            RateQuota {
+                per_second: 10,
+                burst: 30
+            }
+        );
+        assert!(
+            !limits
+                .routes
+                .contains_key(&(Method::GET, "/users".to_string()))
+        );
     }
 }
@@ -1,4 +1,5 @@
 pub mod dtos;
+pub mod extractors;
 pub mod handlers;
 pub mod middleware;
 pub mod router;
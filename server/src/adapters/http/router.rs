@@ -1,15 +1,36 @@
-use axum::{Router, extract::State, routing::get};
+use std::time::Duration;
 
-use crate::adapters::http::{middleware::logging, routes};
-use crate::app::state::AppState;
+use axum::{
+    Router, extract::State, http::StatusCode, middleware::from_fn_with_state, routing::get,
+};
 
-pub fn http_router(state: AppState) -> Router {
-    Router::new()
+use crate::adapters::http::{
+    handlers,
+    middleware::{logging, require_operator},
+    routes,
+};
+use crate::app::state::{AppState, WorkerState};
+
+/// `request_timeout` bounds each request, expiring ones get 503
+///
+/// An expired request is dropped mid-flight; the idempotency middleware
+/// releases the key it claimed so the client can retry
+pub fn http_router(state: AppState, request_timeout: Duration) -> Router {
+    // Metrics carry per-sale and per-guard labels, so only operators scrape them
+    let metrics = Router::new()
         .route(
             "/metrics",
             get(|State(state): State<AppState>| async move { state.prometheus_handle.render() }),
         )
-        .merge(routes::routes())
+        .route_layer(from_fn_with_state(state.clone(), require_operator));
+
+    Router::new()
+        .merge(metrics)
+        .merge(routes::routes(state.clone()))
+        .layer(tower_http::timeout::TimeoutLayer::with_status_code(
+            StatusCode::SERVICE_UNAVAILABLE,
+            request_timeout,
+        ))
         .layer(axum::middleware::from_fn(
             crate::adapters::http::middleware::track_metrics,
         ))
@@ -20,3 +41,20 @@ pub fn http_router(state: AppState) -> Router {
         ))
         .with_state(state)
 }
+
+/// Routes of a worker process, which has no API of its own
+pub fn worker_router(state: WorkerState) -> Router {
+    let metrics = Router::new()
+        .route(
+            "/metrics",
+            get(|State(state): State<WorkerState>| async move { state.prometheus_handle.render() }),
+        )
+        .route_layer(from_fn_with_state(state.clone(), require_operator));
+
+    Router::new()
+        .route("/health", get(handlers::health_handler::liveness))
+        .route("/health/live", get(handlers::health_handler::liveness))
+        .route("/health/ready", get(handlers::health_handler::readiness))
+        .merge(metrics)
+        .with_state(state)
+}
@@ -1,23 +1,151 @@
 use axum::{
     Router,
-    routing::{get, post},
+    middleware::from_fn_with_state,
+    routing::{delete, get, patch, post, put},
 };
 
-use crate::adapters::http::handlers;
+use crate::adapters::http::{handlers, middleware};
 use crate::app::state::AppState;
 
-pub fn routes() -> Router<AppState> {
+pub fn routes(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", get(handlers::health_handler::hello_world))
-        .route("/health", get(handlers::health_handler::health_check))
-        .route("/users", post(handlers::user_handler::create_user))
-        .route("/users", get(handlers::user_handler::get_users))
-        .route("/users/{id}", get(handlers::user_handler::get_user_by_id))
-        .route("/products", post(handlers::product_handler::create_product))
+        .route("/health", get(handlers::health_handler::liveness))
+        .route("/health/live", get(handlers::health_handler::liveness))
+        .route("/health/ready", get(handlers::health_handler::readiness))
+        .route(
+            "/users/{id}",
+            get(handlers::user_handler::get_user_by_id).patch(handlers::user_handler::update_user),
+        )
         .route("/products", get(handlers::product_handler::get_products))
+        .route(
+            "/products/search",
+            get(handlers::product_handler::search_products),
+        )
+        .route(
+            "/products/{id}",
+            get(handlers::product_handler::get_product),
+        )
+        .route(
+            "/flash-sales",
+            get(handlers::flash_sale_handler::get_flash_sales),
+        )
+        .route(
+            "/flash-sales/{id}",
+            get(handlers::flash_sale_handler::get_flash_sale),
+        )
+        .route(
+            "/flash-sales/{id}/challenge",
+            get(handlers::flash_sale_handler::get_flash_sale_challenge),
+        )
         .route("/orders", post(handlers::order_handler::create_order))
+        .route(
+            "/users/{id}/orders",
+            get(handlers::order_handler::get_user_orders),
+        )
+        .route(
+            "/orders/{order_id}",
+            get(handlers::order_handler::get_order),
+        )
         .route(
             "/orders/{order_id}/status",
             get(handlers::order_handler::get_order_status),
         )
+        .merge(idempotent_routes(state.clone()))
+        .merge(operator_routes(state.clone()))
+        .merge(admin_routes(state.clone()))
+        // Outermost route layer, so limited requests skip auth and idempotency
+        .route_layer(from_fn_with_state(state, middleware::rate_limit))
+}
+
+/// Mutating routes made retry-safe by the idempotency middleware
+///
+/// `POST /orders` is not listed here, it tracks its own Idempotency-Key
+fn idempotent_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/users", post(handlers::user_handler::create_user))
+        .route_layer(from_fn_with_state(state, middleware::idempotency))
+}
+
+/// Catalog management and customer data, restricted to operators and admins
+fn operator_routes(state: AppState) -> Router<AppState> {
+    let idempotent = Router::new()
+        .route("/products", post(handlers::product_handler::create_product))
+        .route(
+            "/flash-sales",
+            post(handlers::flash_sale_handler::create_flash_sale),
+        )
+        .route_layer(from_fn_with_state(state.clone(), middleware::idempotency));
+
+    // Authorization is the outer layer so rejected callers never claim a key
+    Router::new()
+        .route("/users", get(handlers::user_handler::get_users))
+        .route(
+            "/products/{id}",
+            patch(handlers::product_handler::update_product),
+        )
+        .route(
+            "/products/{id}/archive",
+            post(handlers::product_handler::archive_product),
+        )
+        .route(
+            "/flash-sales/{id}/admission",
+            put(handlers::flash_sale_handler::update_flash_sale_admission),
+        )
+        .route(
+            "/flash-sales/{id}/guards",
+            put(handlers::flash_sale_handler::update_flash_sale_guards),
+        )
+        .route(
+            "/flash-sales/{id}/allowlist",
+            post(handlers::order_guard_handler::allow_flash_sale_users),
+        )
+        .route(
+            "/flash-sales/{id}/allowlist/{user_id}",
+            delete(handlers::order_guard_handler::disallow_flash_sale_user),
+        )
+        .route(
+            "/users/lookup",
+            get(handlers::user_handler::get_user_by_email),
+        )
+        .merge(idempotent)
+        .route_layer(from_fn_with_state(state, middleware::require_operator))
+}
+
+/// Partner API keys, runtime settings and the order blocklist, restricted to admins
+fn admin_routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/api-keys",
+            post(handlers::api_key_handler::issue_api_key)
+                .get(handlers::api_key_handler::get_api_keys),
+        )
+        .route(
+            "/admin/api-keys/{id}",
+            delete(handlers::api_key_handler::revoke_api_key),
+        )
+        .route(
+            "/admin/api-keys/{id}/rotate",
+            post(handlers::api_key_handler::rotate_api_key),
+        )
+        .route(
+            "/admin/api-clients/{client_id}/users/{user_id}",
+            put(handlers::api_key_handler::link_partner_user)
+                .delete(handlers::api_key_handler::unlink_partner_user),
+        )
+        .route(
+            "/admin/settings",
+            get(handlers::settings_handler::get_settings)
+                .patch(handlers::settings_handler::update_settings),
+        )
+        .route(
+            "/admin/settings/audit",
+            get(handlers::settings_handler::get_settings_audit),
+        )
+        .route(
+            "/admin/blocked-users/{user_id}",
+            put(handlers::order_guard_handler::block_user)
+                .delete(handlers::order_guard_handler::unblock_user),
+        )
+        .route_layer(from_fn_with_state(state, middleware::require_admin))
 }
@@ -0,0 +1,202 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+use crate::app::config::AdaptiveAdmissionConfig;
+
+struct LimitState {
+    limit: f64,
+    last_decrease: Option<Instant>,
+}
+
+/// Queue depth at which admission stops, found from how orders fare
+///
+/// Additive increase, multiplicative decrease: every order placed within the
+/// latency target raises the limit by `1 / limit`, so about one per `limit`
+/// orders, while an order over the target or failing on the database cuts it
+/// to `backoff_ratio` of its value, at most once per cooldown so one slow
+/// batch doesn't collapse it. The limit starts at the queue capacity and
+/// stays between `min_limit` and that capacity.
+pub struct AdaptiveAdmission {
+    config: AdaptiveAdmissionConfig,
+    max_limit: usize,
+    state: Mutex<LimitState>,
+    /// Whole part of the limit, read on every admission without locking
+    current: AtomicUsize,
+}
+
+impl AdaptiveAdmission {
+    pub fn new(config: AdaptiveAdmissionConfig, queue_capacity: usize) -> Self {
+        metrics::gauge!("adaptive_admission_limit").set(queue_capacity as f64);
+
+        Self {
+            config,
+            max_limit: queue_capacity,
+            state: Mutex::new(LimitState {
+                limit: queue_capacity as f64,
+                last_decrease: None,
+            }),
+            current: AtomicUsize::new(queue_capacity),
+        }
+    }
+
+    /// Queued orders at which admission currently stops
+    pub fn limit(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// Feeds back how long one order took and whether the database failed it
+    ///
+    /// Orders that failed for business reasons, such as a sold out sale,
+    /// count by their latency like successful ones.
+    pub fn record(&self, latency: Duration, database_failed: bool) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let congested =
+            database_failed || latency > Duration::from_millis(self.config.latency_target_ms);
+
+        if congested {
+            let cooling_down = state.last_decrease.is_some_and(|at| {
+                now.duration_since(at) < Duration::from_millis(self.config.decrease_cooldown_ms)
+            });
+            if cooling_down {
+                return;
+            }
+            state.limit =
+                (state.limit * self.config.backoff_ratio).max(self.config.min_limit as f64);
+            state.last_decrease = Some(now);
+            debug!(
+                limit = state.limit,
+                latency_ms = latency.as_millis() as u64,
+                database_failed,
+                "Admission limit lowered"
+            );
+        } else {
+            state.limit = (state.limit + 1.0 / state.limit).min(self.max_limit as f64);
+        }
+
+        let limit = state.limit as usize;
+        if self.current.swap(limit, Ordering::Relaxed) != limit {
+            metrics::gauge!("adaptive_admission_limit").set(limit as f64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: Duration = Duration::from_millis(100);
+    const FAST: Duration = Duration::from_millis(10);
+    const SLOW: Duration = Duration::from_millis(500);
+
+    fn admission(decrease_cooldown_ms: u64) -> AdaptiveAdmission {
+        AdaptiveAdmission::new(
+            AdaptiveAdmissionConfig {
+                enabled: true,
+                min_limit: 10,
+                latency_target_ms: TARGET.as_millis() as u64,
+                backoff_ratio: 0.5,
+                decrease_cooldown_ms,
+            },
+            100,
+        )
+    }
+
+    #[test]
+    fn starts_at_queue_capacity() {
+        assert_eq!(admission(0).limit(), 100);
+    }
+
+    #[test]
+    fn slow_order_cuts_limit() {
+        let admission = admission(0);
+        admission.record(SLOW, false);
+
+        assert_eq!(admission.limit(), 50);
+    }
+
+    #[test]
+    fn database_failure_cuts_limit() {
+        let admission = admission(0);
+        admission.record(FAST, true);
+
+        assert_eq!(admission.limit(), 50);
+    }
+
+    #[test]
+    fn never_drops_below_min_limit() {
+        let admission = admission(0);
+        for _ in 0..10 {
+            admission.record(SLOW, false);
+        }
+
+        assert_eq!(admission.limit(), 10);
+    }
+
+    #[test]
+    fn fast_orders_raise_limit_by_about_one_per_limit() {
+        let admission = admission(0);
+        admission.record(SLOW, false);
+
+        for _ in 0..50 {
+            admission.record(FAST, false);
+        }
+        assert_eq!(admission.limit(), 50);
+        admission.record(FAST, false);
+        assert_eq!(admission.limit(), 51);
+    }
+
+    #[test]
+    fn never_rises_above_queue_capacity() {
+        let admission = admission(0);
+        for _ in 0..1_000 {
+            admission.record(FAST, false);
+        }
+
+        assert_eq!(admission.limit(), 100);
+    }
+
+    #[test]
+    fn cooldown_holds_off_further_cuts() {
+        let admission = admission(60_000);
+        admission.record(SLOW, false);
+        admission.record(SLOW, false);
+        admission.record(FAST, true);
+
+        assert_eq!(admission.limit(), 50);
+    }
+
+    #[test]
+    fn cuts_again_after_cooldown() {
+        let admission = admission(10);
+        admission.record(SLOW, false);
+        std::thread::sleep(Duration::from_millis(20));
+        admission.record(SLOW, false);
+
+        assert_eq!(admission.limit(), 25);
+    }
+
+    #[test]
+    fn disabled_keeps_limit() {
+        let admission = AdaptiveAdmission::new(
+            AdaptiveAdmissionConfig {
+                enabled: false,
+                ..AdaptiveAdmissionConfig::default()
+            },
+            100,
+        );
+        admission.record(SLOW, true);
+
+        assert_eq!(admission.limit(), 100);
+    }
+}
@@ -0,0 +1,481 @@
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+use crate::{
+    app::config::CircuitBreakerConfig,
+    errors::{AppError, ServiceError},
+};
+
+/// Where a breaker stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through, outcomes are counted per window
+    Closed,
+    /// Calls are refused until the open period ends
+    Open,
+    /// A few trial calls go through to see if the dependency recovered
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half_open",
+        }
+    }
+
+    /// Value of the `circuit_breaker_state` gauge
+    fn gauge_value(&self) -> f64 {
+        match self {
+            Self::Closed => 0.0,
+            Self::HalfOpen => 1.0,
+            Self::Open => 2.0,
+        }
+    }
+}
+
+struct Inner {
+    state: CircuitState,
+    window_started: Instant,
+    calls: u32,
+    failures: u32,
+    /// When an open breaker lets trial calls through again
+    open_until: Instant,
+    /// Trial calls started and succeeded while half-open
+    trials_started: u32,
+    trials_succeeded: u32,
+    /// Counts half-open periods, so a late permit can't free a newer trial
+    half_open_period: u64,
+}
+
+/// Stops calling a failing dependency for a while instead of waiting on it
+///
+/// While closed, outcomes are counted in fixed windows; once a window holds
+/// at least `min_calls` calls and the share of failures reaches
+/// `failure_rate_threshold` the breaker opens. After `open_duration_ms` it
+/// turns half-open and lets `half_open_max_calls` trial calls through: if
+/// all succeed it closes, the first failure opens it again.
+pub struct CircuitBreaker {
+    name: &'static str,
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &'static str, config: CircuitBreakerConfig) -> Self {
+        let now = Instant::now();
+        metrics::gauge!("circuit_breaker_state", "breaker" => name)
+            .set(CircuitState::Closed.gauge_value());
+
+        Self {
+            name,
+            config,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                window_started: now,
+                calls: 0,
+                failures: 0,
+                open_until: now,
+                trials_started: 0,
+                trials_succeeded: 0,
+                half_open_period: 0,
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Current state, an open breaker past its open period reads as half-open
+    pub fn state(&self) -> CircuitState {
+        let inner = self.lock();
+        match inner.state {
+            CircuitState::Open if Instant::now() >= inner.open_until => CircuitState::HalfOpen,
+            state => state,
+        }
+    }
+
+    /// How long callers should wait before trying again, `None` unless open
+    pub fn retry_after(&self) -> Option<Duration> {
+        let inner = self.lock();
+        let now = Instant::now();
+        (inner.state == CircuitState::Open && now < inner.open_until)
+            .then(|| inner.open_until - now)
+    }
+
+    /// Fails fast while the breaker is open, without taking a trial call
+    ///
+    /// For callers that only feed work to the dependency later, such as
+    /// admission in front of the order queue.
+    pub fn reject_if_open(&self) -> Result<(), AppError> {
+        match self.retry_after() {
+            Some(retry_after) => Err(self.rejected(retry_after)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with [`ServiceError::CircuitOpen`] when no call may be made now
+    ///
+    /// The outcome of a permitted call is counted with [`CallPermit::record`].
+    pub fn try_acquire(&self) -> Result<CallPermit<'_>, AppError> {
+        let mut inner = self.lock();
+        let now = Instant::now();
+
+        if inner.state == CircuitState::Open {
+            if now < inner.open_until {
+                return Err(self.rejected(inner.open_until - now));
+            }
+            self.transition(&mut inner, CircuitState::HalfOpen, now);
+        }
+
+        if inner.state == CircuitState::HalfOpen {
+            if inner.trials_started >= self.config.half_open_max_calls {
+                // Trials still running, wait a little for their outcome
+                return Err(self.rejected(Duration::from_secs(1)));
+            }
+            inner.trials_started += 1;
+            return Ok(CallPermit {
+                breaker: self,
+                trial: Some(inner.half_open_period),
+            });
+        }
+
+        Ok(CallPermit {
+            breaker: self,
+            trial: None,
+        })
+    }
+
+    /// Counts the outcome of a call permitted by [`CircuitBreaker::try_acquire`]
+    fn record(&self, success: bool) {
+        let mut inner = self.lock();
+        let now = Instant::now();
+
+        match inner.state {
+            CircuitState::Closed => {
+                if now.duration_since(inner.window_started) >= self.window() {
+                    inner.window_started = now;
+                    inner.calls = 0;
+                    inner.failures = 0;
+                }
+                inner.calls += 1;
+                if !success {
+                    inner.failures += 1;
+                }
+
+                let failure_rate = inner.failures as f64 / inner.calls as f64;
+                if inner.calls >= self.config.min_calls
+                    && failure_rate >= self.config.failure_rate_threshold
+                {
+                    warn!(
+                        breaker = self.name,
+                        calls = inner.calls,
+                        failures = inner.failures,
+                        "Circuit breaker opening"
+                    );
+                    self.transition(&mut inner, CircuitState::Open, now);
+                }
+            }
+            CircuitState::HalfOpen if !success => {
+                warn!(breaker = self.name, "Circuit breaker trial call failed");
+                self.transition(&mut inner, CircuitState::Open, now);
+            }
+            CircuitState::HalfOpen => {
+                inner.trials_succeeded += 1;
+                if inner.trials_succeeded >= self.config.half_open_max_calls {
+                    info!(breaker = self.name, "Circuit breaker closing");
+                    self.transition(&mut inner, CircuitState::Closed, now);
+                }
+            }
+            // Calls started before the breaker opened, nothing to learn from them
+            CircuitState::Open => {}
+        }
+    }
+
+    /// Gives back the trial of a permit dropped before its call finished
+    fn release_trial(&self, half_open_period: u64) {
+        let mut inner = self.lock();
+        if inner.state == CircuitState::HalfOpen && inner.half_open_period == half_open_period {
+            inner.trials_started = inner.trials_started.saturating_sub(1);
+        }
+    }
+
+    /// Runs `call` through the breaker
+    ///
+    /// Only errors for which `is_failure` holds count against the dependency,
+    /// so business outcomes such as a sold out sale don't trip it.
+    pub async fn call<T, F>(
+        &self,
+        call: F,
+        is_failure: impl Fn(&AppError) -> bool,
+    ) -> Result<T, AppError>
+    where
+        F: Future<Output = Result<T, AppError>>,
+    {
+        let permit = self.try_acquire()?;
+
+        let result = call.await;
+        permit.record(!matches!(&result, Err(e) if is_failure(e)));
+
+        result
+    }
+
+    fn transition(&self, inner: &mut Inner, to: CircuitState, now: Instant) {
+        inner.state = to;
+        match to {
+            CircuitState::Closed => {
+                inner.window_started = now;
+                inner.calls = 0;
+                inner.failures = 0;
+            }
+            CircuitState::Open => {
+                inner.open_until = now + Duration::from_millis(self.config.open_duration_ms);
+            }
+            CircuitState::HalfOpen => {
+                inner.trials_started = 0;
+                inner.trials_succeeded = 0;
+                inner.half_open_period += 1;
+            }
+        }
+
+        metrics::gauge!("circuit_breaker_state", "breaker" => self.name).set(to.gauge_value());
+        metrics::counter!(
+            "circuit_breaker_transitions_total",
+            "breaker" => self.name,
+            "to" => to.as_str()
+        )
+        .increment(1);
+    }
+
+    fn rejected(&self, retry_after: Duration) -> AppError {
+        metrics::counter!("circuit_breaker_rejections_total", "breaker" => self.name).increment(1);
+        AppError::Service(ServiceError::CircuitOpen {
+            dependency: self.name,
+            retry_after,
+        })
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_millis(self.config.window_ms)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Leave to make one call through a [`CircuitBreaker`]
+///
+/// A permit dropped without [`CallPermit::record`], as when a request is
+/// cancelled or times out mid-call, counts no outcome and gives its half-open
+/// trial back, so abandoned trials can't keep the breaker half-open for good.
+#[must_use = "the call's outcome is counted with `record`"]
+pub struct CallPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    /// Half-open period of the trial this permit holds, if any
+    trial: Option<u64>,
+}
+
+impl CallPermit<'_> {
+    /// Counts the outcome of the permitted call
+    pub fn record(mut self, success: bool) {
+        self.trial = None;
+        self.breaker.record(success);
+    }
+}
+
+impl Drop for CallPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(half_open_period) = self.trial {
+            self.breaker.release_trial(half_open_period);
+        }
+    }
+}
+
+/// Errors that say the database itself is unwell, as opposed to a rejected
+/// write or a missing row
+pub fn is_database_failure(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::Repo(
+            crate::errors::RepoError::Transaction(_)
+                | crate::errors::RepoError::ConnectionPool(_)
+                | crate::errors::RepoError::Database { .. }
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPEN_MS: u64 = 20;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(
+            "test",
+            CircuitBreakerConfig {
+                failure_rate_threshold: 0.5,
+                min_calls: 4,
+                window_ms: 60_000,
+                open_duration_ms: OPEN_MS,
+                half_open_max_calls: 2,
+            },
+        )
+    }
+
+    fn fail(breaker: &CircuitBreaker, calls: u32) {
+        for _ in 0..calls {
+            breaker.try_acquire().unwrap().record(false);
+        }
+    }
+
+    fn wait_out_open_period() {
+        std::thread::sleep(Duration::from_millis(OPEN_MS + 10));
+    }
+
+    #[test]
+    fn stays_closed_below_min_calls() {
+        let breaker = breaker();
+        fail(&breaker, 3);
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.reject_if_open().is_ok());
+    }
+
+    #[test]
+    fn stays_closed_below_failure_rate() {
+        let breaker = breaker();
+        for _ in 0..3 {
+            breaker.try_acquire().unwrap().record(true);
+        }
+        fail(&breaker, 2);
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn opens_at_failure_rate_and_rejects() {
+        let breaker = breaker();
+        fail(&breaker, 4);
+
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.retry_after().is_some());
+        assert!(matches!(
+            breaker.try_acquire(),
+            Err(AppError::Service(ServiceError::CircuitOpen {
+                dependency: "test",
+                ..
+            }))
+        ));
+        assert!(breaker.reject_if_open().is_err());
+    }
+
+    #[test]
+    fn half_open_after_open_period_limits_trials() {
+        let breaker = breaker();
+        fail(&breaker, 4);
+        wait_out_open_period();
+
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(breaker.retry_after(), None);
+        let first = breaker.try_acquire().unwrap();
+        let second = breaker.try_acquire().unwrap();
+        assert!(breaker.try_acquire().is_err());
+        first.record(true);
+        second.record(true);
+    }
+
+    #[tokio::test]
+    async fn dropped_trial_call_frees_its_slot() {
+        let breaker = breaker();
+        fail(&breaker, 4);
+        wait_out_open_period();
+
+        // Both trials are cancelled mid-call, as by a request timeout
+        for _ in 0..2 {
+            let pending = breaker.call(
+                std::future::pending::<Result<(), AppError>>(),
+                is_database_failure,
+            );
+            let timed_out = tokio::time::timeout(Duration::from_millis(1), pending).await;
+            assert!(timed_out.is_err());
+        }
+
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        for _ in 0..2 {
+            breaker.try_acquire().unwrap().record(true);
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn late_permit_does_not_free_a_newer_trial() {
+        let breaker = breaker();
+        fail(&breaker, 4);
+        wait_out_open_period();
+
+        let stale = breaker.try_acquire().unwrap();
+        breaker.try_acquire().unwrap().record(false);
+        wait_out_open_period();
+
+        let first = breaker.try_acquire().unwrap();
+        let second = breaker.try_acquire().unwrap();
+        drop(stale);
+        assert!(breaker.try_acquire().is_err());
+        first.record(true);
+        second.record(true);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn closes_after_successful_trials() {
+        let breaker = breaker();
+        fail(&breaker, 4);
+        wait_out_open_period();
+
+        for _ in 0..2 {
+            breaker.try_acquire().unwrap().record(true);
+        }
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        // The failure window starts over once closed
+        fail(&breaker, 3);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn failed_trial_opens_again() {
+        let breaker = breaker();
+        fail(&breaker, 4);
+        wait_out_open_period();
+
+        breaker.try_acquire().unwrap().record(false);
+
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.try_acquire().is_err());
+    }
+
+    #[tokio::test]
+    async fn call_counts_only_dependency_failures() {
+        let breaker = breaker();
+        for _ in 0..4 {
+            let result: Result<(), AppError> = breaker
+                .call(
+                    async { Err(AppError::Service(ServiceError::SoldOut)) },
+                    is_database_failure,
+                )
+                .await;
+            assert!(result.is_err());
+        }
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
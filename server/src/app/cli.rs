@@ -0,0 +1,166 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::{
+    adapters::{auth::JwtTokenVerifier, db},
+    app::{
+        config::Config,
+        runtime::{self, Role},
+        seed::{SeedOptions, seed},
+    },
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "flash-sale", version, about = "Flash sale order service")]
+pub struct Cli {
+    /// Config file, takes the place of CONFIG_FILE and ./config.toml
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP API and hand orders to separate worker processes
+    Serve,
+    /// Run only the order worker, consuming orders handed off by `serve`
+    Worker,
+    /// Run the HTTP API and the order worker in one process (the default)
+    All,
+    /// Apply pending database migrations
+    Migrate,
+    /// Create users and flash sales for local testing and load tests
+    Seed {
+        #[arg(long, default_value_t = 100)]
+        users: u32,
+        #[arg(long, default_value_t = 1)]
+        sales: u32,
+        /// Units on offer in each sale
+        #[arg(long, default_value_t = 50)]
+        inventory: i32,
+        #[arg(long, default_value_t = 1)]
+        per_user_limit: i32,
+        /// Delay before the sales open
+        #[arg(long, default_value_t = 5)]
+        starts_in_secs: i64,
+        #[arg(long, default_value_t = 60)]
+        duration_mins: i64,
+        /// Instead of creating data, remove the sale, product and users the
+        /// old seed data migration inserted
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Validate the configuration and print it with secrets redacted
+    CheckConfig,
+}
+
+impl Cli {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let config = Config::load_from(self.config.as_deref())?;
+
+        match self.command.unwrap_or(Command::All) {
+            Command::Serve => runtime::run(config, Role::Api).await,
+            Command::Worker => runtime::run(config, Role::Worker).await,
+            Command::All => runtime::run(config, Role::All).await,
+            Command::Migrate => {
+                let pool = db::pool::create_pool(&config.database).await?;
+                db::migrate::run_migrations(&pool).await?;
+                println!("Migrations applied");
+                Ok(())
+            }
+            Command::Seed {
+                users,
+                sales,
+                inventory,
+                per_user_limit,
+                starts_in_secs,
+                duration_mins,
+                remove,
+            } => {
+                let pool = db::pool::create_pool(&config.database).await?;
+                if remove {
+                    let removed = db::legacy_seed::remove_legacy_seed(&pool).await?;
+                    println!(
+                        "Removed {} flash sales, {} products, {} users, {} orders and {} order jobs",
+                        removed.flash_sales,
+                        removed.products,
+                        removed.users,
+                        removed.orders,
+                        removed.order_jobs
+                    );
+                    return Ok(());
+                }
+
+                let options = SeedOptions {
+                    users,
+                    sales,
+                    inventory,
+                    per_user_limit,
+                    starts_in: chrono::Duration::seconds(starts_in_secs),
+                    duration: chrono::Duration::minutes(duration_mins),
+                };
+                let summary = seed(&pool, &options).await?;
+
+                println!("Created {} users", summary.user_ids.len());
+                for id in &summary.flash_sale_ids {
+                    println!("Created flash sale {id}");
+                }
+                Ok(())
+            }
+            Command::CheckConfig => {
+                JwtTokenVerifier::from_config(&config.jwt)?;
+                println!("{}", config.redacted());
+                println!("Configuration is valid");
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn arguments_are_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn runs_everything_when_no_command_is_given() {
+        let cli = Cli::try_parse_from(["flash-sale"]).unwrap();
+
+        assert!(cli.command.is_none());
+        assert!(cli.config.is_none());
+    }
+
+    #[test]
+    fn config_is_accepted_after_the_command() {
+        let cli = Cli::try_parse_from(["flash-sale", "worker", "--config", "prod.toml"]).unwrap();
+
+        assert!(matches!(cli.command, Some(Command::Worker)));
+        assert_eq!(cli.config, Some(PathBuf::from("prod.toml")));
+    }
+
+    #[test]
+    fn seed_options_default_to_a_small_local_sale() {
+        let cli = Cli::try_parse_from(["flash-sale", "seed", "--users", "10"]).unwrap();
+
+        let Some(Command::Seed {
+            users,
+            sales,
+            inventory,
+            per_user_limit,
+            remove,
+            ..
+        }) = cli.command
+        else {
+            panic!("expected the seed command, got {:?}", cli.command);
+        };
+        assert_eq!((users, sales, inventory, per_user_limit), (10, 1, 50, 1));
+        assert!(!remove);
+    }
+}
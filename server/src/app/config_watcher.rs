@@ -0,0 +1,77 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use crate::{
+    app::{config::Config, runtime_settings::RuntimeSettingsStore},
+    domain::runtime_settings::{RuntimeSettings, RuntimeSettingsPatch, SettingsChangeSource},
+    ports::SettingsAuditRepo,
+};
+
+/// Polls the config file and applies edits to the runtime settings
+///
+/// The file is reloaded through [`Config::load_from`], so environment variables
+/// still win and an invalid file is rejected as a whole. Only settings edited
+/// in the file are applied, values changed through the admin API are kept
+/// otherwise. Settings outside [`RuntimeSettings`] need a restart.
+pub fn spawn_config_watcher(
+    path: PathBuf,
+    loaded: RuntimeSettings,
+    interval: Duration,
+    db_pool: sqlx::PgPool,
+    audit_repo: Arc<dyn SettingsAuditRepo>,
+    settings: Arc<RuntimeSettingsStore>,
+) {
+    tokio::spawn(async move {
+        let modified_at = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut last_modified = modified_at(&path);
+        let mut loaded = loaded;
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            let modified = modified_at(&path);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            let config = match Config::load_from(Some(&path)) {
+                Ok(config) => config,
+                Err(e) => {
+                    metrics::counter!("config_reload_failures_total").increment(1);
+                    tracing::error!(error = ?e, "Ignoring changed config file {}", path.display());
+                    continue;
+                }
+            };
+
+            let from_file = config.runtime_settings();
+            let patch = RuntimeSettingsPatch::diff(&loaded, &from_file);
+
+            let result = match db_pool.acquire().await {
+                Ok(mut conn) => {
+                    settings
+                        .update(
+                            &mut conn,
+                            audit_repo.as_ref(),
+                            patch,
+                            SettingsChangeSource::ConfigFile,
+                            None,
+                        )
+                        .await
+                }
+                Err(e) => Err(crate::errors::RepoError::ConnectionPool(e.to_string()).into()),
+            };
+
+            match result {
+                Ok(_) => {
+                    loaded = from_file;
+                    tracing::info!("Config file {} reloaded", path.display());
+                }
+                Err(e) => {
+                    metrics::counter!("config_reload_failures_total").increment(1);
+                    tracing::error!(error = ?e, "Failed to apply changed config file {}", path.display());
+                }
+            }
+        }
+    });
+}
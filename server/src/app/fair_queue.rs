@@ -0,0 +1,805 @@
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        order_lanes::{LaneWeights, OrderLane},
+        order_queue::OrderQueueMessage,
+    },
+    domain::order::QueueEstimate,
+};
+
+/// Why an order could not be queued
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueRejection {
+    /// The queue as a whole is at its admission limit
+    Full,
+    /// The order's sale already holds all the slots it may
+    SaleShareExhausted,
+}
+
+/// An order taken from the queue
+pub struct QueuedOrder {
+    pub message: OrderQueueMessage,
+    pub lane: OrderLane,
+    /// Weight its sale was queued with
+    pub scheduling_weight: u32,
+    /// Returned by [`Reservation::send`], hand it back to
+    /// [`FairOrderQueue::finish`]
+    pub sequence: u64,
+}
+
+struct SaleQueue {
+    /// In sequence order
+    orders: VecDeque<QueuedOrder>,
+    weight: u32,
+    /// Orders taken during the sale's current turn
+    served: u32,
+}
+
+/// Orders of one priority lane, shared between sales by weighted round robin
+#[derive(Default)]
+struct Lane {
+    sales: HashMap<Uuid, SaleQueue>,
+    /// Sales with orders waiting, the front one has its turn
+    rotation: VecDeque<Uuid>,
+    len: usize,
+}
+
+impl Lane {
+    fn push(&mut self, flash_sale_id: Uuid, order: QueuedOrder) {
+        let weight = order.scheduling_weight;
+        let sale = self
+            .sales
+            .entry(flash_sale_id)
+            .or_insert_with(|| SaleQueue {
+                orders: VecDeque::new(),
+                weight,
+                served: 0,
+            });
+        // A changed weight applies from the sale's next turn
+        sale.weight = weight;
+        sale.orders.push_back(order);
+        if sale.orders.len() == 1 {
+            self.rotation.push_back(flash_sale_id);
+        }
+        self.len += 1;
+    }
+
+    /// The sale in turn gives up to its weight in orders, then moves to the back
+    fn pop(&mut self) -> Option<(Uuid, QueuedOrder)> {
+        let flash_sale_id = *self.rotation.front()?;
+        let sale = self.sales.get_mut(&flash_sale_id)?;
+
+        let order = sale.orders.pop_front()?;
+        sale.served += 1;
+
+        if sale.orders.is_empty() {
+            self.sales.remove(&flash_sale_id);
+            self.rotation.pop_front();
+        } else if sale.served >= sale.weight {
+            sale.served = 0;
+            self.rotation.rotate_left(1);
+        }
+
+        self.len -= 1;
+        Some((flash_sale_id, order))
+    }
+}
+
+struct QueueState {
+    lanes: [Lane; 4],
+    /// Lane whose turn it is, as an index into [`OrderLane::ALL`]
+    turn: usize,
+    /// Orders taken during the current lane's turn
+    served: u32,
+    /// Orders waiting plus reserved slots per sale, across lanes
+    held: HashMap<Uuid, usize>,
+    /// Orders waiting plus reserved slots
+    len: usize,
+    /// Sequence number of the last order sent
+    sequence: u64,
+    /// Line of each waiting order by sequence number
+    waiting: HashMap<u64, (OrderLane, Uuid)>,
+    /// Orders taken that the consumer hasn't finished
+    in_flight: BTreeSet<u64>,
+    /// Moving average of the time the consumer spends per order, in ms
+    service_ms: Option<f64>,
+}
+
+impl QueueState {
+    fn release(&mut self, flash_sale_id: Uuid) {
+        if let Some(held) = self.held.get_mut(&flash_sale_id) {
+            *held -= 1;
+            if *held == 0 {
+                self.held.remove(&flash_sale_id);
+            }
+        }
+        self.len -= 1;
+    }
+
+    /// Weighted round robin over the lanes: the lane in turn gives up to its
+    /// weight in orders before the next lane with orders gets its turn, so
+    /// busy priority lanes can't starve the standard one
+    fn next(&mut self, weights: &LaneWeights) -> Option<QueuedOrder> {
+        for _ in 0..=OrderLane::ALL.len() {
+            let lane = OrderLane::ALL[self.turn];
+            if self.lanes[self.turn].len > 0 && self.served < weights.weight(lane) {
+                let (flash_sale_id, order) = self.lanes[self.turn].pop()?;
+                self.served += 1;
+                self.release(flash_sale_id);
+                self.waiting.remove(&order.sequence);
+                self.in_flight.insert(order.sequence);
+                return Some(order);
+            }
+
+            self.turn = (self.turn + 1) % OrderLane::ALL.len();
+            self.served = 0;
+        }
+
+        None
+    }
+
+    /// Waiting orders taken up to and including the one sent as `sequence`
+    ///
+    /// Counts the weighted turns of the lanes and of the sales within its
+    /// lane from where they stand, so it costs one step per lane and per sale
+    /// rather than per waiting order. Counts only what is waiting now, so it
+    /// drifts as new orders arrive in lines due before it.
+    fn position(
+        &self,
+        weights: &LaneWeights,
+        lane: OrderLane,
+        flash_sale_id: Uuid,
+        sequence: u64,
+    ) -> Option<u64> {
+        let from_lane = self.lanes[lane.index()].taken_until(flash_sale_id, sequence)?;
+
+        // Lanes take their turns from the current one onwards; the lane in
+        // turn only has what is left of its weight
+        let lanes = (0..OrderLane::ALL.len()).map(|offset| {
+            let index = (self.turn + offset) % OrderLane::ALL.len();
+            let weight = u64::from(weights.weight(OrderLane::ALL[index]));
+            let first_turn = if offset == 0 {
+                weight.saturating_sub(u64::from(self.served))
+            } else {
+                weight
+            };
+            Turns {
+                left: self.lanes[index].len as u64,
+                first_turn,
+                weight,
+            }
+        });
+        let own = (lane.index() + OrderLane::ALL.len() - self.turn) % OrderLane::ALL.len();
+
+        taken_until(lanes, own, from_lane)
+    }
+}
+
+impl Lane {
+    /// Orders this lane gives until the one sent as `sequence` in the line of
+    /// `flash_sale_id` is taken, that one included
+    fn taken_until(&self, flash_sale_id: Uuid, sequence: u64) -> Option<u64> {
+        let own_line = self.sales.get(&flash_sale_id)?;
+        let wanted = own_line
+            .orders
+            .binary_search_by_key(&sequence, |order| order.sequence)
+            .ok()?
+            + 1;
+
+        // The sale in turn always gives at least one more order, see `pop`
+        let sales = self.rotation.iter().enumerate().map(|(index, id)| {
+            let sale = &self.sales[id];
+            let weight = u64::from(sale.weight.max(1));
+            let first_turn = if index == 0 {
+                weight.saturating_sub(u64::from(sale.served)).max(1)
+            } else {
+                weight
+            };
+            Turns {
+                left: sale.orders.len() as u64,
+                first_turn,
+                weight,
+            }
+        });
+        let own = self.rotation.iter().position(|id| *id == flash_sale_id)?;
+
+        taken_until(sales, own, wanted as u64)
+    }
+}
+
+/// One member of a weighted round robin, as seen from where it stands
+struct Turns {
+    left: u64,
+    /// What the member may give in its next turn
+    first_turn: u64,
+    /// What it may give in each turn after that
+    weight: u64,
+}
+
+/// Items the round robin gives, in turn order starting with the first of
+/// `members`, until the member at `own` has given `wanted` of them
+///
+/// Members take turns in a fixed order and give up to their share each
+/// turn, dropping out once they have nothing left.
+fn taken_until(members: impl Iterator<Item = Turns>, own: usize, wanted: u64) -> Option<u64> {
+    let members: Vec<Turns> = members.collect();
+    let target = members.get(own)?;
+    if wanted > target.left {
+        return None;
+    }
+
+    // Turns the member needs, counting a first turn that may give nothing
+    let turns = if wanted <= target.first_turn {
+        1
+    } else {
+        1 + (wanted - target.first_turn).div_ceil(target.weight.max(1))
+    };
+
+    let others: u64 = members
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != own)
+        .map(|(index, member)| {
+            // Members ahead of it get as many turns, those behind one fewer
+            let member_turns = if index < own { turns } else { turns - 1 };
+            if member_turns == 0 {
+                return 0;
+            }
+            member
+                .left
+                .min(member.first_turn + (member_turns - 1) * member.weight)
+        })
+        .sum();
+
+    Some(wanted + others)
+}
+
+/// Weight of the newest sample in the moving average of service time
+const SERVICE_TIME_SMOOTHING: f64 = 0.2;
+
+/// Bounded order queue shared fairly between priority lanes and flash sales
+///
+/// Each lane holds a line per sale. Admission reserves a slot against both
+/// the overall limit and the sale's own share, and the worker takes orders
+/// from the lanes by their weights and, within a lane, from the sales in
+/// turn by the sales' weights, so one busy sale can neither fill the queue
+/// nor hold up the others.
+pub struct FairOrderQueue {
+    capacity: usize,
+    lane_weights: LaneWeights,
+    state: Mutex<QueueState>,
+    notify: Notify,
+}
+
+impl FairOrderQueue {
+    pub fn new(capacity: usize, lane_weights: LaneWeights) -> Self {
+        Self {
+            capacity,
+            lane_weights,
+            state: Mutex::new(QueueState {
+                lanes: Default::default(),
+                turn: 0,
+                served: 0,
+                held: HashMap::new(),
+                len: 0,
+                sequence: 0,
+                waiting: HashMap::new(),
+                in_flight: BTreeSet::new(),
+                service_ms: None,
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn lane_weights(&self) -> &LaneWeights {
+        &self.lane_weights
+    }
+
+    /// Orders waiting or reserved
+    pub fn len(&self) -> usize {
+        self.lock().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Orders waiting in `lane`
+    pub fn lane_len(&self, lane: OrderLane) -> usize {
+        self.lock().lanes[lane.index()].len
+    }
+
+    /// Holds a slot in `lane` for an order of `flash_sale_id`
+    ///
+    /// Fails once `limit` slots are taken overall or the sale holds
+    /// `sale_slots` of them. The slot is given back if the reservation is
+    /// dropped without sending.
+    pub fn try_reserve(
+        &self,
+        flash_sale_id: Uuid,
+        lane: OrderLane,
+        limit: usize,
+        sale_slots: usize,
+        weight: u32,
+    ) -> Result<Reservation<'_>, QueueRejection> {
+        let mut state = self.lock();
+
+        if state.len >= limit.min(self.capacity) {
+            return Err(QueueRejection::Full);
+        }
+        if state.held.get(&flash_sale_id).copied().unwrap_or(0) >= sale_slots {
+            return Err(QueueRejection::SaleShareExhausted);
+        }
+
+        *state.held.entry(flash_sale_id).or_default() += 1;
+        state.len += 1;
+
+        Ok(Reservation {
+            queue: self,
+            flash_sale_id,
+            lane,
+            weight: weight.max(1),
+            sent: false,
+        })
+    }
+
+    /// Waits for the next order in fair order
+    ///
+    /// The order counts as in flight until [`Self::finish`] is called for it.
+    pub async fn pop(&self) -> QueuedOrder {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(order) = self.lock().next(&self.lane_weights) {
+                return order;
+            }
+            notified.await;
+        }
+    }
+
+    /// Records that an order taken with [`Self::pop`] is done: placed, or
+    /// handed off and its outcome collected, or given up on
+    pub fn finish(&self, sequence: u64) {
+        self.lock().in_flight.remove(&sequence);
+    }
+
+    /// Records that the consumer finished `orders` orders in `took`
+    pub fn record_service(&self, orders: usize, took: Duration) {
+        if orders == 0 {
+            return;
+        }
+
+        let mut state = self.lock();
+        let sample = took.as_secs_f64() * 1000.0 / orders as f64;
+        state.service_ms = Some(match state.service_ms {
+            Some(average) => average + SERVICE_TIME_SMOOTHING * (sample - average),
+            None => sample,
+        });
+    }
+
+    /// Position and expected wait of the order sent as `sequence`
+    ///
+    /// Orders in flight come first, in the order they were sent; a waiting
+    /// order comes after them by counting the lane and sale turns ahead of
+    /// it, which orders arriving later in busier lanes can still overtake.
+    /// `None` once the order is finished.
+    pub fn estimate(&self, sequence: u64) -> Option<QueueEstimate> {
+        let state = self.lock();
+        let position = if state.in_flight.contains(&sequence) {
+            state.in_flight.range(..sequence).count() as u64 + 1
+        } else {
+            let &(lane, flash_sale_id) = state.waiting.get(&sequence)?;
+            state.in_flight.len() as u64
+                + state.position(&self.lane_weights, lane, flash_sale_id, sequence)?
+        };
+
+        Some(QueueEstimate {
+            position,
+            estimated_wait: state
+                .service_ms
+                .map(|ms| Duration::from_secs_f64(ms * position as f64 / 1000.0)),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A slot held in the queue, like a channel permit
+pub struct Reservation<'a> {
+    queue: &'a FairOrderQueue,
+    flash_sale_id: Uuid,
+    lane: OrderLane,
+    weight: u32,
+    sent: bool,
+}
+
+impl Reservation<'_> {
+    /// Queues the order and returns its sequence number
+    pub fn send(mut self, message: OrderQueueMessage) -> u64 {
+        let sequence = {
+            let mut state = self.queue.lock();
+            state.sequence += 1;
+            let sequence = state.sequence;
+            state.lanes[self.lane.index()].push(
+                self.flash_sale_id,
+                QueuedOrder {
+                    message,
+                    lane: self.lane,
+                    scheduling_weight: self.weight,
+                    sequence,
+                },
+            );
+            state
+                .waiting
+                .insert(sequence, (self.lane, self.flash_sale_id));
+            sequence
+        };
+
+        self.sent = true;
+        self.queue.notify.notify_one();
+        sequence
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if self.sent {
+            return;
+        }
+
+        self.queue.lock().release(self.flash_sale_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::config::PriorityLanesConfig, logic::order_logic::CreateOrderCommand};
+
+    const SALE_A: Uuid = Uuid::from_u128(0xa);
+    const SALE_B: Uuid = Uuid::from_u128(0xb);
+
+    fn message(flash_sale_id: Uuid, n: u128) -> OrderQueueMessage {
+        OrderQueueMessage {
+            order_id: Uuid::from_u128(n),
+            command: CreateOrderCommand {
+                order_id: Uuid::from_u128(n),
+                user_id: Uuid::nil(),
+                flash_sale_id,
+                quantity: 1,
+                idempotency_key: n.to_string(),
+                request_fingerprint: String::new(),
+                api_client_id: None,
+            },
+        }
+    }
+
+    /// Queued order numbered `n`, which is also its sequence
+    fn order(flash_sale_id: Uuid, weight: u32, n: u128) -> QueuedOrder {
+        QueuedOrder {
+            message: message(flash_sale_id, n),
+            lane: OrderLane::Standard,
+            scheduling_weight: weight,
+            sequence: n as u64,
+        }
+    }
+
+    fn drain_lane(lane: &mut Lane) -> Vec<u64> {
+        std::iter::from_fn(|| lane.pop())
+            .map(|(_, order)| order.sequence)
+            .collect()
+    }
+
+    fn weights(retry: u32, vip: u32, early_access: u32, standard: u32) -> LaneWeights {
+        LaneWeights::from(&PriorityLanesConfig {
+            retry_weight: retry,
+            vip_weight: vip,
+            early_access_weight: early_access,
+            standard_weight: standard,
+            ..PriorityLanesConfig::default()
+        })
+    }
+
+    fn enqueue(queue: &FairOrderQueue, lane: OrderLane, flash_sale_id: Uuid, n: u128) -> u64 {
+        queue
+            .try_reserve(flash_sale_id, lane, usize::MAX, usize::MAX, 1)
+            .unwrap()
+            .send(message(flash_sale_id, n))
+    }
+
+    fn position(queue: &FairOrderQueue, sequence: u64) -> Option<u64> {
+        queue.estimate(sequence).map(|estimate| estimate.position)
+    }
+
+    fn next(queue: &FairOrderQueue) -> Option<QueuedOrder> {
+        queue.lock().next(&queue.lane_weights)
+    }
+
+    #[test]
+    fn lane_keeps_order_within_a_sale() {
+        let mut lane = Lane::default();
+        for n in 1..=3 {
+            lane.push(SALE_A, order(SALE_A, 1, n));
+        }
+
+        assert_eq!(drain_lane(&mut lane), [1, 2, 3]);
+        assert_eq!(lane.len, 0);
+        assert!(lane.sales.is_empty() && lane.rotation.is_empty());
+    }
+
+    #[test]
+    fn lane_serves_sales_by_weight() {
+        let mut lane = Lane::default();
+        for n in 1..=4 {
+            lane.push(SALE_A, order(SALE_A, 2, n));
+        }
+        for n in 11..=13 {
+            lane.push(SALE_B, order(SALE_B, 1, n));
+        }
+
+        assert_eq!(drain_lane(&mut lane), [1, 2, 11, 3, 4, 12, 13]);
+    }
+
+    #[test]
+    fn lane_pop_reports_sale_and_weight() {
+        let mut lane = Lane::default();
+        lane.push(SALE_B, order(SALE_B, 3, 1));
+
+        let (flash_sale_id, order) = lane.pop().unwrap();
+        assert_eq!((flash_sale_id, order.scheduling_weight), (SALE_B, 3));
+        assert!(lane.pop().is_none());
+    }
+
+    #[test]
+    fn emptied_sale_rejoins_at_the_back() {
+        let mut lane = Lane::default();
+        lane.push(SALE_A, order(SALE_A, 1, 1));
+        lane.push(SALE_B, order(SALE_B, 1, 11));
+        lane.push(SALE_B, order(SALE_B, 1, 12));
+
+        assert_eq!(lane.pop().unwrap().1.sequence, 1);
+        lane.push(SALE_A, order(SALE_A, 1, 2));
+
+        assert_eq!(drain_lane(&mut lane), [11, 2, 12]);
+    }
+
+    #[test]
+    fn changed_weight_applies_to_waiting_sale() {
+        let mut lane = Lane::default();
+        lane.push(SALE_A, order(SALE_A, 1, 1));
+        lane.push(SALE_A, order(SALE_A, 1, 2));
+        lane.push(SALE_B, order(SALE_B, 1, 11));
+        lane.push(SALE_A, order(SALE_A, 2, 3));
+
+        assert_eq!(drain_lane(&mut lane), [1, 2, 11, 3]);
+    }
+
+    #[test]
+    fn next_serves_lanes_by_weight() {
+        let queue = FairOrderQueue::new(100, weights(2, 1, 1, 1));
+        for n in 1..=4 {
+            enqueue(&queue, OrderLane::Retry, SALE_A, n);
+            enqueue(&queue, OrderLane::Standard, SALE_A, 10 + n);
+        }
+
+        let served: Vec<_> = std::iter::from_fn(|| next(&queue))
+            .map(|order| order.message.order_id.as_u128())
+            .collect();
+        assert_eq!(served, [1, 2, 11, 3, 4, 12, 13, 14]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn next_skips_empty_lanes() {
+        let queue = FairOrderQueue::new(100, weights(8, 4, 2, 1));
+        enqueue(&queue, OrderLane::Standard, SALE_A, 1);
+        enqueue(&queue, OrderLane::Vip, SALE_A, 2);
+
+        assert_eq!(next(&queue).unwrap().lane, OrderLane::Vip);
+        assert_eq!(next(&queue).unwrap().lane, OrderLane::Standard);
+        assert!(next(&queue).is_none());
+    }
+
+    #[test]
+    fn busy_priority_lane_does_not_starve_standard() {
+        let queue = FairOrderQueue::new(1_000, weights(3, 1, 1, 1));
+        for n in 1..=5 {
+            enqueue(&queue, OrderLane::Standard, SALE_A, 100 + n);
+        }
+
+        let mut since_standard = 0;
+        let mut standard_served = 0;
+        for n in 0..20 {
+            // Retries keep arriving faster than they are served
+            enqueue(&queue, OrderLane::Retry, SALE_A, 2 * n);
+            enqueue(&queue, OrderLane::Retry, SALE_A, 2 * n + 1);
+
+            match next(&queue).unwrap().lane {
+                OrderLane::Standard => {
+                    standard_served += 1;
+                    since_standard = 0;
+                }
+                _ => since_standard += 1,
+            }
+            assert!(since_standard <= 3, "standard lane starved");
+        }
+        assert_eq!(standard_served, 5);
+    }
+
+    #[test]
+    fn reservations_stop_at_the_limit_and_the_sale_share() {
+        let queue = FairOrderQueue::new(3, LaneWeights::default());
+        let reserve =
+            |flash_sale_id| queue.try_reserve(flash_sale_id, OrderLane::Standard, 10, 2, 1);
+
+        let first = reserve(SALE_A).unwrap();
+        let _second = reserve(SALE_A).unwrap();
+        assert_eq!(
+            reserve(SALE_A).err(),
+            Some(QueueRejection::SaleShareExhausted)
+        );
+
+        // The capacity caps a larger admission limit
+        let _third = reserve(SALE_B).unwrap();
+        assert_eq!(reserve(SALE_B).err(), Some(QueueRejection::Full));
+
+        drop(first);
+        assert!(reserve(SALE_A).is_ok());
+    }
+
+    #[test]
+    fn next_releases_sale_slots() {
+        let queue = FairOrderQueue::new(100, LaneWeights::default());
+        enqueue(&queue, OrderLane::Standard, SALE_A, 1);
+        assert!(
+            queue
+                .try_reserve(SALE_A, OrderLane::Standard, 10, 1, 1)
+                .is_err()
+        );
+
+        let order = next(&queue).unwrap();
+        assert_eq!(order.scheduling_weight, 1);
+        assert!(queue.is_empty());
+        assert!(
+            queue
+                .try_reserve(SALE_A, OrderLane::Standard, 10, 1, 1)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn estimate_follows_own_line_and_in_flight_orders() {
+        let queue = FairOrderQueue::new(100, LaneWeights::default());
+        let sent: Vec<_> = (1..=3)
+            .map(|n| enqueue(&queue, OrderLane::Standard, SALE_A, n))
+            .collect();
+        assert_eq!(
+            sent.iter()
+                .map(|&s| position(&queue, s))
+                .collect::<Vec<_>>(),
+            [Some(1), Some(2), Some(3)]
+        );
+
+        let taken = next(&queue).unwrap();
+        assert_eq!(taken.sequence, sent[0]);
+        assert_eq!(position(&queue, sent[0]), Some(1));
+        assert_eq!(position(&queue, sent[1]), Some(2));
+
+        queue.finish(taken.sequence);
+        assert_eq!(position(&queue, sent[0]), None);
+        assert_eq!(position(&queue, sent[1]), Some(1));
+        assert_eq!(position(&queue, sent[2]), Some(2));
+    }
+
+    #[test]
+    fn estimate_keeps_orders_passed_by_other_lanes() {
+        let queue = FairOrderQueue::new(100, weights(8, 4, 2, 1));
+        let standard = enqueue(&queue, OrderLane::Standard, SALE_A, 1);
+        for n in 2..=4 {
+            enqueue(&queue, OrderLane::Retry, SALE_A, n);
+        }
+
+        for _ in 0..3 {
+            let order = next(&queue).unwrap();
+            assert_eq!(order.lane, OrderLane::Retry);
+            queue.finish(order.sequence);
+        }
+        assert_eq!(position(&queue, standard), Some(1));
+    }
+
+    #[test]
+    fn estimate_counts_turns_of_other_lanes_and_sales() {
+        let queue = FairOrderQueue::new(100, weights(2, 1, 1, 1));
+        let standard: Vec<_> = (1..=4)
+            .map(|n| enqueue(&queue, OrderLane::Standard, SALE_A, n))
+            .collect();
+        for n in 5..=8 {
+            enqueue(&queue, OrderLane::Retry, SALE_A, n);
+        }
+        enqueue(&queue, OrderLane::Standard, SALE_B, 9);
+
+        // Served as retry, retry, A, retry, retry, B, A, A, A
+        assert_eq!(position(&queue, standard[0]), Some(3));
+        assert_eq!(position(&queue, standard[1]), Some(7));
+        assert_eq!(position(&queue, standard[3]), Some(9));
+    }
+
+    #[test]
+    fn estimate_matches_the_serving_order_of_a_large_queue() {
+        let queue = FairOrderQueue::new(100_000, weights(3, 2, 2, 1));
+        let sales = [(SALE_A, 1), (SALE_B, 3), (Uuid::from_u128(0xc), 2)];
+
+        // A fixed mix of lanes, sales and sale weights
+        let mut sent = Vec::new();
+        let mut seed = 7u64;
+        for n in 0..40_000u128 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let lane = OrderLane::ALL[(seed >> 33) as usize % 4];
+            let (flash_sale_id, weight) = sales[(seed >> 40) as usize % sales.len()];
+            let sequence = queue
+                .try_reserve(flash_sale_id, lane, usize::MAX, usize::MAX, weight)
+                .unwrap()
+                .send(message(flash_sale_id, n));
+            sent.push(sequence);
+
+            // Stop partway through lane and sale turns
+            if n % 1_000 == 999 {
+                for _ in 0..7 {
+                    let order = next(&queue).unwrap();
+                    queue.finish(order.sequence);
+                }
+            }
+        }
+        let in_flight = next(&queue).unwrap().sequence;
+
+        let expected: HashMap<u64, u64> = sent
+            .iter()
+            .filter_map(|&sequence| Some((sequence, position(&queue, sequence)?)))
+            .collect();
+        assert_eq!(expected.len(), queue.len() + 1);
+        assert_eq!(expected.get(&in_flight), Some(&1));
+
+        let mut served = 1;
+        while let Some(order) = next(&queue) {
+            served += 1;
+            assert_eq!(
+                expected[&order.sequence], served,
+                "order {} was estimated at {} but served at {served}",
+                order.sequence, expected[&order.sequence]
+            );
+        }
+        assert_eq!(served as usize, expected.len());
+    }
+
+    #[test]
+    fn estimated_wait_uses_service_time() {
+        let queue = FairOrderQueue::new(100, LaneWeights::default());
+        let first = enqueue(&queue, OrderLane::Standard, SALE_A, 1);
+        let second = enqueue(&queue, OrderLane::Standard, SALE_A, 2);
+        assert_eq!(queue.estimate(second).unwrap().estimated_wait, None);
+
+        queue.record_service(4, Duration::from_millis(200));
+        assert_eq!(
+            queue.estimate(first).unwrap().estimated_wait,
+            Some(Duration::from_millis(50))
+        );
+        assert_eq!(
+            queue.estimate(second).unwrap().estimated_wait,
+            Some(Duration::from_millis(100))
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::domain::flash_sale::{FlashSaleDetails, FlashSaleStatusFilter};
+
+/// Unknown sale ids remembered at most; ids are client-supplied, so expired
+/// ones are dropped once this many are held
+const MISSING_SALES_MAX: usize = 10_000;
+
+struct CacheEntry<T> {
+    value: T,
+    stored_at: Instant,
+}
+
+/// Short-lived copies of the public flash sale reads
+///
+/// Clients poll sale pages heavily while a sale runs; answering from memory
+/// keeps that traffic away from the rows the order worker locks. Stock shown
+/// to clients may lag by up to the TTL, orders always check the database.
+/// Ids found not to exist are remembered for the TTL as well, so orders for
+/// made-up sales don't each cost a query.
+pub struct FlashSaleCache {
+    ttl: Duration,
+    sales: DashMap<Uuid, CacheEntry<FlashSaleDetails>>,
+    missing: DashMap<Uuid, Instant>,
+    listings: DashMap<FlashSaleStatusFilter, CacheEntry<Vec<FlashSaleDetails>>>,
+}
+
+impl FlashSaleCache {
+    /// A zero TTL disables caching
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            sales: DashMap::new(),
+            missing: DashMap::new(),
+            listings: DashMap::new(),
+        }
+    }
+
+    pub fn get_sale(&self, id: Uuid) -> Option<FlashSaleDetails> {
+        let hit = self
+            .sales
+            .get(&id)
+            .filter(|entry| entry.stored_at.elapsed() < self.ttl)
+            .map(|entry| entry.value.clone());
+
+        record_lookup("sale", hit.is_some());
+        hit
+    }
+
+    pub fn put_sale(&self, details: FlashSaleDetails) {
+        self.sales.insert(
+            details.flash_sale.id,
+            CacheEntry {
+                value: details,
+                stored_at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn evict_sale(&self, id: Uuid) {
+        self.sales.remove(&id);
+        self.missing.remove(&id);
+    }
+
+    /// Whether `id` was recently looked up and not found
+    pub fn is_missing(&self, id: Uuid) -> bool {
+        let hit = self
+            .missing
+            .get(&id)
+            .is_some_and(|stored_at| stored_at.elapsed() < self.ttl);
+
+        record_lookup("missing_sale", hit);
+        hit
+    }
+
+    pub fn put_missing(&self, id: Uuid) {
+        if self.missing.len() >= MISSING_SALES_MAX {
+            self.missing
+                .retain(|_, stored_at| stored_at.elapsed() < self.ttl);
+            if self.missing.len() >= MISSING_SALES_MAX {
+                self.missing.clear();
+            }
+        }
+        self.missing.insert(id, Instant::now());
+    }
+
+    pub fn get_listing(&self, status: FlashSaleStatusFilter) -> Option<Vec<FlashSaleDetails>> {
+        let hit = self
+            .listings
+            .get(&status)
+            .filter(|entry| entry.stored_at.elapsed() < self.ttl)
+            .map(|entry| entry.value.clone());
+
+        record_lookup("listing", hit.is_some());
+        hit
+    }
+
+    pub fn put_listing(&self, status: FlashSaleStatusFilter, sales: Vec<FlashSaleDetails>) {
+        self.listings.insert(
+            status,
+            CacheEntry {
+                value: sales,
+                stored_at: Instant::now(),
+            },
+        );
+    }
+}
+
+fn record_lookup(kind: &'static str, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    metrics::counter!("flash_sale_cache_lookups_total", "kind" => kind, "result" => result)
+        .increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_listings_until_the_ttl_passes() {
+        let cache = FlashSaleCache::new(Duration::from_millis(50));
+        cache.put_listing(FlashSaleStatusFilter::Live, Vec::new());
+
+        assert!(cache.get_listing(FlashSaleStatusFilter::Live).is_some());
+        assert!(cache.get_listing(FlashSaleStatusFilter::Upcoming).is_none());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.get_listing(FlashSaleStatusFilter::Live).is_none());
+    }
+
+    #[test]
+    fn a_zero_ttl_disables_caching() {
+        let cache = FlashSaleCache::new(Duration::ZERO);
+        cache.put_listing(FlashSaleStatusFilter::Live, Vec::new());
+
+        assert!(cache.get_listing(FlashSaleStatusFilter::Live).is_none());
+    }
+
+    #[test]
+    fn remembers_unknown_sales_until_created() {
+        let cache = FlashSaleCache::new(Duration::from_secs(60));
+        let id = Uuid::from_u128(1);
+        assert!(!cache.is_missing(id));
+
+        cache.put_missing(id);
+        assert!(cache.is_missing(id));
+        assert!(!cache.is_missing(Uuid::from_u128(2)));
+
+        cache.evict_sale(id);
+        assert!(!cache.is_missing(id));
+    }
+}
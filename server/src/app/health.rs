@@ -0,0 +1,207 @@
+use std::{sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+use crate::{
+    app::{
+        circuit_breaker::{CircuitBreaker, CircuitState},
+        config::HealthConfig,
+        fair_queue::FairOrderQueue,
+        heartbeat::{Heartbeat, age},
+    },
+    domain::health::{ComponentHealth, HealthStatus, ReadinessReport},
+    logic::health_logic::latest_worker_heartbeat,
+    ports::WorkerHeartbeatRepo,
+};
+
+/// How long liveness waits for a freshly spawned task to run
+const LIVENESS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Where the order worker's heartbeat is read from
+pub enum WorkerLiveness {
+    /// Worker loop in this process
+    Local(Arc<Heartbeat>),
+    /// Worker processes publishing to the shared heartbeat table
+    Shared(Arc<dyn WorkerHeartbeatRepo>),
+}
+
+/// Answers the liveness and readiness probes
+pub struct HealthChecker {
+    db_pool: sqlx::PgPool,
+    config: HealthConfig,
+    /// `None` in worker processes, which have no admission queue
+    order_queue: Option<Arc<FairOrderQueue>>,
+    workers: WorkerLiveness,
+    db_breaker: Arc<CircuitBreaker>,
+    started: Instant,
+}
+
+impl HealthChecker {
+    pub fn new(
+        db_pool: sqlx::PgPool,
+        config: HealthConfig,
+        order_queue: Option<Arc<FairOrderQueue>>,
+        workers: WorkerLiveness,
+        db_breaker: Arc<CircuitBreaker>,
+    ) -> Self {
+        Self {
+            db_pool,
+            config,
+            order_queue,
+            workers,
+            db_breaker,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether the async runtime still schedules new tasks in good time
+    pub async fn is_live(&self) -> bool {
+        tokio::time::timeout(LIVENESS_TIMEOUT, tokio::spawn(async {}))
+            .await
+            .is_ok_and(|joined| joined.is_ok())
+    }
+
+    /// Checks every dependency the process needs to take traffic
+    pub async fn readiness(&self) -> ReadinessReport {
+        let ((database, pool), workers) = tokio::join!(self.check_database(), self.check_workers());
+
+        let mut components = vec![
+            ("database", database),
+            ("pool", pool),
+            ("circuit_breaker", self.check_breaker()),
+        ];
+        if let Some(queue) = self.check_order_queue() {
+            components.push(("order_queue", queue));
+        }
+        components.push(("worker", workers));
+
+        for (name, health) in &components {
+            let up = if health.status == HealthStatus::Up {
+                1.0
+            } else {
+                0.0
+            };
+            metrics::gauge!("health_component_up", "component" => *name).set(up);
+        }
+
+        ReadinessReport { components }
+    }
+
+    /// Pings the database through the pool, returning the health of the
+    /// database and of the pool, judged by how long the connection took
+    async fn check_database(&self) -> (ComponentHealth, ComponentHealth) {
+        let timeout = Duration::from_millis(self.config.db_timeout_ms);
+        let start = Instant::now();
+        let mut waited = None;
+
+        let ping = async {
+            let mut conn = self.db_pool.acquire().await?;
+            waited = Some(start.elapsed());
+            sqlx::Connection::ping(&mut *conn).await
+        };
+        let result = tokio::time::timeout(timeout, ping).await;
+
+        // A probe that never got a connection waited for as long as it ran
+        let pool = self.check_pool(waited.unwrap_or_else(|| start.elapsed()));
+        let database = match result {
+            Ok(Ok(())) => {
+                let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+                ComponentHealth::below(
+                    latency_ms,
+                    self.config.db_timeout_ms as f64,
+                    "round trip in milliseconds",
+                )
+            }
+            Ok(Err(e)) => ComponentHealth::down(format!("database unreachable: {e}")),
+            Err(_) => {
+                ComponentHealth::down(format!("no answer within {}ms", self.config.db_timeout_ms))
+            }
+        };
+
+        (database, pool)
+    }
+
+    /// Every connection being busy is only a problem once requests queue up
+    /// for one, which the probe sees as its own wait
+    fn check_pool(&self, waited: Duration) -> ComponentHealth {
+        let max = self.db_pool.options().get_max_connections();
+        let in_use = self
+            .db_pool
+            .size()
+            .saturating_sub(self.db_pool.num_idle() as u32);
+
+        ComponentHealth::below(
+            waited.as_secs_f64() * 1000.0,
+            self.config.pool_acquire_wait_limit_ms as f64,
+            format!("milliseconds waited for a connection, {in_use} of {max} in use"),
+        )
+    }
+
+    /// A half-open breaker is already letting calls through, so only open counts
+    fn check_breaker(&self) -> ComponentHealth {
+        match self.db_breaker.state() {
+            CircuitState::Open => ComponentHealth::down(format!(
+                "{} breaker open, retrying in {}ms",
+                self.db_breaker.name(),
+                self.db_breaker
+                    .retry_after()
+                    .unwrap_or_default()
+                    .as_millis()
+            )),
+            state => ComponentHealth::up(format!(
+                "{} breaker {}",
+                self.db_breaker.name(),
+                state.as_str()
+            )),
+        }
+    }
+
+    fn check_order_queue(&self) -> Option<ComponentHealth> {
+        let queue = self.order_queue.as_ref()?;
+        let queued = queue.len();
+
+        Some(ComponentHealth::below(
+            queued as f64 / queue.capacity() as f64,
+            self.config.queue_fill_limit,
+            format!("{queued} of {} queue slots taken", queue.capacity()),
+        ))
+    }
+
+    async fn check_workers(&self) -> ComponentHealth {
+        let last_beat = match &self.workers {
+            WorkerLiveness::Local(heartbeat) => heartbeat.last_beat(),
+            WorkerLiveness::Shared(repo) => {
+                let timeout = Duration::from_millis(self.config.db_timeout_ms);
+                let latest = async {
+                    let mut conn = self.db_pool.acquire().await.map_err(|e| e.to_string())?;
+                    latest_worker_heartbeat(&mut conn, repo.as_ref())
+                        .await
+                        .map_err(|e| e.to_string())
+                };
+
+                match tokio::time::timeout(timeout, latest).await {
+                    Ok(Ok(Some(last_beat))) => last_beat,
+                    Ok(Ok(None)) => return ComponentHealth::down("no worker heartbeat recorded"),
+                    Ok(Err(e)) => {
+                        return ComponentHealth::down(format!("heartbeat lookup failed: {e}"));
+                    }
+                    Err(_) => {
+                        return ComponentHealth::down(format!(
+                            "heartbeat lookup took over {}ms",
+                            self.config.db_timeout_ms
+                        ));
+                    }
+                }
+            }
+        };
+
+        ComponentHealth::below(
+            age(last_beat).as_secs_f64() * 1000.0,
+            self.config.worker_heartbeat_max_age_ms as f64,
+            "milliseconds since the last heartbeat",
+        )
+    }
+}
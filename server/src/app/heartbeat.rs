@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicI64, Ordering},
+    },
+    time::Duration,
+};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    errors::RepoError,
+    logic::health_logic::{record_worker_heartbeat, remove_worker_heartbeat},
+    ports::WorkerHeartbeatRepo,
+};
+
+/// Last time an order worker loop made progress
+///
+/// The loop beats on every pass, including idle ones, so a stale heartbeat
+/// means the worker is stuck or gone rather than just without work.
+pub struct Heartbeat {
+    started_at: DateTime<Utc>,
+    last_beat_ms: AtomicI64,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        let now = Utc::now();
+        Self {
+            started_at: now,
+            last_beat_ms: AtomicI64::new(now.timestamp_millis()),
+        }
+    }
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn beat(&self) {
+        self.last_beat_ms
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    pub fn last_beat(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(self.last_beat_ms.load(Ordering::Relaxed))
+            .unwrap_or(self.started_at)
+    }
+}
+
+/// Time since `at`, zero if it lies in the future
+pub fn age(at: DateTime<Utc>) -> Duration {
+    (Utc::now() - at).to_std().unwrap_or_default()
+}
+
+/// Copies a worker's heartbeat to the shared table for the API to see
+///
+/// The row is removed when `shutdown` flips, so a worker stopped on purpose
+/// doesn't linger as a stale heartbeat.
+pub fn spawn_heartbeat_publisher(
+    db_pool: sqlx::PgPool,
+    repo: Arc<dyn WorkerHeartbeatRepo>,
+    heartbeat: Arc<Heartbeat>,
+    interval: Duration,
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    let worker_id = Uuid::new_v4();
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        while !*shutdown.borrow() {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => continue,
+            }
+
+            let result = match db_pool.acquire().await {
+                Ok(mut conn) => {
+                    record_worker_heartbeat(
+                        &mut conn,
+                        repo.as_ref(),
+                        worker_id,
+                        heartbeat.started_at(),
+                        heartbeat.last_beat(),
+                    )
+                    .await
+                }
+                Err(e) => Err(RepoError::ConnectionPool(e.to_string()).into()),
+            };
+            if let Err(e) = result {
+                error!(error = ?e, "Failed to publish worker heartbeat");
+            }
+        }
+
+        let result = match db_pool.acquire().await {
+            Ok(mut conn) => remove_worker_heartbeat(&mut conn, repo.as_ref(), worker_id).await,
+            Err(e) => Err(RepoError::ConnectionPool(e.to_string()).into()),
+        };
+        if let Err(e) = result {
+            error!(error = ?e, "Failed to remove worker heartbeat");
+        }
+    })
+}
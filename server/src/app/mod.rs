@@ -1,4 +1,20 @@
+pub mod adaptive_admission;
+pub mod circuit_breaker;
+pub mod cli;
 pub mod config;
+pub mod config_watcher;
+pub mod fair_queue;
+pub mod flash_sale_cache;
+pub mod health;
+pub mod heartbeat;
+pub mod order_challenge;
+pub mod order_guards;
+pub mod order_job_worker;
+pub mod order_lanes;
 pub mod order_queue;
+pub mod rate_limiter;
 pub mod runtime;
+pub mod runtime_settings;
+pub mod seed;
+pub mod sold_out_sales;
 pub mod state;
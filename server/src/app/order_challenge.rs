@@ -0,0 +1,298 @@
+use anyhow::Context;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::{
+    app::config::ChallengeConfig,
+    domain::order_challenge::DifficultyPolicy,
+    errors::RepoError,
+    logic::order_challenge_logic::{purge_expired_challenges, sync_challenge_difficulty},
+    ports::OrderChallengeRepo,
+};
+
+/// Longest solution accepted, real ones are a counter of a few digits
+const MAX_SOLUTION_LEN: usize = 64;
+
+/// A challenge handed to a client, valid for one order
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    /// Signed `<flash_sale_id>.<nonce>.<difficulty>.<expires>.<mac>`
+    pub token: String,
+    pub difficulty: u32,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Why a solution was not accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeRejection {
+    /// Not signed by this service, for another sale, or malformed
+    Invalid,
+    Expired,
+    /// The hash doesn't have enough leading zero bits
+    Unsolved,
+    /// The challenge was already used for another order
+    Replayed,
+}
+
+impl ChallengeRejection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Invalid => "invalid",
+            Self::Expired => "expired",
+            Self::Unsolved => "unsolved",
+            Self::Replayed => "replayed",
+        }
+    }
+}
+
+/// A challenge whose signature and solution checked out
+///
+/// It still has to be spent on the order with
+/// [`use_challenge`](crate::logic::order_challenge_logic::use_challenge),
+/// which turns away a challenge already used by another order.
+#[derive(Debug, Clone)]
+pub struct SolvedChallenge {
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Issues and checks hashcash-style challenges for orders
+///
+/// A solution is a string such that `SHA-256("<token>:<user_id>:<solution>")`
+/// starts with `difficulty` zero bits, so it only counts for the user it was
+/// solved for. Each challenge admits one order; it may be presented again
+/// only with the same order, so retries of a failed attempt keep working.
+/// Used challenges are recorded in the database until they expire, so this
+/// holds across instances and restarts.
+///
+/// Difficulty rises a step each interval the queues of all instances overflow
+/// faster than the threshold and falls back a step each interval they stay
+/// under half of it. Instances report their overflows and pick up the shared
+/// difficulty every `sync_interval_ms`, see [`spawn_order_challenge_sync`].
+/// The difficulty is signed into the challenge, so a change only applies to
+/// challenges issued after it.
+pub struct OrderChallenges {
+    key: Vec<u8>,
+    config: ChallengeConfig,
+    /// Shared difficulty as of the last sync
+    difficulty: AtomicU32,
+    /// Overflows not yet reported to the shared difficulty
+    overflows: AtomicU64,
+}
+
+impl OrderChallenges {
+    pub fn new(config: ChallengeConfig, key: Vec<u8>) -> Self {
+        metrics::gauge!("order_challenge_difficulty").set(config.base_difficulty as f64);
+
+        Self {
+            key,
+            difficulty: AtomicU32::new(config.base_difficulty),
+            overflows: AtomicU64::new(0),
+            config,
+        }
+    }
+
+    /// Signs with the key in `secret_file`, or a random key when it's not set
+    pub fn from_config(config: &ChallengeConfig) -> anyhow::Result<Self> {
+        let key = if config.secret_file.is_empty() {
+            tracing::info!(
+                "challenge.secret_file is not set, challenges only verify on the instance that issued them"
+            );
+            [Uuid::new_v4().into_bytes(), Uuid::new_v4().into_bytes()].concat()
+        } else {
+            std::fs::read(&config.secret_file)
+                .with_context(|| {
+                    format!(
+                        "Failed to read challenge secret file {}",
+                        config.secret_file
+                    )
+                })?
+                .trim_ascii()
+                .to_vec()
+        };
+
+        Ok(Self::new(config.clone(), key))
+    }
+
+    /// Difficulty of challenges issued now
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty.load(Ordering::Relaxed)
+    }
+
+    /// Counts an order turned away because the queue or the sale's share of
+    /// it was full
+    pub fn record_overflow(&self) {
+        self.overflows.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn issue(&self, flash_sale_id: Uuid) -> Challenge {
+        let difficulty = self.difficulty();
+        let expires_at = Utc::now() + chrono::Duration::seconds(self.config.ttl_secs as i64);
+        let payload = format!(
+            "{}.{}.{}.{}",
+            flash_sale_id,
+            Uuid::new_v4().simple(),
+            difficulty,
+            expires_at.timestamp()
+        );
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&payload).finalize().into_bytes());
+
+        Challenge {
+            token: format!("{payload}.{signature}"),
+            difficulty,
+            expires_at,
+        }
+    }
+
+    /// Checks `solution` of `token` for an order of `user_id`
+    pub fn verify(
+        &self,
+        token: &str,
+        solution: &str,
+        flash_sale_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<SolvedChallenge, ChallengeRejection> {
+        let (payload, signature) = token.rsplit_once('.').ok_or(ChallengeRejection::Invalid)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| ChallengeRejection::Invalid)?;
+        self.mac(payload)
+            .verify_slice(&signature)
+            .map_err(|_| ChallengeRejection::Invalid)?;
+
+        let [sale, nonce, difficulty, expires] = payload
+            .split('.')
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| ChallengeRejection::Invalid)?;
+        if sale.parse::<Uuid>().ok() != Some(flash_sale_id) {
+            return Err(ChallengeRejection::Invalid);
+        }
+        let difficulty = difficulty
+            .parse::<u32>()
+            .map_err(|_| ChallengeRejection::Invalid)?;
+        let expires_at = expires
+            .parse::<i64>()
+            .ok()
+            .and_then(|expires| DateTime::from_timestamp(expires, 0))
+            .ok_or(ChallengeRejection::Invalid)?;
+        if expires_at <= Utc::now() {
+            return Err(ChallengeRejection::Expired);
+        }
+
+        if solution.len() > MAX_SOLUTION_LEN
+            || leading_zero_bits(&Sha256::digest(format!("{token}:{user_id}:{solution}")))
+                < difficulty
+        {
+            return Err(ChallengeRejection::Unsolved);
+        }
+
+        Ok(SolvedChallenge {
+            nonce: nonce.to_string(),
+            expires_at,
+        })
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    fn policy(&self) -> DifficultyPolicy {
+        DifficultyPolicy {
+            base: self.config.base_difficulty,
+            max: self.config.max_difficulty,
+            overflow_rate_threshold: self.config.overflow_rate_threshold,
+            adjust_interval: Duration::from_secs(self.config.adjust_interval_secs),
+        }
+    }
+
+    /// Reports the overflows counted since the last sync and takes on the
+    /// shared difficulty
+    ///
+    /// Overflows that couldn't be reported are kept for the next sync. Returns
+    /// whether the shared window closed with this report.
+    async fn sync(&self, db_pool: &sqlx::PgPool, repo: &dyn OrderChallengeRepo) -> bool {
+        let overflows = self.overflows.swap(0, Ordering::Relaxed);
+        let result = match db_pool.acquire().await {
+            Ok(mut conn) => {
+                sync_challenge_difficulty(&mut conn, repo, overflows, &self.policy()).await
+            }
+            Err(e) => Err(RepoError::ConnectionPool(e.to_string()).into()),
+        };
+
+        let (shared, rate) = match result {
+            Ok(synced) => synced,
+            Err(e) => {
+                self.overflows.fetch_add(overflows, Ordering::Relaxed);
+                tracing::warn!(error = ?e, "Failed to sync challenge difficulty");
+                return false;
+            }
+        };
+
+        let previous = self.difficulty.swap(shared.difficulty, Ordering::Relaxed);
+        if previous != shared.difficulty {
+            metrics::gauge!("order_challenge_difficulty").set(shared.difficulty as f64);
+            debug!(
+                difficulty = shared.difficulty,
+                overflow_rate = rate,
+                "Challenge difficulty changed"
+            );
+        }
+        rate.is_some()
+    }
+}
+
+/// Keeps the difficulty in step with the other instances
+///
+/// The instance that closes a difficulty window also removes expired used
+/// challenges, so they are purged once per window rather than by everyone.
+pub fn spawn_order_challenge_sync(
+    challenges: Arc<OrderChallenges>,
+    db_pool: sqlx::PgPool,
+    repo: Arc<dyn OrderChallengeRepo>,
+    interval: Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if !challenges.sync(&db_pool, repo.as_ref()).await {
+                continue;
+            }
+
+            let result = match db_pool.acquire().await {
+                Ok(mut conn) => purge_expired_challenges(&mut conn, repo.as_ref()).await,
+                Err(e) => Err(RepoError::ConnectionPool(e.to_string()).into()),
+            };
+            match result {
+                Ok(removed) => debug!("Removed {} expired order challenges", removed),
+                Err(e) => tracing::error!(error = ?e, "Failed to remove expired order challenges"),
+            }
+        }
+    });
+}
+
+fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in digest {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
@@ -0,0 +1,361 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgConnection;
+use std::sync::Arc;
+
+use crate::{
+    domain::{
+        flash_sale::FlashSale,
+        order_guard::{GuardRejection, OrderGuardSettings},
+    },
+    errors::{AppError, RepoError, ServiceError},
+    logic::{
+        order_guard_logic::{count_recent_user_orders, is_user_allowed, is_user_blocked},
+        order_logic::CreateOrderCommand,
+        user_logic::get_user_by_id,
+    },
+    ports::{OrderGuardRepo, OrderRepo, UserRepo},
+};
+
+/// What is known about an order when its guards run
+pub struct GuardedOrder<'a> {
+    pub command: &'a CreateOrderCommand,
+    pub flash_sale: &'a FlashSale,
+    pub now: DateTime<Utc>,
+}
+
+/// One eligibility rule checked before an order is queued
+#[async_trait]
+pub trait OrderGuard: Send + Sync {
+    /// Label of the guard in metrics
+    fn name(&self) -> &'static str;
+    /// Whether the sale's settings turn the guard on
+    fn enabled(&self, settings: &OrderGuardSettings) -> bool;
+    /// `Ok(Some(_))` refuses the order, errors are failures to decide
+    async fn check(
+        &self,
+        conn: &mut PgConnection,
+        order: &GuardedOrder<'_>,
+    ) -> Result<Option<GuardRejection>, AppError>;
+}
+
+/// Refuses users on the blocklist
+pub struct BlocklistGuard {
+    repo: Arc<dyn OrderGuardRepo>,
+}
+
+impl BlocklistGuard {
+    pub fn new(repo: Arc<dyn OrderGuardRepo>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait]
+impl OrderGuard for BlocklistGuard {
+    fn name(&self) -> &'static str {
+        "blocklist"
+    }
+
+    fn enabled(&self, settings: &OrderGuardSettings) -> bool {
+        settings.blocklist
+    }
+
+    async fn check(
+        &self,
+        conn: &mut PgConnection,
+        order: &GuardedOrder<'_>,
+    ) -> Result<Option<GuardRejection>, AppError> {
+        let user_id = order.command.user_id;
+        let blocked = is_user_blocked(conn, self.repo.as_ref(), user_id).await?;
+
+        Ok(blocked.then_some(GuardRejection::Blocklisted { user_id }))
+    }
+}
+
+/// Refuses accounts created less than the sale's minimum age ago
+///
+/// Users without an account, as a token may name one, count as too new
+pub struct AccountAgeGuard {
+    repo: Arc<dyn UserRepo>,
+}
+
+impl AccountAgeGuard {
+    pub fn new(repo: Arc<dyn UserRepo>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait]
+impl OrderGuard for AccountAgeGuard {
+    fn name(&self) -> &'static str {
+        "account_age"
+    }
+
+    fn enabled(&self, settings: &OrderGuardSettings) -> bool {
+        settings.min_account_age_secs.is_some()
+    }
+
+    async fn check(
+        &self,
+        conn: &mut PgConnection,
+        order: &GuardedOrder<'_>,
+    ) -> Result<Option<GuardRejection>, AppError> {
+        let Some(min_age_secs) = order.flash_sale.guards.min_account_age_secs else {
+            return Ok(None);
+        };
+
+        let created_at = match get_user_by_id(conn, self.repo.as_ref(), order.command.user_id).await
+        {
+            Ok(user) => Some(user.created_at),
+            Err(AppError::Repo(RepoError::NotFound { .. })) => None,
+            Err(e) => return Err(e),
+        };
+        let old_enough = created_at
+            .is_some_and(|created_at| created_at + Duration::seconds(min_age_secs) <= order.now);
+
+        Ok((!old_enough).then_some(GuardRejection::AccountTooNew { min_age_secs }))
+    }
+}
+
+/// Caps how many orders a user places across all sales within a window
+///
+/// Counts orders already placed, so orders still waiting in the queue pass;
+/// the worker checks the limit again when it places each order
+pub struct VelocityGuard {
+    repo: Arc<dyn OrderRepo>,
+}
+
+impl VelocityGuard {
+    pub fn new(repo: Arc<dyn OrderRepo>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait]
+impl OrderGuard for VelocityGuard {
+    fn name(&self) -> &'static str {
+        "velocity"
+    }
+
+    fn enabled(&self, settings: &OrderGuardSettings) -> bool {
+        settings.velocity.is_some()
+    }
+
+    async fn check(
+        &self,
+        conn: &mut PgConnection,
+        order: &GuardedOrder<'_>,
+    ) -> Result<Option<GuardRejection>, AppError> {
+        let Some(limit) = order.flash_sale.guards.velocity else {
+            return Ok(None);
+        };
+
+        let recent = count_recent_user_orders(
+            conn,
+            self.repo.as_ref(),
+            order.command.user_id,
+            limit.window_start(order.now),
+        )
+        .await?;
+
+        Ok(limit.check(recent))
+    }
+}
+
+/// Refuses users not on the sale's allowlist
+pub struct AllowlistGuard {
+    repo: Arc<dyn OrderGuardRepo>,
+}
+
+impl AllowlistGuard {
+    pub fn new(repo: Arc<dyn OrderGuardRepo>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait]
+impl OrderGuard for AllowlistGuard {
+    fn name(&self) -> &'static str {
+        "allowlist"
+    }
+
+    fn enabled(&self, settings: &OrderGuardSettings) -> bool {
+        settings.allowlist_only
+    }
+
+    async fn check(
+        &self,
+        conn: &mut PgConnection,
+        order: &GuardedOrder<'_>,
+    ) -> Result<Option<GuardRejection>, AppError> {
+        let allowed = is_user_allowed(
+            conn,
+            self.repo.as_ref(),
+            order.flash_sale.id,
+            order.command.user_id,
+        )
+        .await?;
+
+        Ok((!allowed).then_some(GuardRejection::NotAllowlisted))
+    }
+}
+
+/// The guards every order passes before it is queued, in order
+///
+/// Only guards the sale turns on run, sharing one connection, and the first
+/// rejection refuses the order. Each guard run is counted by outcome in
+/// `order_guard_evaluations_total`, `order_guard_passes_total` and
+/// `order_guard_rejections_total`, labelled by guard.
+pub struct OrderGuards {
+    guards: Vec<Arc<dyn OrderGuard>>,
+}
+
+impl OrderGuards {
+    pub fn new(guards: Vec<Arc<dyn OrderGuard>>) -> Self {
+        Self { guards }
+    }
+
+    /// Blocklist, account age, velocity and allowlist guards
+    pub fn builtin(
+        user_repo: Arc<dyn UserRepo>,
+        order_repo: Arc<dyn OrderRepo>,
+        order_guard_repo: Arc<dyn OrderGuardRepo>,
+    ) -> Self {
+        Self::new(vec![
+            Arc::new(BlocklistGuard::new(order_guard_repo.clone())),
+            Arc::new(AccountAgeGuard::new(user_repo)),
+            Arc::new(VelocityGuard::new(order_repo)),
+            Arc::new(AllowlistGuard::new(order_guard_repo)),
+        ])
+    }
+
+    /// `Err(OrderRejected)` with the first guard's reason to refuse the order
+    pub async fn evaluate(
+        &self,
+        db_pool: &sqlx::PgPool,
+        order: &GuardedOrder<'_>,
+    ) -> Result<(), AppError> {
+        let settings = &order.flash_sale.guards;
+        let enabled = self
+            .guards
+            .iter()
+            .filter(|guard| guard.enabled(settings))
+            .cloned()
+            .collect::<Vec<_>>();
+        if enabled.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = db_pool
+            .acquire()
+            .await
+            .map_err(|e| RepoError::ConnectionPool(e.to_string()))?;
+
+        for guard in enabled {
+            metrics::counter!("order_guard_evaluations_total", "guard" => guard.name())
+                .increment(1);
+            if let Some(rejection) = guard.check(&mut conn, order).await? {
+                metrics::counter!(
+                    "order_guard_rejections_total",
+                    "guard" => guard.name(),
+                    "reason" => rejection.reason()
+                )
+                .increment(1);
+                return Err(AppError::Service(ServiceError::OrderRejected(rejection)));
+            }
+            metrics::counter!("order_guard_passes_total", "guard" => guard.name()).increment(1);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        adapters::db::{
+            order::repository::PostgresOrderRepo, order_guard::repository::PostgresOrderGuardRepo,
+            user::repository::PostgresUserRepo,
+        },
+        domain::{flash_sale::AdmissionBudget, order_guard::VelocityLimit},
+    };
+    use uuid::Uuid;
+
+    fn builtin() -> OrderGuards {
+        OrderGuards::builtin(
+            Arc::new(PostgresUserRepo::new()),
+            Arc::new(PostgresOrderRepo::new()),
+            Arc::new(PostgresOrderGuardRepo::new()),
+        )
+    }
+
+    fn enabled(guards: &OrderGuards, settings: &OrderGuardSettings) -> Vec<&'static str> {
+        guards
+            .guards
+            .iter()
+            .filter(|guard| guard.enabled(settings))
+            .map(|guard| guard.name())
+            .collect()
+    }
+
+    #[test]
+    fn builtin_guards_follow_the_sale_settings() {
+        let guards = builtin();
+
+        assert!(enabled(&guards, &OrderGuardSettings::default()).is_empty());
+        assert_eq!(
+            enabled(
+                &guards,
+                &OrderGuardSettings {
+                    blocklist: true,
+                    min_account_age_secs: Some(60),
+                    velocity: Some(VelocityLimit {
+                        max_orders: 1,
+                        window_secs: 60,
+                    }),
+                    allowlist_only: true,
+                }
+            ),
+            ["blocklist", "account_age", "velocity", "allowlist"]
+        );
+    }
+
+    #[tokio::test]
+    async fn sales_without_guards_skip_the_database() {
+        // Acquiring from this pool fails, so any query would error
+        let db_pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_millis(50))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .expect("lazy pool");
+        let now = Utc::now();
+        let flash_sale = FlashSale {
+            id: Uuid::from_u128(0xa),
+            product_id: Uuid::from_u128(0xb),
+            start_time: now,
+            end_time: now + Duration::hours(1),
+            total_inventory: 10,
+            remaining_inventory: 10,
+            per_user_limit: 1,
+            admission: AdmissionBudget::default(),
+            guards: OrderGuardSettings::default(),
+            created_at: now,
+        };
+        let command = CreateOrderCommand {
+            order_id: Uuid::from_u128(1),
+            user_id: Uuid::from_u128(2),
+            flash_sale_id: flash_sale.id,
+            quantity: 1,
+            idempotency_key: "key".to_string(),
+            request_fingerprint: String::new(),
+            api_client_id: None,
+        };
+
+        let order = GuardedOrder {
+            command: &command,
+            flash_sale: &flash_sale,
+            now,
+        };
+        assert!(builtin().evaluate(&db_pool, &order).await.is_ok());
+    }
+}
@@ -0,0 +1,116 @@
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{error, info};
+
+use crate::{
+    app::{
+        circuit_breaker::{CircuitBreaker, is_database_failure},
+        heartbeat::Heartbeat,
+    },
+    errors::{AppError, RepoError},
+    logic::order_job_logic::{ProcessedOrderJob, process_next_order_job},
+    ports::{FlashSaleRepo, OrderJobRepo, OrderRepo},
+};
+
+/// Spawn the loop that places orders from the shared job table
+///
+/// Any number of worker processes may run it, each job is claimed by one of
+/// them. The table is polled whenever it runs dry. Once `shutdown` flips the
+/// loop finishes the job in hand and stops. `heartbeat` is beaten on every
+/// pass, busy or idle. While `db_breaker` is open no jobs are claimed, they
+/// stay pending until the database recovers.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_order_job_worker(
+    db_pool: sqlx::PgPool,
+    order_job_repo: Arc<dyn OrderJobRepo>,
+    flash_sale_repo: Arc<dyn FlashSaleRepo>,
+    order_repo: Arc<dyn OrderRepo>,
+    db_breaker: Arc<CircuitBreaker>,
+    heartbeat: Arc<Heartbeat>,
+    poll_interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Order job worker started");
+
+        while !*shutdown.borrow() {
+            heartbeat.beat();
+
+            let Ok(permit) = db_breaker.try_acquire() else {
+                let wait = db_breaker.retry_after().unwrap_or(poll_interval);
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = shutdown.changed() => {}
+                }
+                continue;
+            };
+
+            let result = process_next(
+                &db_pool,
+                order_job_repo.as_ref(),
+                flash_sale_repo.as_ref(),
+                order_repo.as_ref(),
+            )
+            .await;
+
+            permit.record(match &result {
+                Ok(Some(job)) => !matches!(&job.result, Err(e) if is_database_failure(e)),
+                Ok(None) => true,
+                Err(e) => !is_database_failure(e),
+            });
+
+            let idle = match result {
+                Ok(Some(ProcessedOrderJob { order_id, result })) => {
+                    match result {
+                        Ok(_) => {
+                            metrics::counter!("order_jobs_processed_total", "result" => "completed")
+                                .increment(1);
+                            info!(order_id = %order_id, "Order processed successfully");
+                        }
+                        Err(e) => {
+                            metrics::counter!("order_jobs_processed_total", "result" => "failed")
+                                .increment(1);
+                            info!(order_id = %order_id, error = ?e, "Order processing failed");
+                        }
+                    }
+                    false
+                }
+                Ok(None) => true,
+                Err(e) => {
+                    error!(error = ?e, "Failed to process order job");
+                    true
+                }
+            };
+
+            if idle {
+                tokio::select! {
+                    _ = tokio::time::sleep(poll_interval) => {}
+                    _ = shutdown.changed() => {}
+                }
+            }
+        }
+
+        info!("Order job worker shutting down");
+    })
+}
+
+async fn process_next(
+    db_pool: &sqlx::PgPool,
+    order_job_repo: &dyn OrderJobRepo,
+    flash_sale_repo: &dyn FlashSaleRepo,
+    order_repo: &dyn OrderRepo,
+) -> Result<Option<ProcessedOrderJob>, AppError> {
+    let mut tx = db_pool
+        .begin()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    let processed =
+        process_next_order_job(&mut tx, order_job_repo, flash_sale_repo, order_repo).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    Ok(processed)
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    app::config::PriorityLanesConfig,
+    domain::{
+        auth::{Caller, UserTier},
+        flash_sale::FlashSale,
+    },
+    logic::order_logic::CreateOrderCommand,
+};
+
+/// Line of the order queue an order waits in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderLane {
+    Retry,
+    Vip,
+    EarlyAccess,
+    Standard,
+}
+
+impl OrderLane {
+    /// Every lane, in the order the worker visits them each round
+    pub const ALL: [OrderLane; 4] = [Self::Retry, Self::Vip, Self::EarlyAccess, Self::Standard];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Retry => "retry",
+            Self::Vip => "vip",
+            Self::EarlyAccess => "early_access",
+            Self::Standard => "standard",
+        }
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Self::Retry => 0,
+            Self::Vip => 1,
+            Self::EarlyAccess => 2,
+            Self::Standard => 3,
+        }
+    }
+}
+
+/// Orders taken from each lane per round, indexed like [`OrderLane::ALL`]
+#[derive(Debug, Clone, Copy)]
+pub struct LaneWeights([u32; 4]);
+
+impl LaneWeights {
+    pub fn weight(&self, lane: OrderLane) -> u32 {
+        self.0[lane.index()]
+    }
+}
+
+impl From<&PriorityLanesConfig> for LaneWeights {
+    fn from(config: &PriorityLanesConfig) -> Self {
+        Self([
+            config.retry_weight,
+            config.vip_weight,
+            config.early_access_weight,
+            config.standard_weight,
+        ])
+    }
+}
+
+impl Default for LaneWeights {
+    fn default() -> Self {
+        Self::from(&PriorityLanesConfig::default())
+    }
+}
+
+/// What is known about an order when it is admitted
+pub struct LaneRequest<'a> {
+    pub command: &'a CreateOrderCommand,
+    pub caller: &'a Caller,
+    /// The idempotency key was accepted before and its attempt failed
+    pub is_retry: bool,
+    /// `None` when the sale could not be found
+    pub flash_sale: Option<&'a FlashSale>,
+    pub now: DateTime<Utc>,
+}
+
+/// Picks the lane an order waits in
+pub trait LaneClassifier: Send + Sync {
+    fn classify(&self, request: &LaneRequest<'_>) -> OrderLane;
+}
+
+/// Default classifier: retries first, then VIP users, then orders placed
+/// during a sale's early-access window
+pub struct RuleLaneClassifier {
+    early_access_window: Duration,
+}
+
+impl RuleLaneClassifier {
+    pub fn new(config: &PriorityLanesConfig) -> Self {
+        Self {
+            early_access_window: Duration::seconds(config.early_access_window_secs as i64),
+        }
+    }
+}
+
+impl LaneClassifier for RuleLaneClassifier {
+    fn classify(&self, request: &LaneRequest<'_>) -> OrderLane {
+        if request.is_retry {
+            return OrderLane::Retry;
+        }
+        if request.caller.tier() == UserTier::Vip {
+            return OrderLane::Vip;
+        }
+
+        let early_access = request.flash_sale.is_some_and(|sale| {
+            request.now >= sale.start_time
+                && request.now < sale.start_time + self.early_access_window
+        });
+        if early_access {
+            OrderLane::EarlyAccess
+        } else {
+            OrderLane::Standard
+        }
+    }
+}
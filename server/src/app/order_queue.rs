@@ -1,12 +1,31 @@
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use dashmap::DashMap;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Semaphore;
 use tracing::{error, info};
 use uuid::Uuid;
 
 use crate::{
-    domain::order::OrderProcessingStatus,
-    logic::order_logic::{CreateOrderCommand, create_order},
-    ports::{FlashSaleRepo, OrderRepo},
+    app::{
+        adaptive_admission::AdaptiveAdmission,
+        circuit_breaker::{CircuitBreaker, is_database_failure},
+        fair_queue::{FairOrderQueue, QueuedOrder},
+        heartbeat::Heartbeat,
+        order_lanes::OrderLane,
+        sold_out_sales::SoldOutSales,
+    },
+    domain::{
+        order::{OrderProcessingStatus, OrderStatusEntry},
+        order_job::{OrderJobErrorCode, OrderJobStatus, OrderJobTurn},
+    },
+    errors::{AppError, RepoError, ServiceError},
+    logic::{
+        order_job_logic::{enqueue_order_job, get_finished_order_jobs},
+        order_logic::{CreateOrderCommand, create_order, get_order},
+    },
+    ports::{FlashSaleRepo, OrderJobRepo, OrderRepo},
 };
 
 /// Message type for the order queue
@@ -18,82 +37,324 @@ pub struct OrderQueueMessage {
 
 /// Create and spawn the order queue worker
 ///
-/// Returns the sender half of the channel that handlers can use to enqueue orders
+/// The worker takes orders from `queue`, where handlers put them, sale by
+/// sale in weighted turns. It beats `heartbeat` after every order and every
+/// `heartbeat_interval` while idle
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_order_queue_worker(
+    queue: Arc<FairOrderQueue>,
     db_pool: sqlx::PgPool,
     flash_sale_repo: Arc<dyn FlashSaleRepo>,
     order_repo: Arc<dyn OrderRepo>,
-    order_status_store: Arc<dashmap::DashMap<Uuid, OrderProcessingStatus>>,
-    queue_capacity: usize,
-) -> mpsc::Sender<OrderQueueMessage> {
-    let (tx, mut rx) = mpsc::channel::<OrderQueueMessage>(queue_capacity);
-
+    order_status_store: Arc<dashmap::DashMap<Uuid, OrderStatusEntry>>,
+    sold_out_sales: Arc<SoldOutSales>,
+    db_breaker: Arc<CircuitBreaker>,
+    admission: Arc<AdaptiveAdmission>,
+    heartbeat: Arc<Heartbeat>,
+    heartbeat_interval: Duration,
+) {
     tokio::spawn(async move {
         info!(
             "Order queue worker started with capacity {}",
-            queue_capacity
+            queue.capacity()
         );
 
-        while let Some(msg) = rx.recv().await {
-            let OrderQueueMessage { order_id, command } = msg;
-
-            // Record queue depth metric
-            metrics::gauge!("order_queue_depth").set(rx.len() as f64);
+        let mut ticker = tokio::time::interval(heartbeat_interval);
 
-            // Process the order
-            let mut tx = match db_pool.begin().await {
-                Ok(conn) => conn,
-                Err(e) => {
-                    error!(order_id = %order_id, error = ?e, "Failed to acquire DB connection");
-                    // Update status to failed
-                    order_status_store.insert(
-                        order_id,
-                        OrderProcessingStatus::Failed(format!("Database connection failed: {}", e)),
-                    );
+        loop {
+            let QueuedOrder {
+                message: OrderQueueMessage { order_id, command },
+                sequence,
+                ..
+            } = tokio::select! {
+                order = queue.pop() => order,
+                _ = ticker.tick() => {
+                    heartbeat.beat();
                     continue;
                 }
             };
 
-            let result = create_order(
-                &mut *tx,
+            record_queue_depth(&queue);
+
+            let started = Instant::now();
+            let status = process_order(
+                &db_pool,
                 flash_sale_repo.as_ref(),
                 order_repo.as_ref(),
+                sold_out_sales.as_ref(),
+                db_breaker.as_ref(),
+                admission.as_ref(),
+                order_id,
                 command,
             )
             .await;
+            queue.finish(sequence);
+            queue.record_service(1, started.elapsed());
+
+            // Store result in status store, keeping what the handler recorded
+            match order_status_store.get_mut(&order_id) {
+                Some(mut entry) => entry.status = status,
+                None => error!(order_id = %order_id, "Order missing from status store"),
+            }
+            heartbeat.beat();
+        }
+    });
+}
+
+/// Runs one order through its transaction and reports the outcome
+///
+/// The transaction goes through `db_breaker`, so while the database is
+/// failing queued orders fail at once instead of each waiting on it. How long
+/// it took is fed back to `admission`
+#[allow(clippy::too_many_arguments)]
+async fn process_order(
+    db_pool: &sqlx::PgPool,
+    flash_sale_repo: &dyn FlashSaleRepo,
+    order_repo: &dyn OrderRepo,
+    sold_out_sales: &SoldOutSales,
+    db_breaker: &CircuitBreaker,
+    admission: &AdaptiveAdmission,
+    order_id: Uuid,
+    command: CreateOrderCommand,
+) -> OrderProcessingStatus {
+    let flash_sale_id = command.flash_sale_id;
+
+    let transaction = async {
+        let mut tx = db_pool.begin().await.map_err(|e| {
+            error!(order_id = %order_id, error = ?e, "Failed to acquire DB connection");
+            RepoError::ConnectionPool(e.to_string())
+        })?;
+
+        let result = create_order(&mut tx, flash_sale_repo, order_repo, command).await;
+
+        tx.commit().await.map_err(|e| {
+            error!(order_id = %order_id, error = ?e, "Failed to commit transaction");
+            RepoError::Transaction(e.to_string())
+        })?;
+
+        result
+    };
+    let started = Instant::now();
+    let result = db_breaker.call(transaction, is_database_failure).await;
+    record_latency(admission, started.elapsed(), &result);
 
-            let commit_result = tx.commit().await;
-
-            if commit_result.is_err() {
-                error!(order_id = %order_id, error = ?commit_result, "Failed to commit transaction");
-                // Update status to failed
-                order_status_store.insert(
-                    order_id,
-                    OrderProcessingStatus::Failed(format!(
-                        "Transaction commit failed: {}",
-                        commit_result.unwrap_err()
-                    )),
-                );
+    match result {
+        Ok(order) => {
+            info!(order_id = %order_id, "Order processed successfully");
+            OrderProcessingStatus::Completed(order)
+        }
+        Err(e) => {
+            if matches!(e, AppError::Service(ServiceError::SoldOut)) {
+                sold_out_sales.mark(flash_sale_id);
+            }
+            info!(order_id = %order_id, error = ?e, "Order processing failed");
+            OrderProcessingStatus::Failed(e.to_string())
+        }
+    }
+}
+
+/// Orders refused by an open breaker never reached the database and say
+/// nothing about its latency
+fn record_latency<T>(
+    admission: &AdaptiveAdmission,
+    latency: Duration,
+    result: &Result<T, AppError>,
+) {
+    match result {
+        Err(AppError::Service(ServiceError::CircuitOpen { .. })) => {}
+        Err(e) => admission.record(latency, is_database_failure(e)),
+        Ok(_) => admission.record(latency, false),
+    }
+}
+
+fn record_queue_depth(queue: &FairOrderQueue) {
+    metrics::gauge!("order_queue_depth").set(queue.len() as f64);
+    for lane in OrderLane::ALL {
+        metrics::gauge!("order_queue_lane_depth", "lane" => lane.as_str())
+            .set(queue.lane_len(lane) as f64);
+    }
+}
+
+/// Create and spawn the consumer used when workers run in other processes
+///
+/// Admission still goes through the in-memory queue; instead of placing
+/// orders itself the consumer writes each one to the shared job table, in
+/// the same fair order as [`spawn_order_queue_worker`], tagged with its lane
+/// and weighted by its lane and sale, which workers claim by. Each
+/// handed-off order holds one of `handoff_slots` until
+/// [`spawn_order_job_result_sync`] collects its outcome, so when workers fall
+/// behind orders back up in the queue, where the admission limit applies,
+/// rather than in the job table.
+pub fn spawn_order_job_forwarder(
+    queue: Arc<FairOrderQueue>,
+    db_pool: sqlx::PgPool,
+    order_job_repo: Arc<dyn OrderJobRepo>,
+    order_status_store: Arc<dashmap::DashMap<Uuid, OrderStatusEntry>>,
+    awaiting_results: Arc<DashMap<Uuid, u64>>,
+    handoff_slots: Arc<Semaphore>,
+    db_breaker: Arc<CircuitBreaker>,
+) {
+    tokio::spawn(async move {
+        info!(
+            "Order job forwarder started with capacity {}",
+            queue.capacity()
+        );
+
+        loop {
+            // Taken before the order, so it is picked as late as possible
+            match handoff_slots.acquire().await {
+                Ok(slot) => slot.forget(),
+                Err(_) => return,
+            }
+            let QueuedOrder {
+                message: OrderQueueMessage { order_id, command },
+                lane,
+                scheduling_weight,
+                sequence,
+            } = queue.pop().await;
+            let turn = OrderJobTurn {
+                lane: lane.as_str(),
+                weight: queue
+                    .lane_weights()
+                    .weight(lane)
+                    .saturating_mul(scheduling_weight),
+            };
+
+            record_queue_depth(&queue);
+
+            let handoff = async {
+                let mut conn = db_pool
+                    .acquire()
+                    .await
+                    .map_err(|e| RepoError::ConnectionPool(e.to_string()))?;
+                enqueue_order_job(&mut conn, order_job_repo.as_ref(), &command, turn).await
+            };
+            let result = db_breaker.call(handoff, is_database_failure).await;
+
+            match result {
+                Ok(()) => {
+                    awaiting_results.insert(order_id, sequence);
+                }
+                Err(e) => {
+                    queue.finish(sequence);
+                    handoff_slots.add_permits(1);
+                    error!(order_id = %order_id, error = ?e, "Failed to hand off order");
+                    match order_status_store.get_mut(&order_id) {
+                        Some(mut entry) => {
+                            entry.status = OrderProcessingStatus::Failed(format!(
+                                "Order could not be queued: {}",
+                                e
+                            ))
+                        }
+                        None => error!(order_id = %order_id, "Order missing from status store"),
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Most job ids looked up per sync round
+const RESULT_SYNC_BATCH: usize = 1000;
+
+/// Copies outcomes of handed-off orders from the job table into the status store
+///
+/// Each collected outcome frees a slot in `handoff_slots` and finishes the
+/// order in `queue`, whose wait estimates follow how fast outcomes come in.
+/// The time from handoff until a worker finished the job is fed back to
+/// `admission`.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_order_job_result_sync(
+    queue: Arc<FairOrderQueue>,
+    db_pool: sqlx::PgPool,
+    order_job_repo: Arc<dyn OrderJobRepo>,
+    order_repo: Arc<dyn OrderRepo>,
+    order_status_store: Arc<dashmap::DashMap<Uuid, OrderStatusEntry>>,
+    awaiting_results: Arc<DashMap<Uuid, u64>>,
+    handoff_slots: Arc<Semaphore>,
+    sold_out_sales: Arc<SoldOutSales>,
+    admission: Arc<AdaptiveAdmission>,
+    interval: Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // Start of the time workers spent on the outcomes not yet collected
+        let mut busy_since = Instant::now();
+
+        loop {
+            ticker.tick().await;
+
+            metrics::gauge!("order_jobs_awaiting_results").set(awaiting_results.len() as f64);
+            if awaiting_results.is_empty() {
+                busy_since = Instant::now();
                 continue;
             }
 
-            // Store result in status store
-            let status = match result {
-                Ok(order) => {
-                    info!(order_id = %order_id, "Order processed successfully");
-                    OrderProcessingStatus::Completed(order)
+            let ids: Vec<Uuid> = awaiting_results
+                .iter()
+                .take(RESULT_SYNC_BATCH)
+                .map(|entry| *entry.key())
+                .collect();
+
+            let mut conn = match db_pool.acquire().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!(error = ?e, "Failed to acquire DB connection for order results");
+                    continue;
                 }
+            };
+
+            let jobs = match get_finished_order_jobs(&mut conn, order_job_repo.as_ref(), &ids).await
+            {
+                Ok(jobs) => jobs,
                 Err(e) => {
-                    info!(order_id = %order_id, error = ?e, "Order processing failed");
-                    OrderProcessingStatus::Failed(e.to_string())
+                    error!(error = ?e, "Failed to load order job results");
+                    continue;
                 }
             };
 
-            order_status_store.insert(order_id, status);
-        }
+            let mut collected = 0;
+            for job in jobs {
+                let order_id = job.command.order_id;
+                // Jobs don't record whether they failed on the database, a
+                // struggling database shows in how long they took instead
+                let latency = (job.updated_at - job.created_at)
+                    .to_std()
+                    .unwrap_or_default();
+                let status = match job.status {
+                    OrderJobStatus::Completed => {
+                        match get_order(&mut conn, order_repo.as_ref(), order_id).await {
+                            Ok(order) => OrderProcessingStatus::Completed(order),
+                            Err(e) => {
+                                error!(order_id = %order_id, error = ?e, "Failed to load completed order");
+                                continue;
+                            }
+                        }
+                    }
+                    OrderJobStatus::Failed => {
+                        if job.error_code == Some(OrderJobErrorCode::SoldOut) {
+                            sold_out_sales.mark(job.command.flash_sale_id);
+                        }
+                        OrderProcessingStatus::Failed(job.error.unwrap_or_default())
+                    }
+                    OrderJobStatus::Pending => continue,
+                };
 
-        info!("Order queue worker shutting down");
+                match order_status_store.get_mut(&order_id) {
+                    Some(mut entry) => entry.status = status,
+                    None => error!(order_id = %order_id, "Order missing from status store"),
+                }
+                if let Some((_, sequence)) = awaiting_results.remove(&order_id) {
+                    queue.finish(sequence);
+                    handoff_slots.add_permits(1);
+                    admission.record(latency, false);
+                    collected += 1;
+                }
+            }
+            if collected > 0 {
+                queue.record_service(collected, busy_since.elapsed());
+                busy_since = Instant::now();
+            }
+        }
     });
-
-    tx
 }
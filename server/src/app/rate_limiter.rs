@@ -0,0 +1,199 @@
+use async_trait::async_trait;
+use dashmap::DashMap;
+use governor::{
+    Quota, RateLimiter,
+    clock::{Clock, DefaultClock},
+    middleware::StateInformationMiddleware,
+    state::keyed::DashMapStateStore,
+};
+use std::{num::NonZeroU32, sync::Arc, time::Duration};
+use tracing::debug;
+
+use crate::{
+    app::{
+        circuit_breaker::{CircuitBreaker, is_database_failure},
+        config::{CircuitBreakerConfig, RateLimitsConfig},
+    },
+    domain::rate_limit::{RateLimitDecision, RateQuota},
+    errors::{AppError, RepoError},
+    logic::rate_limit_logic::{acquire_rate_limit, purge_expired_rate_limits},
+    ports::RateLimitRepo,
+};
+
+type KeyedLimiter =
+    RateLimiter<String, DashMapStateStore<String>, DefaultClock, StateInformationMiddleware>;
+
+/// Keeps request budgets for rate limits
+///
+/// Keys are namespaced by the caller, e.g. `orders:user:<id>`, and the quota
+/// is passed on every check so it can follow runtime settings.
+#[async_trait]
+pub trait RateLimiterBackend: Send + Sync {
+    /// Counts one request of `key` against `quota`
+    async fn check(&self, key: &str, quota: RateQuota) -> RateLimitDecision;
+    /// Forgets budgets that have refilled completely
+    async fn purge(&self);
+}
+
+/// Budgets held in this process
+///
+/// Keys sharing a quota share one governor limiter, so a changed quota
+/// starts its keys with a full budget.
+#[derive(Default)]
+pub struct InMemoryRateLimiter {
+    limiters: DashMap<RateQuota, Arc<KeyedLimiter>>,
+}
+
+impl InMemoryRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn check_now(&self, key: &str, quota: RateQuota) -> RateLimitDecision {
+        if quota.per_second == 0 {
+            return RateLimitDecision::refused(quota, Duration::from_secs(1));
+        }
+
+        let limiter = self
+            .limiters
+            .entry(quota)
+            .or_insert_with(|| Arc::new(keyed_limiter(quota)))
+            .clone();
+
+        match limiter.check_key(&key.to_string()) {
+            Ok(snapshot) => RateLimitDecision::allowed(quota, snapshot.remaining_burst_capacity()),
+            Err(not_until) => {
+                RateLimitDecision::refused(quota, not_until.wait_time_from(limiter.clock().now()))
+            }
+        }
+    }
+
+    fn purge_now(&self) {
+        self.limiters.retain(|_, limiter| {
+            limiter.retain_recent();
+            limiter.shrink_to_fit();
+            !limiter.is_empty()
+        });
+    }
+}
+
+fn keyed_limiter(quota: RateQuota) -> KeyedLimiter {
+    let rate = NonZeroU32::new(quota.per_second).unwrap_or(NonZeroU32::MIN);
+    let burst = NonZeroU32::new(quota.burst).unwrap_or(NonZeroU32::MIN);
+    RateLimiter::keyed(Quota::per_second(rate).allow_burst(burst))
+        .with_middleware::<StateInformationMiddleware>()
+}
+
+#[async_trait]
+impl RateLimiterBackend for InMemoryRateLimiter {
+    async fn check(&self, key: &str, quota: RateQuota) -> RateLimitDecision {
+        self.check_now(key, quota)
+    }
+
+    async fn purge(&self) {
+        self.purge_now();
+    }
+}
+
+/// Budgets kept in the database, so every instance draws on the same ones
+///
+/// Each check is one atomic GCRA update. When the database is slow or failing,
+/// or the breaker in front of it is open, the instance falls back to strict
+/// local limits: `fallback_quota_share` of each quota, so a few instances
+/// limiting on their own stay near the shared budget.
+pub struct SharedRateLimiter {
+    db_pool: sqlx::PgPool,
+    repo: Arc<dyn RateLimitRepo>,
+    breaker: CircuitBreaker,
+    timeout: Duration,
+    fallback: InMemoryRateLimiter,
+    fallback_quota_share: f64,
+}
+
+impl SharedRateLimiter {
+    pub fn new(
+        db_pool: sqlx::PgPool,
+        repo: Arc<dyn RateLimitRepo>,
+        config: &RateLimitsConfig,
+        breaker_config: CircuitBreakerConfig,
+    ) -> Self {
+        Self {
+            db_pool,
+            repo,
+            breaker: CircuitBreaker::new("rate_limiter", breaker_config),
+            timeout: Duration::from_millis(config.shared_timeout_ms),
+            fallback: InMemoryRateLimiter::new(),
+            fallback_quota_share: config.fallback_quota_share,
+        }
+    }
+
+    async fn check_shared(
+        &self,
+        key: &str,
+        quota: RateQuota,
+    ) -> Result<RateLimitDecision, AppError> {
+        let acquire = async {
+            let mut conn = self
+                .db_pool
+                .acquire()
+                .await
+                .map_err(|e| RepoError::ConnectionPool(e.to_string()))?;
+            acquire_rate_limit(&mut conn, self.repo.as_ref(), key, quota).await
+        };
+
+        tokio::time::timeout(self.timeout, acquire)
+            .await
+            .unwrap_or_else(|_| {
+                Err(AppError::Repo(RepoError::ConnectionPool(
+                    "rate limit backend timed out".to_string(),
+                )))
+            })
+    }
+}
+
+#[async_trait]
+impl RateLimiterBackend for SharedRateLimiter {
+    async fn check(&self, key: &str, quota: RateQuota) -> RateLimitDecision {
+        if quota.per_second == 0 {
+            return RateLimitDecision::refused(quota, Duration::from_secs(1));
+        }
+
+        match self
+            .breaker
+            .call(self.check_shared(key, quota), is_database_failure)
+            .await
+        {
+            Ok(decision) => decision,
+            Err(e) => {
+                debug!(error = %e, "Shared rate limiter unavailable, limiting locally");
+                metrics::counter!("rate_limiter_fallback_total").increment(1);
+                self.fallback
+                    .check_now(key, quota.scaled(self.fallback_quota_share))
+            }
+        }
+    }
+
+    async fn purge(&self) {
+        self.fallback.purge_now();
+
+        let result = match self.db_pool.acquire().await {
+            Ok(mut conn) => purge_expired_rate_limits(&mut conn, self.repo.as_ref()).await,
+            Err(e) => Err(RepoError::ConnectionPool(e.to_string()).into()),
+        };
+        match result {
+            Ok(removed) => tracing::debug!("Removed {} refilled rate limit buckets", removed),
+            Err(e) => tracing::error!(error = ?e, "Failed to remove refilled rate limit buckets"),
+        }
+    }
+}
+
+/// Periodically drops refilled budgets so idle keys don't pile up
+pub fn spawn_rate_limit_cleanup(backend: Arc<dyn RateLimiterBackend>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            backend.purge().await;
+        }
+    });
+}
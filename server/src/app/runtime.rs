@@ -1,64 +1,95 @@
-use std::sync::Arc;
-use tracing_subscriber::EnvFilter;
+use std::{sync::Arc, time::Duration};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt};
 
 use crate::{
     adapters::{
         db::{
             pool::create_pool, product::repository::PostgresProductRepo,
-            user::repository::PostgresUserRepo,
+            rate_limit::repository::PostgresRateLimitRepo, user::repository::PostgresUserRepo,
         },
-        http::router::http_router,
+        http::router::{http_router, worker_router},
+    },
+    app::{
+        adaptive_admission::AdaptiveAdmission,
+        circuit_breaker::CircuitBreaker,
+        config::{Config, RateLimitBackendKind},
+        config_watcher::spawn_config_watcher,
+        fair_queue::FairOrderQueue,
+        flash_sale_cache::FlashSaleCache,
+        health::{HealthChecker, WorkerLiveness},
+        heartbeat::{Heartbeat, spawn_heartbeat_publisher},
+        order_challenge::{OrderChallenges, spawn_order_challenge_sync},
+        order_guards::OrderGuards,
+        order_job_worker::spawn_order_job_worker,
+        order_lanes::{LaneWeights, RuleLaneClassifier},
+        rate_limiter::{
+            InMemoryRateLimiter, RateLimiterBackend, SharedRateLimiter, spawn_rate_limit_cleanup,
+        },
+        runtime_settings::{RuntimeSettingsStore, log_filter},
+        sold_out_sales::SoldOutSales,
+        state::{AppState, WorkerState},
     },
-    app::{config::Config, state::AppState},
 };
 
-pub async fn run() -> anyhow::Result<()> {
-    // Load configuration first to get log settings
-    let config = Config::from_env()?;
+/// Which parts of the service a process runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// HTTP API with the order worker in the same process
+    All,
+    /// HTTP API only, admitted orders are handed to worker processes
+    /// through the `order_jobs` table
+    Api,
+    /// Order worker only, consuming the `order_jobs` table
+    Worker,
+}
 
+pub async fn run(config: Config, role: Role) -> anyhow::Result<()> {
     // Create logs directory if it doesn't exist
-    std::fs::create_dir_all(&config.log_dir)?;
+    std::fs::create_dir_all(&config.logging.dir)?;
 
     // Set up file appender with daily rotation
-    let file_appender = tracing_appender::rolling::daily(&config.log_dir, "flash-sale.log");
+    let file_appender = tracing_appender::rolling::daily(&config.logging.dir, "flash-sale.log");
     let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
 
     // Initialize tracing subscriber with file output
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::new(&config.log_level).add_directive("sqlx::query=info".parse().unwrap()),
+    // The filter sits behind a reload layer so the level can change at runtime
+    let (filter, log_filter_handle) = reload::Layer::new(log_filter(&config.logging.level)?);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_thread_ids(false)
+                .with_file(false)
+                .with_line_number(false)
+                .json() // Maintain JSON formatting for structured logs
+                .with_writer(non_blocking),
         )
-        .with_target(false)
-        .with_thread_ids(false)
-        .with_file(false)
-        .with_line_number(false)
-        .json() // Maintain JSON formatting for structured logs
-        .with_writer(non_blocking)
         .init();
 
     tracing::info!(
         "Logging initialized: dir={}, level={}",
-        config.log_dir,
-        config.log_level
+        config.logging.dir,
+        config.logging.level
     );
-    tracing::debug!("Configuration loaded: {:?}", config);
+    tracing::info!("Configuration loaded:\n{}", config.redacted());
+    tracing::info!("Starting with role {:?}", role);
 
     // Initialize Prometheus metrics with Histogram buckets
     let prometheus_handle = metrics_exporter_prometheus::PrometheusBuilder::new()
-        .set_buckets(&[
-            0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
-        ])
+        .set_buckets(&config.metrics.histogram_buckets)
         .expect("Failed to set buckets")
         .install_recorder()
         .expect("failed to install Prometheus recorder");
     tracing::info!("Prometheus metrics initialized");
 
-    let pool = create_pool(&config).await?;
+    let pool = create_pool(&config.database).await?;
 
     // Periodically record SQLx pool metrics
     let pool_clone = pool.clone();
+    let sample_interval = Duration::from_secs(config.metrics.pool_sample_interval_secs);
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+        let mut interval = tokio::time::interval(sample_interval);
         loop {
             interval.tick().await;
 
@@ -72,6 +103,10 @@ pub async fn run() -> anyhow::Result<()> {
         }
     });
 
+    if role == Role::Worker {
+        return run_worker(&config, pool, prometheus_handle).await;
+    }
+
     let user_repo = Arc::new(PostgresUserRepo::new()) as Arc<dyn crate::ports::user_repo::UserRepo>;
     tracing::debug!("initialized repository: User");
 
@@ -88,51 +123,340 @@ pub async fn run() -> anyhow::Result<()> {
         as Arc<dyn crate::ports::order_repo::OrderRepo>;
     tracing::debug!("initialized repository: Order");
 
-    // Initialize order queue worker
-    const ORDER_QUEUE_CAPACITY: usize = 100;
+    let idempotency_repo =
+        Arc::new(crate::adapters::db::idempotency::repository::PostgresIdempotencyRepo::new())
+            as Arc<dyn crate::ports::idempotency_repo::IdempotencyRepo>;
+    tracing::debug!("initialized repository: Idempotency");
+
+    let api_key_repo = Arc::new(crate::adapters::db::api_key::repository::PostgresApiKeyRepo::new())
+        as Arc<dyn crate::ports::api_key_repo::ApiKeyRepo>;
+    tracing::debug!("initialized repository: ApiKey");
+
+    let settings_audit_repo =
+        Arc::new(crate::adapters::db::settings_audit::repository::PostgresSettingsAuditRepo::new())
+            as Arc<dyn crate::ports::settings_audit_repo::SettingsAuditRepo>;
+    tracing::debug!("initialized repository: SettingsAudit");
+
+    let order_challenge_repo = Arc::new(
+        crate::adapters::db::order_challenge::repository::PostgresOrderChallengeRepo::new(),
+    )
+        as Arc<dyn crate::ports::order_challenge_repo::OrderChallengeRepo>;
+    tracing::debug!("initialized repository: OrderChallenge");
+    let order_guard_repo =
+        Arc::new(crate::adapters::db::order_guard::repository::PostgresOrderGuardRepo::new())
+            as Arc<dyn crate::ports::order_guard_repo::OrderGuardRepo>;
+    tracing::debug!("initialized repository: OrderGuard");
+    let order_guards = Arc::new(OrderGuards::builtin(
+        user_repo.clone(),
+        order_repo.clone(),
+        order_guard_repo.clone(),
+    ));
+
+    // Periodically remove expired idempotency records
+    let pool_clone = pool.clone();
+    let idempotency_repo_clone = idempotency_repo.clone();
+    let cleanup_interval = Duration::from_secs(config.idempotency.cleanup_interval_secs);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(cleanup_interval);
+        loop {
+            interval.tick().await;
 
+            let result = match pool_clone.acquire().await {
+                Ok(mut conn) => {
+                    crate::logic::idempotency_logic::purge_expired_idempotency_records(
+                        &mut conn,
+                        idempotency_repo_clone.as_ref(),
+                    )
+                    .await
+                }
+                Err(e) => Err(crate::errors::RepoError::ConnectionPool(e.to_string()).into()),
+            };
+
+            match result {
+                Ok(removed) => {
+                    metrics::counter!("idempotency_records_expired_total").increment(removed);
+                    tracing::debug!("Removed {} expired idempotency records", removed);
+                }
+                Err(e) => {
+                    tracing::error!(error = ?e, "Failed to remove expired idempotency records")
+                }
+            }
+        }
+    });
+
+    // Initialize order queue worker
     // Create the order status store
     let order_status_store = std::sync::Arc::new(dashmap::DashMap::new());
+    let sold_out_sales = Arc::new(SoldOutSales::new());
+
+    let heartbeat_interval = Duration::from_millis(config.health.heartbeat_interval_ms);
+    let db_breaker = Arc::new(CircuitBreaker::new(
+        "database",
+        config.circuit_breaker.clone(),
+    ));
+    let admission = Arc::new(AdaptiveAdmission::new(
+        config.adaptive_admission.clone(),
+        config.orders.queue_capacity,
+    ));
+
+    let order_queue = Arc::new(FairOrderQueue::new(
+        config.orders.queue_capacity,
+        LaneWeights::from(&config.priority_lanes),
+    ));
+
+    let worker_liveness = if role == Role::All {
+        let heartbeat = Arc::new(Heartbeat::new());
+        crate::app::order_queue::spawn_order_queue_worker(
+            order_queue.clone(),
+            pool.clone(),
+            flash_sale_repo.clone(),
+            order_repo.clone(),
+            order_status_store.clone(),
+            sold_out_sales.clone(),
+            db_breaker.clone(),
+            admission.clone(),
+            heartbeat.clone(),
+            heartbeat_interval,
+        );
+        tracing::info!(
+            "Order queue worker spawned with capacity {}",
+            config.orders.queue_capacity
+        );
+        WorkerLiveness::Local(heartbeat)
+    } else {
+        // Orders are placed by worker processes, this one only hands them off
+        // and collects the outcome
+        let order_job_repo =
+            Arc::new(crate::adapters::db::order_job::repository::PostgresOrderJobRepo::new())
+                as Arc<dyn crate::ports::order_job_repo::OrderJobRepo>;
+        let awaiting_results = Arc::new(dashmap::DashMap::new());
+        let handoff_slots = Arc::new(tokio::sync::Semaphore::new(config.worker.handoff_window));
 
-    let order_queue_tx = crate::app::order_queue::spawn_order_queue_worker(
+        crate::app::order_queue::spawn_order_job_forwarder(
+            order_queue.clone(),
+            pool.clone(),
+            order_job_repo.clone(),
+            order_status_store.clone(),
+            awaiting_results.clone(),
+            handoff_slots.clone(),
+            db_breaker.clone(),
+        );
+        crate::app::order_queue::spawn_order_job_result_sync(
+            order_queue.clone(),
+            pool.clone(),
+            order_job_repo,
+            order_repo.clone(),
+            order_status_store.clone(),
+            awaiting_results,
+            handoff_slots,
+            sold_out_sales.clone(),
+            admission.clone(),
+            Duration::from_millis(config.worker.result_sync_interval_ms),
+        );
+        tracing::info!(
+            "Order job forwarder spawned with capacity {}",
+            config.orders.queue_capacity
+        );
+        let worker_heartbeat_repo = Arc::new(
+            crate::adapters::db::worker_heartbeat::repository::PostgresWorkerHeartbeatRepo::new(),
+        )
+            as Arc<dyn crate::ports::worker_heartbeat_repo::WorkerHeartbeatRepo>;
+        WorkerLiveness::Shared(worker_heartbeat_repo)
+    };
+
+    let health = Arc::new(HealthChecker::new(
         pool.clone(),
-        flash_sale_repo.clone(),
-        order_repo.clone(),
-        order_status_store.clone(),
-        ORDER_QUEUE_CAPACITY,
+        config.health.clone(),
+        Some(order_queue.clone()),
+        worker_liveness,
+        db_breaker.clone(),
+    ));
+
+    // Runtime settings start from the config and may be changed while running
+    let runtime_settings = Arc::new(RuntimeSettingsStore::new(
+        config.runtime_settings(),
+        config.orders.queue_capacity,
+        Some(log_filter_handle),
+    ));
+
+    if let Some(path) = config.source_file.clone()
+        && config.server.config_watch_interval_secs > 0
+    {
+        spawn_config_watcher(
+            path.clone(),
+            config.runtime_settings(),
+            Duration::from_secs(config.server.config_watch_interval_secs),
+            pool.clone(),
+            settings_audit_repo.clone(),
+            runtime_settings.clone(),
+        );
+        tracing::info!("Watching config file {} for changes", path.display());
+    }
+
+    // Initialize rate limiter backend
+    let rate_limiter: Arc<dyn RateLimiterBackend> = match config.rate_limits.backend {
+        RateLimitBackendKind::Memory => Arc::new(InMemoryRateLimiter::new()),
+        RateLimitBackendKind::Postgres => Arc::new(SharedRateLimiter::new(
+            pool.clone(),
+            Arc::new(PostgresRateLimitRepo::new()),
+            &config.rate_limits,
+            config.circuit_breaker.clone(),
+        )),
+    };
+    spawn_rate_limit_cleanup(
+        rate_limiter.clone(),
+        Duration::from_secs(config.rate_limits.cleanup_interval_secs),
     );
     tracing::info!(
-        "Order queue worker spawned with capacity {}",
-        ORDER_QUEUE_CAPACITY
+        "Rate limiter initialized: {} backend, {} req/s per user",
+        config.rate_limits.backend.as_str(),
+        config.orders.rate_limit_per_user
     );
 
-    // Initialize rate limiter (10 requests per second per user)
-    const RATE_LIMIT_PER_USER: u32 = 10;
-    let rate_limiter = crate::adapters::http::middleware::UserRateLimiter::new(RATE_LIMIT_PER_USER);
-    tracing::info!(
-        "Rate limiter initialized: {} req/s per user",
-        RATE_LIMIT_PER_USER
+    // Challenge difficulty is shared by all instances
+    let order_challenges = Arc::new(OrderChallenges::from_config(&config.challenge)?);
+    spawn_order_challenge_sync(
+        order_challenges.clone(),
+        pool.clone(),
+        order_challenge_repo.clone(),
+        Duration::from_millis(config.challenge.sync_interval_ms),
     );
 
-    let app = http_router(AppState {
-        user_repo,
-        product_repo,
+    let token_verifier = Arc::new(crate::adapters::auth::JwtTokenVerifier::from_config(
+        &config.jwt,
+    )?) as Arc<dyn crate::ports::token_verifier::TokenVerifier>;
+    tracing::info!("JWT verification initialized: {}", config.jwt.algorithm);
+
+    let app = http_router(
+        AppState {
+            user_repo,
+            product_repo,
+            flash_sale_repo,
+            order_repo,
+            idempotency_repo,
+            api_key_repo,
+            settings_audit_repo,
+            order_challenge_repo,
+            order_guard_repo,
+            db_pool: pool,
+            prometheus_handle,
+            order_queue,
+            rate_limiter,
+            route_rate_limits: Arc::new(crate::adapters::http::middleware::RouteRateLimits::new(
+                &config.rate_limits,
+            )),
+            default_sale_queue_share: config.orders.default_sale_queue_share,
+            lane_classifier: Arc::new(RuleLaneClassifier::new(&config.priority_lanes)),
+            order_challenges,
+            order_guards,
+            token_verifier,
+            order_status_store,
+            idempotency_ttl: chrono::Duration::seconds(config.idempotency.ttl_secs as i64),
+            idempotency_lease: chrono::Duration::seconds(
+                config.idempotency.in_progress_lease_secs as i64,
+            ),
+            flash_sale_cache: Arc::new(FlashSaleCache::new(Duration::from_millis(
+                config.flash_sales.cache_ttl_ms,
+            ))),
+            stock_display: config.flash_sales.stock_display,
+            runtime_settings,
+            sold_out_sales,
+            health,
+            db_breaker,
+            admission,
+        },
+        config.request_timeout(),
+    );
+    tracing::debug!("HTTP router configured");
+
+    let listener = tokio::net::TcpListener::bind(&config.server.http_addr).await?;
+
+    tracing::info!("Server listening on {}", config.server.http_addr);
+    // Peer addresses are kept for rate limits keyed by IP
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+    Ok(())
+}
+
+/// Places orders from the job table and serves only health and metrics
+async fn run_worker(
+    config: &Config,
+    pool: sqlx::PgPool,
+    prometheus_handle: metrics_exporter_prometheus::PrometheusHandle,
+) -> anyhow::Result<()> {
+    let flash_sale_repo =
+        Arc::new(crate::adapters::db::flash_sale::repository::PostgresFlashSaleRepo::new())
+            as Arc<dyn crate::ports::flash_sale_repo::FlashSaleRepo>;
+    let order_repo = Arc::new(crate::adapters::db::order::repository::PostgresOrderRepo::new())
+        as Arc<dyn crate::ports::order_repo::OrderRepo>;
+    let order_job_repo =
+        Arc::new(crate::adapters::db::order_job::repository::PostgresOrderJobRepo::new())
+            as Arc<dyn crate::ports::order_job_repo::OrderJobRepo>;
+    let worker_heartbeat_repo = Arc::new(
+        crate::adapters::db::worker_heartbeat::repository::PostgresWorkerHeartbeatRepo::new(),
+    )
+        as Arc<dyn crate::ports::worker_heartbeat_repo::WorkerHeartbeatRepo>;
+    tracing::debug!("initialized repositories: FlashSale, Order, OrderJob, WorkerHeartbeat");
+
+    let db_breaker = Arc::new(CircuitBreaker::new(
+        "database",
+        config.circuit_breaker.clone(),
+    ));
+    let heartbeat = Arc::new(Heartbeat::new());
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let worker = spawn_order_job_worker(
+        pool.clone(),
+        order_job_repo,
         flash_sale_repo,
         order_repo,
-        db_pool: pool,
-        prometheus_handle,
-        order_queue_tx,
-        rate_limiter,
-        order_status_store,
-    });
-    tracing::debug!("HTTP router configured");
+        db_breaker.clone(),
+        heartbeat.clone(),
+        Duration::from_millis(config.worker.poll_interval_ms),
+        shutdown_rx.clone(),
+    );
+    let publisher = spawn_heartbeat_publisher(
+        pool.clone(),
+        worker_heartbeat_repo,
+        heartbeat.clone(),
+        Duration::from_millis(config.health.heartbeat_interval_ms),
+        shutdown_rx,
+    );
+
+    let health = Arc::new(HealthChecker::new(
+        pool,
+        config.health.clone(),
+        None,
+        WorkerLiveness::Local(heartbeat),
+        db_breaker,
+    ));
+
+    let token_verifier = Arc::new(crate::adapters::auth::JwtTokenVerifier::from_config(
+        &config.jwt,
+    )?) as Arc<dyn crate::ports::token_verifier::TokenVerifier>;
+
+    let listener = tokio::net::TcpListener::bind(&config.worker.http_addr).await?;
 
-    let listener = tokio::net::TcpListener::bind(&config.http_addr).await?;
+    tracing::info!("Worker listening on {}", config.worker.http_addr);
+    axum::serve(
+        listener,
+        worker_router(WorkerState {
+            prometheus_handle,
+            health,
+            token_verifier,
+        }),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
 
-    tracing::info!("Server listening on {}", config.http_addr);
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    // Let the job in hand commit before exiting
+    shutdown_tx.send_replace(true);
+    worker.await?;
+    publisher.await?;
 
     Ok(())
 }
@@ -0,0 +1,121 @@
+use arc_swap::ArcSwap;
+use sqlx::PgConnection;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing_subscriber::{EnvFilter, Registry, filter::ParseError, reload};
+use uuid::Uuid;
+
+use crate::{
+    domain::runtime_settings::{
+        RuntimeSettings, RuntimeSettingsPatch, SettingsChange, SettingsChangeSource, SoldOutGate,
+    },
+    errors::AppError,
+    logic::settings_logic,
+    ports::SettingsAuditRepo,
+};
+
+/// Handle for swapping the filter of the installed log subscriber
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Service log filter for a level directive, SQL statements stay at info
+pub fn log_filter(level: &str) -> Result<EnvFilter, ParseError> {
+    Ok(EnvFilter::try_new(level)?.add_directive("sqlx::query=info".parse()?))
+}
+
+/// Current runtime settings, swapped atomically while requests read them
+///
+/// Readers take a snapshot with [`RuntimeSettingsStore::current`] and never
+/// block; updates are serialized, audited and then published.
+pub struct RuntimeSettingsStore {
+    current: ArcSwap<RuntimeSettings>,
+    /// Size of the order queue, fixed at startup
+    queue_capacity: usize,
+    log_filter: Option<LogFilterHandle>,
+    update_lock: Mutex<()>,
+}
+
+impl RuntimeSettingsStore {
+    pub fn new(
+        initial: RuntimeSettings,
+        queue_capacity: usize,
+        log_filter: Option<LogFilterHandle>,
+    ) -> Self {
+        record_settings(&initial);
+
+        Self {
+            current: ArcSwap::from_pointee(initial),
+            queue_capacity,
+            log_filter,
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn current(&self) -> Arc<RuntimeSettings> {
+        self.current.load_full()
+    }
+
+    pub fn queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+
+    /// Validates, audits and publishes a change
+    ///
+    /// A patch that changes nothing is neither audited nor counted.
+    pub async fn update<R: SettingsAuditRepo + ?Sized>(
+        &self,
+        conn: &mut PgConnection,
+        audit_repo: &R,
+        patch: RuntimeSettingsPatch,
+        source: SettingsChangeSource,
+        changed_by: Option<Uuid>,
+    ) -> Result<Arc<RuntimeSettings>, AppError> {
+        let _guard = self.update_lock.lock().await;
+
+        let previous = self.current.load_full();
+        let next = previous.apply(patch);
+        if next == *previous {
+            return Ok(previous);
+        }
+        next.validate(self.queue_capacity)?;
+
+        let change = SettingsChange::new(source, changed_by, (*previous).clone(), next.clone());
+        settings_logic::record_settings_change(conn, audit_repo, &change).await?;
+
+        if next.log_level != previous.log_level
+            && let Some(handle) = &self.log_filter
+        {
+            let reloaded = log_filter(&next.log_level)
+                .map_err(|e| e.to_string())
+                .and_then(|filter| handle.reload(filter).map_err(|e| e.to_string()));
+            if let Err(e) = reloaded {
+                tracing::error!(error = %e, "Failed to apply log level {}", next.log_level);
+            }
+        }
+
+        let next = Arc::new(next);
+        self.current.store(Arc::clone(&next));
+
+        record_settings(&next);
+        metrics::counter!("runtime_settings_changes_total", "source" => source.as_str())
+            .increment(1);
+        tracing::info!(
+            change_id = %change.id,
+            source = source.as_str(),
+            changed_by = ?changed_by,
+            previous = ?change.previous,
+            current = ?change.current,
+            "Runtime settings changed"
+        );
+
+        Ok(next)
+    }
+}
+
+fn record_settings(settings: &RuntimeSettings) {
+    metrics::gauge!("runtime_rate_limit_per_user").set(settings.rate_limit_per_user as f64);
+    metrics::gauge!("runtime_queue_admission_limit").set(settings.queue_admission_limit as f64);
+    metrics::gauge!("runtime_sold_out_gate_enabled").set(match settings.sold_out_gate {
+        SoldOutGate::Reject => 1.0,
+        SoldOutGate::Off => 0.0,
+    });
+}
@@ -0,0 +1,105 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    adapters::db::{
+        flash_sale::repository::PostgresFlashSaleRepo, product::repository::PostgresProductRepo,
+        user::repository::PostgresUserRepo,
+    },
+    domain::{flash_sale::AdmissionBudget, order_guard::OrderGuardSettings},
+    errors::{AppError, RepoError},
+    logic::{
+        CreateFlashSaleCommand, CreateProductCommand, CreateUserCommand, create_flash_sale,
+        save_product, save_user,
+    },
+};
+
+/// What `seed` creates
+#[derive(Debug, Clone)]
+pub struct SeedOptions {
+    pub users: u32,
+    pub sales: u32,
+    pub inventory: i32,
+    pub per_user_limit: i32,
+    /// Sales must start in the future, so they open this long after seeding
+    pub starts_in: Duration,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct SeedSummary {
+    pub user_ids: Vec<Uuid>,
+    pub flash_sale_ids: Vec<Uuid>,
+}
+
+/// Creates users and flash sales, each sale with its own product
+///
+/// Everything goes through the regular validation and is committed in one
+/// transaction. Names carry a per-run tag so repeated runs don't collide.
+pub async fn seed(pool: &sqlx::PgPool, options: &SeedOptions) -> Result<SeedSummary, AppError> {
+    let user_repo = PostgresUserRepo::new();
+    let product_repo = PostgresProductRepo::new();
+    let flash_sale_repo = PostgresFlashSaleRepo::new();
+
+    let run = &Uuid::new_v4().simple().to_string()[..8];
+    let mut summary = SeedSummary::default();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    for i in 1..=options.users {
+        let user = save_user(
+            &mut tx,
+            &user_repo,
+            CreateUserCommand {
+                id: Uuid::new_v4(),
+                email: format!("seed-{run}-{i}@example.com"),
+                username: format!("seed_{run}_{i}"),
+            },
+        )
+        .await?;
+        summary.user_ids.push(user.id);
+    }
+
+    let start_time = Utc::now() + options.starts_in;
+    for i in 1..=options.sales {
+        let product = save_product(
+            &mut tx,
+            &product_repo,
+            CreateProductCommand {
+                id: Uuid::new_v4(),
+                name: format!("Seed product {run}-{i}"),
+                description: None,
+                sku: None,
+                image_urls: Vec::new(),
+            },
+        )
+        .await?;
+
+        let details = create_flash_sale(
+            &mut tx,
+            &flash_sale_repo,
+            &product_repo,
+            CreateFlashSaleCommand {
+                id: Uuid::new_v4(),
+                product_id: product.id,
+                start_time,
+                end_time: start_time + options.duration,
+                total_inventory: options.inventory,
+                per_user_limit: options.per_user_limit,
+                admission: AdmissionBudget::default(),
+                guards: OrderGuardSettings::default(),
+            },
+        )
+        .await?;
+        summary.flash_sale_ids.push(details.flash_sale.id);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    Ok(summary)
+}
@@ -0,0 +1,27 @@
+use dashmap::DashSet;
+use uuid::Uuid;
+
+/// Flash sales the order worker has seen run out of stock
+///
+/// Inventory only ever goes down, so a sale once marked stays sold out and
+/// admission can turn its orders away without queueing them.
+#[derive(Default)]
+pub struct SoldOutSales {
+    sales: DashSet<Uuid>,
+}
+
+impl SoldOutSales {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mark(&self, flash_sale_id: Uuid) {
+        if self.sales.insert(flash_sale_id) {
+            metrics::gauge!("sold_out_sales").set(self.sales.len() as f64);
+        }
+    }
+
+    pub fn contains(&self, flash_sale_id: Uuid) -> bool {
+        self.sales.contains(&flash_sale_id)
+    }
+}
@@ -1,15 +1,23 @@
+use axum::extract::FromRef;
 use metrics_exporter_prometheus::PrometheusHandle;
 use std::sync::Arc;
-use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use crate::{
-    adapters::http::middleware::UserRateLimiter,
-    app::order_queue::OrderQueueMessage,
-    domain::order::OrderProcessingStatus,
+    adapters::http::middleware::RouteRateLimits,
+    app::{
+        adaptive_admission::AdaptiveAdmission, circuit_breaker::CircuitBreaker,
+        fair_queue::FairOrderQueue, flash_sale_cache::FlashSaleCache, health::HealthChecker,
+        order_challenge::OrderChallenges, order_guards::OrderGuards, order_lanes::LaneClassifier,
+        rate_limiter::RateLimiterBackend, runtime_settings::RuntimeSettingsStore,
+        sold_out_sales::SoldOutSales,
+    },
+    domain::{flash_sale::StockDisplay, order::OrderStatusEntry},
     ports::{
-        flash_sale_repo::FlashSaleRepo, order_repo::OrderRepo, product_repo::ProductRepo,
-        user_repo::UserRepo,
+        api_key_repo::ApiKeyRepo, flash_sale_repo::FlashSaleRepo,
+        idempotency_repo::IdempotencyRepo, order_challenge_repo::OrderChallengeRepo,
+        order_guard_repo::OrderGuardRepo, order_repo::OrderRepo, product_repo::ProductRepo,
+        settings_audit_repo::SettingsAuditRepo, token_verifier::TokenVerifier, user_repo::UserRepo,
     },
 };
 
@@ -19,12 +27,43 @@ pub struct AppState {
     pub product_repo: Arc<dyn ProductRepo>,
     pub flash_sale_repo: Arc<dyn FlashSaleRepo>,
     pub order_repo: Arc<dyn OrderRepo>,
+    pub idempotency_repo: Arc<dyn IdempotencyRepo>,
+    pub api_key_repo: Arc<dyn ApiKeyRepo>,
+    pub settings_audit_repo: Arc<dyn SettingsAuditRepo>,
+    pub order_challenge_repo: Arc<dyn OrderChallengeRepo>,
+    pub order_guard_repo: Arc<dyn OrderGuardRepo>,
     pub db_pool: sqlx::PgPool,
     pub prometheus_handle: PrometheusHandle,
-    pub order_queue_tx: mpsc::Sender<OrderQueueMessage>,
-    pub rate_limiter: UserRateLimiter,
+    pub order_queue: Arc<FairOrderQueue>,
+    /// Request budgets of users, partner clients, sales and limited routes
+    pub rate_limiter: Arc<dyn RateLimiterBackend>,
+    /// Per-route limits applied before handlers run
+    pub route_rate_limits: Arc<RouteRateLimits>,
+    /// Queue share of flash sales that don't set their own
+    pub default_sale_queue_share: f64,
+    /// Picks the priority lane of each admitted order
+    pub lane_classifier: Arc<dyn LaneClassifier>,
+    /// Proof-of-work challenges of sales that require them
+    pub order_challenges: Arc<OrderChallenges>,
+    /// Eligibility rules orders pass before they are queued
+    pub order_guards: Arc<OrderGuards>,
+    pub token_verifier: Arc<dyn TokenVerifier>,
     /// In-memory store for tracking async order processing status
-    pub order_status_store: Arc<dashmap::DashMap<Uuid, OrderProcessingStatus>>,
+    pub order_status_store: Arc<dashmap::DashMap<Uuid, OrderStatusEntry>>,
+    /// How long responses stored by the idempotency middleware are replayed
+    pub idempotency_ttl: chrono::Duration,
+    /// How long a key stays claimed by a request that never finishes
+    pub idempotency_lease: chrono::Duration,
+    pub flash_sale_cache: Arc<FlashSaleCache>,
+    pub stock_display: StockDisplay,
+    /// Rate limit, admission and logging settings that change without a restart
+    pub runtime_settings: Arc<RuntimeSettingsStore>,
+    pub sold_out_sales: Arc<SoldOutSales>,
+    pub health: Arc<HealthChecker>,
+    /// Guards the database in the order path
+    pub db_breaker: Arc<CircuitBreaker>,
+    /// Queue depth limit adapted to how fast orders are placed
+    pub admission: Arc<AdaptiveAdmission>,
 }
 
 impl AppState {
@@ -32,3 +71,36 @@ impl AppState {
         &self.db_pool
     }
 }
+
+impl FromRef<AppState> for Arc<HealthChecker> {
+    fn from_ref(state: &AppState) -> Self {
+        state.health.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn TokenVerifier> {
+    fn from_ref(state: &AppState) -> Self {
+        state.token_verifier.clone()
+    }
+}
+
+/// State of a worker process's health and metrics server
+#[derive(Clone)]
+pub struct WorkerState {
+    pub prometheus_handle: PrometheusHandle,
+    pub health: Arc<HealthChecker>,
+    /// Verifies the operator tokens `/metrics` requires
+    pub token_verifier: Arc<dyn TokenVerifier>,
+}
+
+impl FromRef<WorkerState> for Arc<HealthChecker> {
+    fn from_ref(state: &WorkerState) -> Self {
+        state.health.clone()
+    }
+}
+
+impl FromRef<WorkerState> for Arc<dyn TokenVerifier> {
+    fn from_ref(state: &WorkerState) -> Self {
+        state.token_verifier.clone()
+    }
+}
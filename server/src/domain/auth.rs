@@ -0,0 +1,246 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    domain::api_key::{ApiKey, ApiKeyScope},
+    errors::ServiceError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Customer,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Customer => "customer",
+            Self::Operator => "operator",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+/// Service tier of a customer, as asserted by the identity provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserTier {
+    #[default]
+    Standard,
+    Vip,
+}
+
+/// Caller identity established from a verified bearer token
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: Uuid,
+    pub role: Role,
+    pub tier: UserTier,
+}
+
+impl AuthenticatedUser {
+    pub fn has_any_role(&self, roles: &[Role]) -> bool {
+        roles.contains(&self.role)
+    }
+
+    /// Operators and admins manage the catalog and can see every user's data
+    pub fn is_staff(&self) -> bool {
+        self.has_any_role(&[Role::Operator, Role::Admin])
+    }
+
+    /// Users see their own data, staff see everyone's
+    pub fn can_see_data_of(&self, owner_id: Uuid) -> bool {
+        self.user_id == owner_id || self.is_staff()
+    }
+}
+
+/// Caller of the order endpoints
+#[derive(Debug, Clone)]
+pub enum Caller {
+    /// End user authenticated with a bearer token
+    User(AuthenticatedUser),
+    /// Partner backend authenticated with an API key, optionally acting for one of its users
+    Partner {
+        api_key: ApiKey,
+        on_behalf_of: Option<Uuid>,
+    },
+}
+
+impl Caller {
+    /// The user the request acts for
+    ///
+    /// `None` when a partner did not name a user
+    pub fn user_id(&self) -> Option<Uuid> {
+        match self {
+            Self::User(user) => Some(user.user_id),
+            Self::Partner { on_behalf_of, .. } => *on_behalf_of,
+        }
+    }
+
+    /// The partner client behind the request, if any
+    pub fn api_client_id(&self) -> Option<Uuid> {
+        match self {
+            Self::User(_) => None,
+            Self::Partner { api_key, .. } => Some(api_key.client_id),
+        }
+    }
+
+    /// Tier of the calling user, partners are served at the standard tier
+    pub fn tier(&self) -> UserTier {
+        match self {
+            Self::User(user) => user.tier,
+            Self::Partner { .. } => UserTier::Standard,
+        }
+    }
+
+    /// Users are not limited by scopes, API keys must carry the scope
+    pub fn require_scope(&self, scope: ApiKeyScope) -> Result<(), ServiceError> {
+        match self {
+            Self::Partner { api_key, .. } if !api_key.has_scope(scope) => Err(
+                ServiceError::Forbidden(format!("API key lacks the {} scope", scope.as_str())),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the caller may list the orders of `user_id`
+    ///
+    /// Partners additionally only get the orders they placed themselves
+    pub fn can_view_orders_of(&self, user_id: Uuid) -> bool {
+        match self {
+            Self::User(user) => user.can_see_data_of(user_id),
+            Self::Partner { on_behalf_of, .. } => on_behalf_of.is_none_or(|id| id == user_id),
+        }
+    }
+
+    /// Whether the caller may see an order owned by `owner_id`
+    ///
+    /// Partners only see orders they placed themselves, narrowed to the named user if any
+    pub fn can_access_order(&self, owner_id: Uuid, placed_by_client: Option<Uuid>) -> bool {
+        match self {
+            Self::User(user) => user.can_see_data_of(owner_id),
+            Self::Partner {
+                api_key,
+                on_behalf_of,
+            } => {
+                placed_by_client == Some(api_key.client_id)
+                    && on_behalf_of.is_none_or(|user_id| user_id == owner_id)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: Uuid = Uuid::from_u128(0xc);
+
+    fn user(role: Role) -> AuthenticatedUser {
+        AuthenticatedUser {
+            user_id: Uuid::from_u128(1),
+            role,
+            tier: UserTier::Standard,
+        }
+    }
+
+    fn partner(scopes: Vec<ApiKeyScope>, on_behalf_of: Option<Uuid>) -> Caller {
+        Caller::Partner {
+            api_key: ApiKey {
+                id: Uuid::from_u128(0xb),
+                client_id: CLIENT,
+                name: "partner".to_string(),
+                key_prefix: "fsk_00000000".to_string(),
+                key_hash: String::new(),
+                scopes,
+                rate_limit_per_second: 10,
+                created_at: chrono::Utc::now(),
+                expires_at: None,
+                revoked_at: None,
+            },
+            on_behalf_of,
+        }
+    }
+
+    #[test]
+    fn operators_and_admins_are_staff() {
+        assert!(!user(Role::Customer).is_staff());
+        assert!(user(Role::Operator).is_staff());
+        assert!(user(Role::Admin).is_staff());
+    }
+
+    #[test]
+    fn admin_routes_exclude_operators() {
+        let admin_only = [Role::Admin];
+
+        assert!(user(Role::Admin).has_any_role(&admin_only));
+        assert!(!user(Role::Operator).has_any_role(&admin_only));
+        assert!(!user(Role::Customer).has_any_role(&admin_only));
+    }
+
+    #[test]
+    fn users_see_their_own_data_and_staff_see_everyones() {
+        let other = Uuid::from_u128(2);
+
+        assert!(user(Role::Customer).can_see_data_of(Uuid::from_u128(1)));
+        assert!(!user(Role::Customer).can_see_data_of(other));
+        assert!(user(Role::Operator).can_see_data_of(other));
+        assert!(user(Role::Admin).can_see_data_of(other));
+    }
+
+    #[test]
+    fn only_api_keys_are_limited_by_scopes() {
+        let reader = partner(vec![ApiKeyScope::OrdersRead], None);
+
+        assert!(reader.require_scope(ApiKeyScope::OrdersRead).is_ok());
+        assert!(matches!(
+            reader.require_scope(ApiKeyScope::OrdersWrite),
+            Err(ServiceError::Forbidden(_))
+        ));
+        assert!(
+            Caller::User(user(Role::Customer))
+                .require_scope(ApiKeyScope::OrdersWrite)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn partners_act_for_the_named_user_under_their_client() {
+        let owner = Uuid::from_u128(1);
+        let caller = partner(vec![], Some(owner));
+
+        assert_eq!(caller.user_id(), Some(owner));
+        assert_eq!(caller.api_client_id(), Some(CLIENT));
+        assert_eq!(partner(vec![], None).user_id(), None);
+        assert_eq!(Caller::User(user(Role::Customer)).api_client_id(), None);
+    }
+
+    #[test]
+    fn partners_only_see_orders_they_placed() {
+        let owner = Uuid::from_u128(1);
+        let other_client = Uuid::from_u128(0xd);
+
+        assert!(partner(vec![], None).can_access_order(owner, Some(CLIENT)));
+        assert!(partner(vec![], Some(owner)).can_access_order(owner, Some(CLIENT)));
+        assert!(!partner(vec![], None).can_access_order(owner, None));
+        assert!(!partner(vec![], None).can_access_order(owner, Some(other_client)));
+        assert!(!partner(vec![], Some(Uuid::from_u128(2))).can_access_order(owner, Some(CLIENT)));
+    }
+
+    #[test]
+    fn order_history_is_open_to_the_owner_staff_and_their_partners() {
+        let owner = Uuid::from_u128(1);
+        let other = Uuid::from_u128(2);
+
+        assert!(Caller::User(user(Role::Customer)).can_view_orders_of(owner));
+        assert!(!Caller::User(user(Role::Customer)).can_view_orders_of(other));
+        assert!(Caller::User(user(Role::Operator)).can_view_orders_of(other));
+        assert!(partner(vec![], None).can_view_orders_of(other));
+        assert!(partner(vec![], Some(owner)).can_view_orders_of(owner));
+        assert!(!partner(vec![], Some(owner)).can_view_orders_of(other));
+    }
+}
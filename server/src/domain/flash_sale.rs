@@ -2,7 +2,65 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::adapters::db::flash_sale::FlashSaleRecord;
+use crate::{
+    adapters::db::{
+        flash_sale::{FlashSaleDetailsRecord, FlashSaleRecord},
+        product::ProductRecord,
+    },
+    domain::{order_guard::OrderGuardSettings, product::Product},
+    errors::{AppError, DomainError},
+    logic::CreateFlashSaleCommand,
+};
+
+/// Remaining stock at or below this percentage of the total is reported as low
+const LOW_STOCK_PERCENT: i64 = 10;
+
+/// Where a sale is in its lifetime relative to a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashSalePhase {
+    Upcoming,
+    Live,
+    Ended,
+}
+
+/// Which sales a public listing shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashSaleStatusFilter {
+    Live,
+    Upcoming,
+}
+
+/// Coarse stock indicator for public listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StockLevel {
+    Available,
+    Low,
+    SoldOut,
+}
+
+/// How much of the remaining inventory is disclosed to clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StockDisplay {
+    /// Exact remaining count alongside the level
+    Exact,
+    /// Only the stock level
+    #[default]
+    Bucketed,
+}
+
+impl StockDisplay {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "exact" => Some(Self::Exact),
+            "bucketed" => Some(Self::Bucketed),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlashSale {
@@ -13,9 +71,63 @@ pub struct FlashSale {
     pub total_inventory: i32,
     pub remaining_inventory: i32,
     pub per_user_limit: i32,
+    pub admission: AdmissionBudget,
+    pub guards: OrderGuardSettings,
     pub created_at: DateTime<Utc>,
 }
 
+/// How much of the order path one sale may take
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdmissionBudget {
+    /// Largest share of the admission limit its queued orders may hold,
+    /// `None` for the configured default
+    pub queue_share: Option<f64>,
+    /// Orders admitted per second across all users, `None` for no limit
+    pub rate_per_second: Option<i32>,
+    /// Orders the worker takes from this sale per turn when sales compete
+    pub scheduling_weight: i32,
+    /// Orders must carry a solved proof-of-work challenge
+    pub challenge_required: bool,
+}
+
+impl Default for AdmissionBudget {
+    fn default() -> Self {
+        Self {
+            queue_share: None,
+            rate_per_second: None,
+            scheduling_weight: 1,
+            challenge_required: false,
+        }
+    }
+}
+
+impl AdmissionBudget {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(share) = self.queue_share
+            && !(share > 0.0 && share <= 1.0)
+        {
+            return Err(AppError::Domain(DomainError::InvalidFlashSaleQueueShare));
+        }
+        if self.rate_per_second.is_some_and(|rate| rate <= 0) {
+            return Err(AppError::Domain(DomainError::InvalidFlashSaleAdmissionRate));
+        }
+        if self.scheduling_weight <= 0 {
+            return Err(AppError::Domain(
+                DomainError::InvalidFlashSaleSchedulingWeight,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Queued orders this sale may hold under the given admission limit,
+    /// always at least one
+    pub fn queue_slots(&self, admission_limit: usize, default_share: f64) -> usize {
+        let share = self.queue_share.unwrap_or(default_share);
+        ((admission_limit as f64 * share).ceil() as usize).max(1)
+    }
+}
+
 impl FlashSale {
     pub fn is_active(&self) -> bool {
         let now = Utc::now();
@@ -25,6 +137,71 @@ impl FlashSale {
     pub fn is_sold_out(&self) -> bool {
         self.remaining_inventory <= 0
     }
+
+    pub fn phase_at(&self, now: DateTime<Utc>) -> FlashSalePhase {
+        if now < self.start_time {
+            FlashSalePhase::Upcoming
+        } else if now <= self.end_time {
+            FlashSalePhase::Live
+        } else {
+            FlashSalePhase::Ended
+        }
+    }
+
+    pub fn stock_level(&self) -> StockLevel {
+        if self.is_sold_out() {
+            StockLevel::SoldOut
+        } else if i64::from(self.remaining_inventory) * 100
+            <= i64::from(self.total_inventory) * LOW_STOCK_PERCENT
+        {
+            StockLevel::Low
+        } else {
+            StockLevel::Available
+        }
+    }
+}
+
+/// Public view of a sale together with the product on offer
+#[derive(Debug, Clone)]
+pub struct FlashSaleDetails {
+    pub flash_sale: FlashSale,
+    pub product: Product,
+}
+
+impl TryFrom<CreateFlashSaleCommand> for FlashSale {
+    type Error = AppError;
+
+    fn try_from(value: CreateFlashSaleCommand) -> Result<Self, Self::Error> {
+        let now = Utc::now();
+
+        if value.start_time <= now {
+            return Err(AppError::Domain(DomainError::InvalidFlashSaleStartTime));
+        }
+        if value.end_time <= value.start_time {
+            return Err(AppError::Domain(DomainError::InvalidFlashSaleEndTime));
+        }
+        if value.total_inventory <= 0 {
+            return Err(AppError::Domain(DomainError::InvalidFlashSaleQuantity));
+        }
+        if value.per_user_limit <= 0 {
+            return Err(AppError::Domain(DomainError::InvalidFlashSalePerUserLimit));
+        }
+        value.admission.validate()?;
+        value.guards.validate()?;
+
+        Ok(Self {
+            id: value.id,
+            product_id: value.product_id,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            total_inventory: value.total_inventory,
+            remaining_inventory: value.total_inventory,
+            per_user_limit: value.per_user_limit,
+            admission: value.admission,
+            guards: value.guards,
+            created_at: now,
+        })
+    }
 }
 
 impl From<FlashSaleRecord> for FlashSale {
@@ -37,7 +214,167 @@ impl From<FlashSaleRecord> for FlashSale {
             total_inventory: value.total_inventory,
             remaining_inventory: value.remaining_inventory,
             per_user_limit: value.per_user_limit,
+            admission: AdmissionBudget {
+                queue_share: value.queue_share,
+                rate_per_second: value.admission_rate_per_second,
+                scheduling_weight: value.scheduling_weight,
+                challenge_required: value.challenge_required,
+            },
+            guards: OrderGuardSettings::from_columns(
+                value.guard_blocklist,
+                value.min_account_age_secs,
+                value.velocity_max_orders,
+                value.velocity_window_secs,
+                value.allowlist_only,
+            ),
             created_at: value.created_at,
         }
     }
 }
+
+impl TryFrom<FlashSaleDetailsRecord> for FlashSaleDetails {
+    type Error = AppError;
+
+    fn try_from(value: FlashSaleDetailsRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            flash_sale: FlashSale {
+                id: value.id,
+                product_id: value.product_id,
+                start_time: value.start_time,
+                end_time: value.end_time,
+                total_inventory: value.total_inventory,
+                remaining_inventory: value.remaining_inventory,
+                per_user_limit: value.per_user_limit,
+                admission: AdmissionBudget {
+                    queue_share: value.queue_share,
+                    rate_per_second: value.admission_rate_per_second,
+                    scheduling_weight: value.scheduling_weight,
+                    challenge_required: value.challenge_required,
+                },
+                guards: OrderGuardSettings::from_columns(
+                    value.guard_blocklist,
+                    value.min_account_age_secs,
+                    value.velocity_max_orders,
+                    value.velocity_window_secs,
+                    value.allowlist_only,
+                ),
+                created_at: value.created_at,
+            },
+            product: Product::try_from(ProductRecord {
+                id: value.product_id,
+                name: value.product_name,
+                description: value.product_description,
+                sku: value.product_sku,
+                image_urls: value.product_image_urls,
+                created_at: value.product_created_at,
+                archived_at: value.product_archived_at,
+            })?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn sale(total_inventory: i32, remaining_inventory: i32) -> FlashSale {
+        let start_time = Utc::now();
+        FlashSale {
+            id: Uuid::from_u128(1),
+            product_id: Uuid::from_u128(2),
+            start_time,
+            end_time: start_time + Duration::hours(1),
+            total_inventory,
+            remaining_inventory,
+            per_user_limit: 1,
+            admission: AdmissionBudget::default(),
+            guards: OrderGuardSettings::default(),
+            created_at: start_time,
+        }
+    }
+
+    #[test]
+    fn phase_follows_the_sale_window_inclusively() {
+        let sale = sale(100, 100);
+
+        assert_eq!(
+            sale.phase_at(sale.start_time - Duration::seconds(1)),
+            FlashSalePhase::Upcoming
+        );
+        assert_eq!(sale.phase_at(sale.start_time), FlashSalePhase::Live);
+        assert_eq!(sale.phase_at(sale.end_time), FlashSalePhase::Live);
+        assert_eq!(
+            sale.phase_at(sale.end_time + Duration::seconds(1)),
+            FlashSalePhase::Ended
+        );
+    }
+
+    #[test]
+    fn stock_is_low_at_a_tenth_of_the_total() {
+        assert_eq!(sale(100, 11).stock_level(), StockLevel::Available);
+        assert_eq!(sale(100, 10).stock_level(), StockLevel::Low);
+        assert_eq!(sale(100, 1).stock_level(), StockLevel::Low);
+        assert_eq!(sale(100, 0).stock_level(), StockLevel::SoldOut);
+        assert_eq!(sale(5, 1).stock_level(), StockLevel::Available);
+    }
+
+    #[test]
+    fn stock_display_parses_its_config_names() {
+        assert_eq!(StockDisplay::parse("exact"), Some(StockDisplay::Exact));
+        assert_eq!(
+            StockDisplay::parse("bucketed"),
+            Some(StockDisplay::Bucketed)
+        );
+        assert_eq!(StockDisplay::parse("Exact"), None);
+    }
+
+    #[test]
+    fn admission_budgets_need_a_share_up_to_one_and_positive_rates() {
+        let share = |queue_share| AdmissionBudget {
+            queue_share: Some(queue_share),
+            ..Default::default()
+        };
+
+        assert!(AdmissionBudget::default().validate().is_ok());
+        assert!(share(1.0).validate().is_ok());
+        for invalid in [0.0, 1.5, f64::NAN] {
+            assert!(matches!(
+                share(invalid).validate(),
+                Err(AppError::Domain(DomainError::InvalidFlashSaleQueueShare))
+            ));
+        }
+        assert!(matches!(
+            AdmissionBudget {
+                rate_per_second: Some(0),
+                ..Default::default()
+            }
+            .validate(),
+            Err(AppError::Domain(DomainError::InvalidFlashSaleAdmissionRate))
+        ));
+        assert!(matches!(
+            AdmissionBudget {
+                scheduling_weight: 0,
+                ..Default::default()
+            }
+            .validate(),
+            Err(AppError::Domain(
+                DomainError::InvalidFlashSaleSchedulingWeight
+            ))
+        ));
+    }
+
+    #[test]
+    fn queue_slots_round_up_and_never_reach_zero() {
+        let default_share = AdmissionBudget::default();
+        let quarter = AdmissionBudget {
+            queue_share: Some(0.25),
+            ..Default::default()
+        };
+
+        assert_eq!(default_share.queue_slots(1000, 0.5), 500);
+        assert_eq!(quarter.queue_slots(1000, 0.5), 250);
+        assert_eq!(quarter.queue_slots(10, 0.5), 3);
+        assert_eq!(quarter.queue_slots(0, 0.5), 1);
+    }
+}
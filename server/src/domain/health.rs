@@ -0,0 +1,113 @@
+/// State of one dependency checked for readiness
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+}
+
+/// Outcome of checking one component
+///
+/// `value` is what was measured and `limit` the point at which the component
+/// counts as down, both in the unit named by the message.
+#[derive(Debug, Clone)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    pub message: String,
+    pub value: Option<f64>,
+    pub limit: Option<f64>,
+}
+
+impl ComponentHealth {
+    pub fn up(message: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Up,
+            message: message.into(),
+            value: None,
+            limit: None,
+        }
+    }
+
+    pub fn down(message: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Down,
+            message: message.into(),
+            value: None,
+            limit: None,
+        }
+    }
+
+    /// Up while `value` stays below `limit`
+    pub fn below(value: f64, limit: f64, message: impl Into<String>) -> Self {
+        Self {
+            status: if value < limit {
+                HealthStatus::Up
+            } else {
+                HealthStatus::Down
+            },
+            message: message.into(),
+            value: Some(value),
+            limit: Some(limit),
+        }
+    }
+}
+
+/// Result of a readiness check, one entry per component
+#[derive(Debug, Clone, Default)]
+pub struct ReadinessReport {
+    pub components: Vec<(&'static str, ComponentHealth)>,
+}
+
+impl ReadinessReport {
+    /// Ready only when every component is up
+    pub fn is_ready(&self) -> bool {
+        self.components
+            .iter()
+            .all(|(_, health)| health.status == HealthStatus::Up)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn below_is_down_from_the_limit_on() {
+        let under = ComponentHealth::below(249.9, 250.0, "milliseconds waited");
+        assert_eq!(under.status, HealthStatus::Up);
+        assert_eq!((under.value, under.limit), (Some(249.9), Some(250.0)));
+
+        assert_eq!(
+            ComponentHealth::below(250.0, 250.0, "milliseconds waited").status,
+            HealthStatus::Down
+        );
+    }
+
+    #[test]
+    fn ready_only_while_every_component_is_up() {
+        let mut report = ReadinessReport {
+            components: vec![
+                ("database", ComponentHealth::up("reachable")),
+                (
+                    "order_queue",
+                    ComponentHealth::below(0.2, 0.9, "queue fill"),
+                ),
+            ],
+        };
+        assert!(report.is_ready());
+
+        report.components.push((
+            "worker",
+            ComponentHealth::down("no worker heartbeat recorded"),
+        ));
+        assert!(!report.is_ready());
+    }
+}
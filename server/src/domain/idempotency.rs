@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// Response captured for the first request made with an idempotency key
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    pub status_code: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Outcome of presenting an idempotency key
+#[derive(Debug, Clone)]
+pub enum IdempotentRequest {
+    /// The key was claimed for this request at the given time
+    Claimed(DateTime<Utc>),
+    /// A finished request already used the key, its response is replayed
+    Replay(StoredResponse),
+}
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    pub scope: String,
+    pub idempotency_key: String,
+    pub request_fingerprint: String,
+    /// `None` while the original request is still in progress
+    pub response: Option<StoredResponse>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl IdempotencyRecord {
+    pub fn in_progress(
+        scope: String,
+        idempotency_key: String,
+        request_fingerprint: String,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            scope,
+            idempotency_key,
+            request_fingerprint,
+            response: None,
+            created_at: Utc::now(),
+            expires_at,
+        }
+    }
+
+    pub fn is_in_progress(&self) -> bool {
+        self.response.is_none()
+    }
+}
+
+/// Hex-encoded SHA-256 of a canonical request representation
+pub fn fingerprint(canonical: &[u8]) -> String {
+    Sha256::digest(canonical)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_is_hex_sha256() {
+        assert_eq!(
+            fingerprint(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn fingerprint_tells_requests_apart() {
+        let original = fingerprint(b"POST /users user:1\n{\"email\":\"a@example.com\"}");
+
+        assert_eq!(
+            original,
+            fingerprint(b"POST /users user:1\n{\"email\":\"a@example.com\"}")
+        );
+        assert_ne!(
+            original,
+            fingerprint(b"POST /users user:1\n{\"email\":\"b@example.com\"}")
+        );
+    }
+}
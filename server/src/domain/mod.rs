@@ -1,6 +1,16 @@
+pub mod api_key;
+pub mod auth;
 pub mod flash_sale;
+pub mod health;
+pub mod idempotency;
 pub mod order;
+pub mod order_challenge;
+pub mod order_guard;
+pub mod order_job;
+pub mod pagination;
 pub mod product;
+pub mod rate_limit;
+pub mod runtime_settings;
 pub mod user;
 
 pub use crate::domain::{product::*, user::*};
@@ -1,11 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use uuid::Uuid;
 
-use crate::adapters::db::order::OrderRecord;
+use crate::{adapters::db::order::OrderRecord, domain::idempotency};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "order_status", rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderStatus {
     Pending,
     Confirmed,
@@ -23,6 +25,50 @@ pub enum OrderProcessingStatus {
     Failed(String),
 }
 
+impl OrderProcessingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Completed(_) => "completed",
+            Self::Failed(_) => "failed",
+        }
+    }
+}
+
+/// Entry in the order status store
+///
+/// Keeps the owner, for authorizing status lookups, and the fingerprint of the
+/// request that claimed the idempotency key so a replay with different
+/// parameters can be rejected before it is queued
+#[derive(Debug, Clone)]
+pub struct OrderStatusEntry {
+    pub user_id: Uuid,
+    /// Partner client that placed the order through an API key
+    pub api_client_id: Option<Uuid>,
+    pub request_fingerprint: String,
+    pub status: OrderProcessingStatus,
+    /// Sequence number in the order queue, set once the order is queued
+    pub queue_sequence: Option<u64>,
+}
+
+/// Where a queued order stands
+#[derive(Debug, Clone, Copy)]
+pub struct QueueEstimate {
+    /// Orders ahead of it plus itself, counting every lane
+    pub position: u64,
+    /// `None` until the consumer has finished an order
+    pub estimated_wait: Option<Duration>,
+}
+
+/// Narrows an order listing, unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct OrderFilter {
+    pub status: Option<OrderStatus>,
+    pub flash_sale_id: Option<Uuid>,
+    /// Only orders placed by this partner client
+    pub api_client_id: Option<Uuid>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
     pub id: Uuid,
@@ -31,21 +77,62 @@ pub struct Order {
     pub quantity: i32,
     pub status: OrderStatus,
     pub idempotency_key: String,
+    pub request_fingerprint: String,
+    /// Partner client that placed the order, `None` if the user placed it
+    pub api_client_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
 impl Order {
-    pub fn new(user_id: Uuid, flash_sale_id: Uuid, quantity: i32, idempotency_key: String) -> Self {
+    /// `id` is the one handed out when the order was accepted, so clients
+    /// can look the order up by it once it is persisted
+    pub fn new(
+        id: Uuid,
+        user_id: Uuid,
+        flash_sale_id: Uuid,
+        quantity: i32,
+        idempotency_key: String,
+        request_fingerprint: String,
+        api_client_id: Option<Uuid>,
+    ) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id,
             user_id,
             flash_sale_id,
             quantity,
             status: OrderStatus::Pending,
             idempotency_key,
+            request_fingerprint,
+            api_client_id,
             created_at: Utc::now(),
         }
     }
+
+    /// Id of the order placed with `idempotency_key` by `owner`, see
+    /// [`Order::idempotency_owner`]
+    ///
+    /// Known before the order is persisted, so it can be handed out on
+    /// acceptance; keys of different owners never map to the same order
+    pub fn id_for_key(owner: Uuid, idempotency_key: &str) -> Uuid {
+        let namespace = Uuid::from_u128(0x6ba7b810_9dad_11d1_80b4_00c04fd430c8);
+        let name = format!("{owner}:{idempotency_key}");
+        Uuid::new_v5(&namespace, name.as_bytes())
+    }
+
+    /// Whose idempotency keys an order's key belongs to: the partner client
+    /// that placed it, or else the user
+    pub fn idempotency_owner(user_id: Uuid, api_client_id: Option<Uuid>) -> Uuid {
+        api_client_id.unwrap_or(user_id)
+    }
+
+    /// Computes the fingerprint of an order request
+    ///
+    /// SHA-256 (hex) over the canonical form `user_id:flash_sale_id:quantity`.
+    /// The backfill migration uses the same form, so keep them in sync.
+    pub fn fingerprint(user_id: Uuid, flash_sale_id: Uuid, quantity: i32) -> String {
+        let canonical = format!("{}:{}:{}", user_id, flash_sale_id, quantity);
+        idempotency::fingerprint(canonical.as_bytes())
+    }
 }
 
 impl From<OrderRecord> for Order {
@@ -57,7 +144,61 @@ impl From<OrderRecord> for Order {
             quantity: value.quantity,
             status: value.status,
             idempotency_key: value.idempotency_key,
+            request_fingerprint: value.request_fingerprint,
+            api_client_id: value.api_client_id,
             created_at: value.created_at,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: Uuid = Uuid::from_u128(1);
+    const SALE: Uuid = Uuid::from_u128(0xa);
+
+    #[test]
+    fn fingerprint_matches_the_backfill_form() {
+        // sha256 hex of "user_id:flash_sale_id:quantity", as in the migration
+        assert_eq!(
+            Order::fingerprint(USER, SALE, 2),
+            "7468cb4c667748323f4db8e44945a279150cc048ce4c249225a1d3bc6221a4b7"
+        );
+    }
+
+    #[test]
+    fn fingerprint_changes_with_every_parameter() {
+        let original = Order::fingerprint(USER, SALE, 2);
+
+        assert_ne!(original, Order::fingerprint(Uuid::from_u128(2), SALE, 2));
+        assert_ne!(original, Order::fingerprint(USER, Uuid::from_u128(0xb), 2));
+        assert_ne!(original, Order::fingerprint(USER, SALE, 3));
+    }
+
+    #[test]
+    fn processing_status_labels_match_the_accepted_response() {
+        assert_eq!(OrderProcessingStatus::Pending.as_str(), "pending");
+        assert_eq!(
+            OrderProcessingStatus::Failed("sold out".to_string()).as_str(),
+            "failed"
+        );
+    }
+
+    #[test]
+    fn ids_are_stable_per_owner_and_key() {
+        let id = Order::id_for_key(USER, "checkout-1");
+
+        assert_eq!(id, Order::id_for_key(USER, "checkout-1"));
+        assert_ne!(id, Order::id_for_key(USER, "checkout-2"));
+        assert_ne!(id, Order::id_for_key(Uuid::from_u128(2), "checkout-1"));
+    }
+
+    #[test]
+    fn partner_orders_share_the_client_keys() {
+        let client = Uuid::from_u128(0xc);
+
+        assert_eq!(Order::idempotency_owner(USER, None), USER);
+        assert_eq!(Order::idempotency_owner(USER, Some(client)), client);
+    }
+}
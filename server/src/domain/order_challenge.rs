@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// How the challenge difficulty follows queue overflows
+#[derive(Debug, Clone, Copy)]
+pub struct DifficultyPolicy {
+    pub base: u32,
+    pub max: u32,
+    /// Overflows per second above which difficulty rises a step
+    pub overflow_rate_threshold: f64,
+    /// How long overflows are counted before difficulty moves
+    pub adjust_interval: Duration,
+}
+
+/// Difficulty shared by all instances, with the overflows they reported
+/// since its current window started
+#[derive(Debug, Clone, Copy)]
+pub struct ChallengeDifficulty {
+    pub difficulty: u32,
+    pub window_started: DateTime<Utc>,
+    pub overflows: u64,
+}
+
+impl ChallengeDifficulty {
+    /// Once the window is up, moves the difficulty a step by the overflow
+    /// rate seen during it and starts the next window
+    ///
+    /// Rises above the threshold, falls back under half of it, and stays
+    /// within the policy's bounds. Returns the rate when the window closed.
+    pub fn advance(&mut self, now: DateTime<Utc>, policy: &DifficultyPolicy) -> Option<f64> {
+        let elapsed = (now - self.window_started).to_std().unwrap_or_default();
+        if elapsed < policy.adjust_interval {
+            return None;
+        }
+
+        let rate = self.overflows as f64 / elapsed.as_secs_f64();
+        let current = self.difficulty.clamp(policy.base, policy.max);
+        self.difficulty = if rate > policy.overflow_rate_threshold {
+            (current + 1).min(policy.max)
+        } else if rate < policy.overflow_rate_threshold / 2.0 {
+            current.saturating_sub(1).max(policy.base)
+        } else {
+            current
+        };
+        self.window_started = now;
+        self.overflows = 0;
+        Some(rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: DifficultyPolicy = DifficultyPolicy {
+        base: 16,
+        max: 18,
+        overflow_rate_threshold: 5.0,
+        adjust_interval: Duration::from_secs(10),
+    };
+
+    fn window(difficulty: u32, overflows: u64) -> (ChallengeDifficulty, DateTime<Utc>) {
+        let started = Utc::now();
+        (
+            ChallengeDifficulty {
+                difficulty,
+                window_started: started,
+                overflows,
+            },
+            started + chrono::Duration::seconds(10),
+        )
+    }
+
+    #[test]
+    fn waits_for_the_window_to_close() {
+        let (mut state, end) = window(16, 1000);
+
+        assert_eq!(
+            state.advance(end - chrono::Duration::seconds(1), &POLICY),
+            None
+        );
+        assert_eq!(state.difficulty, 16);
+        assert_eq!(state.overflows, 1000);
+    }
+
+    #[test]
+    fn rises_a_step_above_the_threshold_up_to_max() {
+        let (mut state, end) = window(17, 60);
+
+        assert_eq!(state.advance(end, &POLICY), Some(6.0));
+        assert_eq!(state.difficulty, 18);
+        assert_eq!(state.overflows, 0);
+        assert_eq!(state.window_started, end);
+
+        state.overflows = 60;
+        state.advance(end + chrono::Duration::seconds(10), &POLICY);
+        assert_eq!(state.difficulty, 18);
+    }
+
+    #[test]
+    fn falls_back_under_half_the_threshold_down_to_base() {
+        let (mut state, end) = window(17, 20);
+
+        state.advance(end, &POLICY);
+        assert_eq!(state.difficulty, 16);
+
+        state.advance(end + chrono::Duration::seconds(10), &POLICY);
+        assert_eq!(state.difficulty, 16);
+    }
+
+    #[test]
+    fn holds_between_half_and_the_threshold() {
+        let (mut state, end) = window(17, 40);
+
+        state.advance(end, &POLICY);
+        assert_eq!(state.difficulty, 17);
+    }
+
+    #[test]
+    fn pulls_a_stored_difficulty_back_into_bounds() {
+        // The bounds may have been changed since the row was written
+        let (mut state, end) = window(22, 40);
+
+        state.advance(end, &POLICY);
+        assert_eq!(state.difficulty, 18);
+    }
+}
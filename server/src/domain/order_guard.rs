@@ -0,0 +1,198 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    adapters::db::order_guard::BlockedUserRecord,
+    errors::{AppError, DomainError},
+};
+
+/// Eligibility rules a sale applies to orders before admitting them
+///
+/// All off by default, each one that is on costs a query per order
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct OrderGuardSettings {
+    /// Refuse users on the blocklist
+    pub blocklist: bool,
+    /// Accounts younger than this may not order, `None` for any age
+    pub min_account_age_secs: Option<i64>,
+    /// Cap on a user's orders across all sales, `None` for no cap
+    pub velocity: Option<VelocityLimit>,
+    /// Only users on the sale's allowlist may order
+    pub allowlist_only: bool,
+}
+
+impl OrderGuardSettings {
+    /// Rebuilds the settings from their columns, a velocity limit needs both
+    pub fn from_columns(
+        blocklist: bool,
+        min_account_age_secs: Option<i64>,
+        velocity_max_orders: Option<i32>,
+        velocity_window_secs: Option<i64>,
+        allowlist_only: bool,
+    ) -> Self {
+        Self {
+            blocklist,
+            min_account_age_secs,
+            velocity: velocity_max_orders.zip(velocity_window_secs).map(
+                |(max_orders, window_secs)| VelocityLimit {
+                    max_orders,
+                    window_secs,
+                },
+            ),
+            allowlist_only,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.min_account_age_secs.is_some_and(|age| age <= 0) {
+            return Err(AppError::Domain(DomainError::InvalidOrderGuardAccountAge));
+        }
+        if self
+            .velocity
+            .is_some_and(|limit| limit.max_orders <= 0 || limit.window_secs <= 0)
+        {
+            return Err(AppError::Domain(DomainError::InvalidOrderGuardVelocity));
+        }
+
+        Ok(())
+    }
+}
+
+/// At most `max_orders` placed within the last `window_secs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VelocityLimit {
+    pub max_orders: i32,
+    pub window_secs: i64,
+}
+
+impl VelocityLimit {
+    /// Start of the window that ends at `now`
+    pub fn window_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::seconds(self.window_secs)
+    }
+
+    /// Refuses another order once `recent` orders fill the window
+    pub fn check(&self, recent: i64) -> Option<GuardRejection> {
+        (recent >= i64::from(self.max_orders)).then_some(GuardRejection::VelocityExceeded {
+            max_orders: self.max_orders,
+            window_secs: self.window_secs,
+        })
+    }
+}
+
+/// A user refused by the blocklist guard
+#[derive(Debug, Clone)]
+pub struct BlockedUser {
+    pub user_id: Uuid,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<BlockedUserRecord> for BlockedUser {
+    fn from(value: BlockedUserRecord) -> Self {
+        Self {
+            user_id: value.user_id,
+            reason: value.reason,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// Why a guard refused an order
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GuardRejection {
+    #[error("user {user_id} is blocked from ordering")]
+    Blocklisted { user_id: Uuid },
+
+    #[error("account must be at least {min_age_secs} seconds old to order")]
+    AccountTooNew { min_age_secs: i64 },
+
+    #[error("at most {max_orders} orders are allowed per {window_secs} seconds across sales")]
+    VelocityExceeded { max_orders: i32, window_secs: i64 },
+
+    #[error("this sale is only open to invited users")]
+    NotAllowlisted,
+}
+
+impl GuardRejection {
+    /// Label of the rejection in metrics
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::Blocklisted { .. } => "blocklisted",
+            Self::AccountTooNew { .. } => "account_too_new",
+            Self::VelocityExceeded { .. } => "velocity_exceeded",
+            Self::NotAllowlisted => "not_allowlisted",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn velocity_is_only_restored_from_both_columns() {
+        let limit = VelocityLimit {
+            max_orders: 3,
+            window_secs: 60,
+        };
+
+        assert_eq!(
+            OrderGuardSettings::from_columns(false, None, Some(3), Some(60), false).velocity,
+            Some(limit)
+        );
+        assert_eq!(
+            OrderGuardSettings::from_columns(false, None, Some(3), None, false).velocity,
+            None
+        );
+    }
+
+    #[test]
+    fn settings_need_positive_ages_and_limits() {
+        let velocity = |max_orders, window_secs| OrderGuardSettings {
+            velocity: Some(VelocityLimit {
+                max_orders,
+                window_secs,
+            }),
+            ..Default::default()
+        };
+
+        assert!(OrderGuardSettings::default().validate().is_ok());
+        assert!(velocity(1, 1).validate().is_ok());
+        assert!(matches!(
+            OrderGuardSettings {
+                min_account_age_secs: Some(0),
+                ..Default::default()
+            }
+            .validate(),
+            Err(AppError::Domain(DomainError::InvalidOrderGuardAccountAge))
+        ));
+        for invalid in [velocity(0, 60), velocity(3, 0)] {
+            assert!(matches!(
+                invalid.validate(),
+                Err(AppError::Domain(DomainError::InvalidOrderGuardVelocity))
+            ));
+        }
+    }
+
+    #[test]
+    fn velocity_refuses_once_the_window_is_full() {
+        let limit = VelocityLimit {
+            max_orders: 3,
+            window_secs: 60,
+        };
+        let now = Utc::now();
+
+        assert_eq!(limit.window_start(now), now - chrono::Duration::seconds(60));
+        assert_eq!(limit.check(2), None);
+        assert_eq!(
+            limit.check(3),
+            Some(GuardRejection::VelocityExceeded {
+                max_orders: 3,
+                window_secs: 60
+            })
+        );
+        assert_eq!(limit.check(3).unwrap().reason(), "velocity_exceeded");
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    adapters::db::order_job::OrderJobRecord,
+    errors::{AppError, ServiceError},
+    logic::order_logic::CreateOrderCommand,
+};
+
+/// Where a queued order stands in the shared job table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderJobStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+impl OrderJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(Self::Pending),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Why a job failed, when the API needs to react to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderJobErrorCode {
+    SoldOut,
+}
+
+impl OrderJobErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SoldOut => "SOLD_OUT",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "SOLD_OUT" => Some(Self::SoldOut),
+            _ => None,
+        }
+    }
+
+    pub fn of(error: &AppError) -> Option<Self> {
+        match error {
+            AppError::Service(ServiceError::SoldOut) => Some(Self::SoldOut),
+            _ => None,
+        }
+    }
+}
+
+/// Where a handed-off order lines up for the workers
+#[derive(Debug, Clone, Copy)]
+pub struct OrderJobTurn {
+    /// Priority lane the order was admitted to
+    pub lane: &'static str,
+    /// Its lane's weight times its sale's weight
+    pub weight: u32,
+}
+
+/// An admitted order handed from the API to a worker process
+///
+/// The job id is the order id, so a worker creates the same order the
+/// API already reported to the client
+#[derive(Debug, Clone)]
+pub struct OrderJob {
+    pub command: CreateOrderCommand,
+    pub status: OrderJobStatus,
+    pub error: Option<String>,
+    pub error_code: Option<OrderJobErrorCode>,
+    pub created_at: DateTime<Utc>,
+    /// When a worker finished the job, for jobs no longer pending
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<OrderJobRecord> for OrderJob {
+    type Error = AppError;
+
+    fn try_from(value: OrderJobRecord) -> Result<Self, Self::Error> {
+        let status = OrderJobStatus::parse(&value.status).ok_or_else(|| {
+            AppError::Unexpected(anyhow::anyhow!("unknown order job status {}", value.status))
+        })?;
+
+        Ok(Self {
+            command: CreateOrderCommand {
+                order_id: value.id,
+                user_id: value.user_id,
+                flash_sale_id: value.flash_sale_id,
+                quantity: value.quantity,
+                idempotency_key: value.idempotency_key,
+                request_fingerprint: value.request_fingerprint,
+                api_client_id: value.api_client_id,
+            },
+            status,
+            error: value.error,
+            // Codes this build doesn't know are treated as plain failures
+            error_code: value
+                .error_code
+                .as_deref()
+                .and_then(OrderJobErrorCode::parse),
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses_round_trip_through_their_column_values() {
+        for status in [
+            OrderJobStatus::Pending,
+            OrderJobStatus::Completed,
+            OrderJobStatus::Failed,
+        ] {
+            assert_eq!(OrderJobStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(OrderJobStatus::parse("running"), None);
+    }
+
+    #[test]
+    fn only_sold_out_failures_carry_a_code() {
+        let code = OrderJobErrorCode::of(&AppError::Service(ServiceError::SoldOut));
+
+        assert_eq!(code, Some(OrderJobErrorCode::SoldOut));
+        assert_eq!(OrderJobErrorCode::parse("SOLD_OUT"), code);
+        assert_eq!(
+            OrderJobErrorCode::of(&AppError::Service(ServiceError::Unauthenticated)),
+            None
+        );
+        assert_eq!(OrderJobErrorCode::parse("PAYMENT_DECLINED"), None);
+    }
+}
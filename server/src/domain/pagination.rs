@@ -0,0 +1,232 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use uuid::Uuid;
+
+use crate::errors::{AppError, DomainError};
+
+pub const DEFAULT_PAGE_LIMIT: u32 = 50;
+pub const MAX_PAGE_LIMIT: u32 = 200;
+
+/// Direction of a listing over `(created_at, id)`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Oldest first
+    #[default]
+    Asc,
+    /// Newest first
+    Desc,
+}
+
+/// Keyset position: the `(created_at, id)` of the last row a client has seen
+///
+/// Clients only ever see it as an opaque string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let raw = format!("{}:{}", self.created_at.timestamp_micros(), self.id);
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(value: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::Domain(DomainError::InvalidCursor);
+
+        let raw = URL_SAFE_NO_PAD.decode(value).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (micros, id) = raw.split_once(':').ok_or_else(invalid)?;
+
+        Ok(Self {
+            created_at: micros
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_micros)
+                .ok_or_else(invalid)?,
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl Serialize for Cursor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+/// Which slice of a listing to return
+#[derive(Debug, Clone, Copy)]
+pub struct PageRequest {
+    pub limit: u32,
+    pub after: Option<Cursor>,
+    pub order: SortOrder,
+}
+
+impl PageRequest {
+    pub fn new(
+        limit: Option<u32>,
+        cursor: Option<&str>,
+        order: Option<SortOrder>,
+    ) -> Result<Self, AppError> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(AppError::Domain(DomainError::InvalidPageLimit {
+                max: MAX_PAGE_LIMIT,
+            }));
+        }
+
+        Ok(Self {
+            limit,
+            after: cursor.map(Cursor::decode).transpose()?,
+            order: order.unwrap_or_default(),
+        })
+    }
+
+    /// Rows to fetch: one more than the limit reveals whether a next page exists
+    pub fn fetch_limit(&self) -> i64 {
+        i64::from(self.limit) + 1
+    }
+}
+
+/// One page of a listing, shared by repositories and HTTP responses
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass back as `cursor` to get the next page, `None` on the last page
+    pub next_cursor: Option<Cursor>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from up to `fetch_limit()` rows
+    pub fn from_rows(
+        mut rows: Vec<T>,
+        request: &PageRequest,
+        cursor_of: impl Fn(&T) -> Cursor,
+    ) -> Self {
+        let has_more = rows.len() > request.limit as usize;
+        rows.truncate(request.limit as usize);
+
+        let next_cursor = if has_more {
+            rows.last().map(cursor_of)
+        } else {
+            None
+        };
+
+        Self {
+            items: rows,
+            next_cursor,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor() -> Cursor {
+        Cursor {
+            created_at: DateTime::from_timestamp_micros(1_767_225_600_123_456).unwrap(),
+            id: Uuid::parse_str("0b6c1c1e-5a55-4c1e-9d7e-3a4f5b6c7d8e").unwrap(),
+        }
+    }
+
+    fn is_invalid_cursor(result: Result<Cursor, AppError>) -> bool {
+        matches!(result, Err(AppError::Domain(DomainError::InvalidCursor)))
+    }
+
+    #[test]
+    fn cursor_round_trips_with_microsecond_precision() {
+        assert_eq!(Cursor::decode(&cursor().encode()).unwrap(), cursor());
+    }
+
+    #[test]
+    fn cursor_rejects_malformed_input() {
+        assert!(is_invalid_cursor(Cursor::decode("")));
+        assert!(is_invalid_cursor(Cursor::decode("not base64!")));
+        // Padded standard base64 is not accepted
+        assert!(is_invalid_cursor(Cursor::decode(&format!(
+            "{}=",
+            cursor().encode()
+        ))));
+    }
+
+    #[test]
+    fn cursor_rejects_tampered_contents() {
+        let tampered = |raw: &str| Cursor::decode(&URL_SAFE_NO_PAD.encode(raw));
+
+        assert!(is_invalid_cursor(tampered("1767225600123456")));
+        assert!(is_invalid_cursor(tampered(
+            "yesterday:0b6c1c1e-5a55-4c1e-9d7e-3a4f5b6c7d8e"
+        )));
+        assert!(is_invalid_cursor(tampered(&format!(
+            "{}:0b6c1c1e-5a55-4c1e-9d7e-3a4f5b6c7d8e",
+            i64::MAX
+        ))));
+        assert!(is_invalid_cursor(tampered("1767225600123456:not-a-uuid")));
+        assert!(is_invalid_cursor(Cursor::decode(
+            &URL_SAFE_NO_PAD.encode([0xff, 0xfe, b':'])
+        )));
+    }
+
+    #[test]
+    fn page_request_defaults() {
+        let page = PageRequest::new(None, None, None).unwrap();
+
+        assert_eq!(page.limit, DEFAULT_PAGE_LIMIT);
+        assert_eq!(page.after, None);
+        assert_eq!(page.order, SortOrder::Asc);
+        assert_eq!(page.fetch_limit(), i64::from(DEFAULT_PAGE_LIMIT) + 1);
+    }
+
+    #[test]
+    fn page_request_limit_must_be_in_range() {
+        for limit in [0, MAX_PAGE_LIMIT + 1] {
+            assert!(matches!(
+                PageRequest::new(Some(limit), None, None),
+                Err(AppError::Domain(DomainError::InvalidPageLimit {
+                    max: MAX_PAGE_LIMIT
+                }))
+            ));
+        }
+        assert!(PageRequest::new(Some(1), None, None).is_ok());
+        assert!(PageRequest::new(Some(MAX_PAGE_LIMIT), None, None).is_ok());
+    }
+
+    #[test]
+    fn page_request_decodes_the_cursor() {
+        let encoded = cursor().encode();
+        let page = PageRequest::new(Some(10), Some(&encoded), Some(SortOrder::Desc)).unwrap();
+
+        assert_eq!(page.after, Some(cursor()));
+        assert_eq!(page.order, SortOrder::Desc);
+        assert!(PageRequest::new(Some(10), Some("garbage!"), None).is_err());
+    }
+
+    #[test]
+    fn page_from_rows_hands_out_a_cursor_only_when_more_rows_exist() {
+        let request = PageRequest::new(Some(2), None, None).unwrap();
+        let cursor_of = |n: &u32| Cursor {
+            created_at: cursor().created_at,
+            id: Uuid::from_u128(u128::from(*n)),
+        };
+
+        let page = Page::from_rows(vec![1, 2, 3], &request, cursor_of);
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next_cursor.map(|c| c.id), Some(Uuid::from_u128(2)));
+
+        let last = Page::from_rows(vec![1, 2], &request, cursor_of);
+        assert_eq!(last.items, vec![1, 2]);
+        assert_eq!(last.next_cursor, None);
+    }
+}
@@ -7,6 +7,10 @@ use crate::{
     logic::CreateProductCommand,
 };
 
+const DESCRIPTION_MAX_LEN: usize = 5000;
+const SKU_MAX_LEN: usize = 64;
+const IMAGE_URL_MAX_LEN: usize = 2048;
+
 #[derive(Debug, Clone)]
 pub struct ProductName(String);
 
@@ -24,11 +28,101 @@ impl ProductName {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ProductDescription(String);
+
+impl ProductDescription {
+    pub fn new(value: String) -> Result<Self, AppError> {
+        if value.chars().count() > DESCRIPTION_MAX_LEN {
+            return Err(AppError::Domain(DomainError::ProductDescriptionTooLong {
+                max: DESCRIPTION_MAX_LEN,
+            }));
+        }
+
+        Ok(Self(value))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Stock keeping unit, stored uppercased so `ab-1` and `AB-1` collide
+#[derive(Debug, Clone)]
+pub struct Sku(String);
+
+impl Sku {
+    pub fn new(value: String) -> Result<Self, AppError> {
+        let normalized = value.trim().to_uppercase();
+
+        if normalized.is_empty()
+            || normalized.len() > SKU_MAX_LEN
+            || !normalized
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        {
+            return Err(AppError::Domain(DomainError::InvalidSku(value)));
+        }
+
+        Ok(Self(normalized))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageUrl(String);
+
+impl ImageUrl {
+    pub fn new(value: String) -> Result<Self, AppError> {
+        let valid = value.len() <= IMAGE_URL_MAX_LEN
+            && !value.chars().any(char::is_whitespace)
+            && ["https://", "http://"].iter().any(|scheme| {
+                value
+                    .strip_prefix(scheme)
+                    .is_some_and(|rest| !rest.is_empty())
+            });
+
+        if !valid {
+            return Err(AppError::Domain(DomainError::InvalidImageUrl(value)));
+        }
+
+        Ok(Self(value))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Narrows a product listing, unset fields match everything
+///
+/// Archived products are never listed
+#[derive(Debug, Clone, Default)]
+pub struct ProductFilter {
+    /// Case-insensitive prefix of the product name
+    pub name_prefix: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Product {
     pub id: Uuid,
     pub name: ProductName,
+    pub description: Option<ProductDescription>,
+    pub sku: Option<Sku>,
+    pub image_urls: Vec<ImageUrl>,
     pub created_at: DateTime<Utc>,
+    /// Set once the product is withdrawn from the catalog
+    pub archived_at: Option<DateTime<Utc>>,
+}
+
+impl Product {
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
 }
 
 impl TryFrom<CreateProductCommand> for Product {
@@ -36,9 +130,17 @@ impl TryFrom<CreateProductCommand> for Product {
 
     fn try_from(value: CreateProductCommand) -> Result<Self, Self::Error> {
         Ok(Self {
-            id: Uuid::new_v4(),
+            id: value.id,
             name: ProductName::new(value.name)?,
+            description: value.description.map(ProductDescription::new).transpose()?,
+            sku: value.sku.map(Sku::new).transpose()?,
+            image_urls: value
+                .image_urls
+                .into_iter()
+                .map(ImageUrl::new)
+                .collect::<Result<_, _>>()?,
             created_at: DateTime::default(),
+            archived_at: None,
         })
     }
 }
@@ -50,7 +152,76 @@ impl TryFrom<ProductRecord> for Product {
         Ok(Self {
             id: value.id,
             name: ProductName::new(value.name)?,
+            description: value.description.map(ProductDescription::new).transpose()?,
+            sku: value.sku.map(Sku::new).transpose()?,
+            image_urls: value
+                .image_urls
+                .into_iter()
+                .map(ImageUrl::new)
+                .collect::<Result<_, _>>()?,
             created_at: value.created_at,
+            archived_at: value.archived_at,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_must_not_be_blank() {
+        assert!(ProductName::new("Headphones".to_string()).is_ok());
+        assert!(matches!(
+            ProductName::new("  ".to_string()),
+            Err(AppError::Domain(DomainError::ProductNameEmpty))
+        ));
+    }
+
+    #[test]
+    fn descriptions_are_limited_in_characters() {
+        assert!(ProductDescription::new("é".repeat(DESCRIPTION_MAX_LEN)).is_ok());
+        assert!(matches!(
+            ProductDescription::new("a".repeat(DESCRIPTION_MAX_LEN + 1)),
+            Err(AppError::Domain(
+                DomainError::ProductDescriptionTooLong { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn skus_are_trimmed_and_uppercased() {
+        assert_eq!(Sku::new(" ab-1_c ".to_string()).unwrap().as_str(), "AB-1_C");
+
+        for invalid in ["", "ab 1", "ab/1", &"a".repeat(SKU_MAX_LEN + 1)] {
+            assert!(
+                matches!(
+                    Sku::new(invalid.to_string()),
+                    Err(AppError::Domain(DomainError::InvalidSku(_)))
+                ),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn image_urls_must_be_http_without_whitespace() {
+        assert!(ImageUrl::new("https://cdn.example.com/a.png".to_string()).is_ok());
+        assert!(ImageUrl::new("http://cdn.example.com/a.png".to_string()).is_ok());
+
+        for invalid in [
+            "https://",
+            "ftp://cdn.example.com/a.png",
+            "https://cdn.example.com/a b.png",
+            "cdn.example.com/a.png",
+        ] {
+            assert!(
+                matches!(
+                    ImageUrl::new(invalid.to_string()),
+                    Err(AppError::Domain(DomainError::InvalidImageUrl(_)))
+                ),
+                "{invalid:?}"
+            );
+        }
+    }
+}
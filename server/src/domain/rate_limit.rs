@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use crate::errors::{AppError, ServiceError};
+
+/// Sustained rate and burst of one limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RateQuota {
+    pub per_second: u32,
+    pub burst: u32,
+}
+
+impl RateQuota {
+    /// A quota whose burst equals its rate
+    pub fn per_second(requests_per_second: u32) -> Self {
+        Self {
+            per_second: requests_per_second,
+            burst: requests_per_second,
+        }
+    }
+
+    /// Time for a spent request to be given back
+    pub fn emission_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.per_second.max(1)
+    }
+
+    /// `share` of the quota, at least one request per second
+    pub fn scaled(&self, share: f64) -> Self {
+        let scale = |value: u32| ((value as f64 * share).floor() as u32).max(1);
+        Self {
+            per_second: scale(self.per_second),
+            burst: scale(self.burst),
+        }
+    }
+}
+
+/// State of a GCRA bucket right after a request was counted against it
+#[derive(Debug, Clone, Copy)]
+pub struct GcraBucket {
+    pub allowed: bool,
+    /// How far the bucket's theoretical arrival time is ahead of now
+    pub ahead: Duration,
+}
+
+/// Outcome of a rate limit check, as reported in the `RateLimit-*` headers
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitDecision {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Until the full burst is available again
+    pub reset: Duration,
+    /// Set when the request was refused
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitDecision {
+    pub fn allowed(quota: RateQuota, remaining: u32) -> Self {
+        Self {
+            limit: quota.burst,
+            remaining,
+            reset: quota.emission_interval() * quota.burst.saturating_sub(remaining),
+            retry_after: None,
+        }
+    }
+
+    pub fn refused(quota: RateQuota, retry_after: Duration) -> Self {
+        Self {
+            limit: quota.burst,
+            remaining: 0,
+            reset: retry_after + quota.emission_interval() * quota.burst.saturating_sub(1),
+            retry_after: Some(retry_after),
+        }
+    }
+
+    /// Reads the decision off a bucket kept by the shared backend
+    pub fn from_gcra(quota: RateQuota, bucket: GcraBucket) -> Self {
+        let interval = quota.emission_interval();
+        if bucket.allowed {
+            let spent = bucket.ahead.as_nanos().div_ceil(interval.as_nanos().max(1)) as u32;
+            Self::allowed(quota, quota.burst.saturating_sub(spent))
+        } else {
+            let window = interval * quota.burst.saturating_sub(1);
+            Self::refused(quota, bucket.ahead.saturating_sub(window))
+        }
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        self.retry_after.is_none()
+    }
+
+    /// `Err(RateLimitExceeded)` when the request was refused
+    pub fn into_result(self) -> Result<(), AppError> {
+        match self.retry_after {
+            Some(retry_after) => Err(AppError::Service(ServiceError::RateLimitExceeded {
+                retry_after,
+            })),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUOTA: RateQuota = RateQuota {
+        per_second: 10,
+        burst: 10,
+    };
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn allowed(ahead: Duration) -> RateLimitDecision {
+        RateLimitDecision::from_gcra(
+            QUOTA,
+            GcraBucket {
+                allowed: true,
+                ahead,
+            },
+        )
+    }
+
+    fn refused(ahead: Duration) -> RateLimitDecision {
+        RateLimitDecision::from_gcra(
+            QUOTA,
+            GcraBucket {
+                allowed: false,
+                ahead,
+            },
+        )
+    }
+
+    #[test]
+    fn first_request_spends_one_of_the_burst() {
+        let decision = allowed(ms(100));
+
+        assert!(decision.is_allowed());
+        assert_eq!(decision.limit, 10);
+        assert_eq!(decision.remaining, 9);
+        assert_eq!(decision.reset, ms(100));
+    }
+
+    #[test]
+    fn partly_refilled_request_counts_as_spent() {
+        let decision = allowed(ms(150));
+
+        assert_eq!(decision.remaining, 8);
+        assert_eq!(decision.reset, ms(200));
+    }
+
+    #[test]
+    fn last_request_of_the_burst_leaves_nothing() {
+        let decision = allowed(ms(1000));
+
+        assert!(decision.is_allowed());
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.reset, ms(1000));
+    }
+
+    #[test]
+    fn refused_request_waits_for_one_interval_to_refill() {
+        // The bucket is a full burst ahead, the next request fits in 100ms
+        let decision = refused(ms(1000));
+
+        assert!(!decision.is_allowed());
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.retry_after, Some(ms(100)));
+        assert_eq!(decision.reset, ms(1000));
+    }
+
+    #[test]
+    fn refused_request_never_waits_a_negative_time() {
+        let decision = refused(ms(500));
+
+        assert_eq!(decision.retry_after, Some(Duration::ZERO));
+        assert_eq!(decision.reset, ms(900));
+    }
+
+    #[test]
+    fn smaller_burst_tightens_the_window() {
+        let quota = RateQuota {
+            per_second: 10,
+            burst: 2,
+        };
+        let decision = RateLimitDecision::from_gcra(
+            quota,
+            GcraBucket {
+                allowed: false,
+                ahead: ms(250),
+            },
+        );
+
+        assert_eq!(decision.limit, 2);
+        assert_eq!(decision.retry_after, Some(ms(150)));
+    }
+
+    #[test]
+    fn into_result_reports_the_wait() {
+        assert!(allowed(ms(100)).into_result().is_ok());
+        match refused(ms(1200)).into_result() {
+            Err(AppError::Service(ServiceError::RateLimitExceeded { retry_after })) => {
+                assert_eq!(retry_after, ms(300))
+            }
+            other => panic!("expected RateLimitExceeded, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn scaled_quota_keeps_at_least_one_request() {
+        assert_eq!(
+            QUOTA.scaled(0.25),
+            RateQuota {
+                per_second: 2,
+                burst: 2
+            }
+        );
+        assert_eq!(
+            QUOTA.scaled(0.01),
+            RateQuota {
+                per_second: 1,
+                burst: 1
+            }
+        );
+        assert_eq!(RateQuota::per_second(0).emission_interval(), ms(1000));
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    adapters::db::settings_audit::SettingsChangeRecord,
+    errors::{AppError, DomainError},
+};
+
+/// What admission does with orders for a flash sale already known to be sold out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoldOutGate {
+    /// Rejected with 409 before they reach the queue
+    #[default]
+    Reject,
+    /// Queued like any other order and failed by the worker
+    Off,
+}
+
+impl SoldOutGate {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "reject" => Some(Self::Reject),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Settings that can be changed while the service is running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuntimeSettings {
+    /// Order requests a single user may make per second
+    pub rate_limit_per_user: u32,
+    /// Orders are admitted while fewer than this many wait in the queue
+    pub queue_admission_limit: usize,
+    /// `EnvFilter` directive for the service log
+    pub log_level: String,
+    pub sold_out_gate: SoldOutGate,
+}
+
+impl RuntimeSettings {
+    /// The admission limit can't exceed the queue, which is sized at startup
+    pub fn validate(&self, queue_capacity: usize) -> Result<(), AppError> {
+        if self.rate_limit_per_user == 0 {
+            return Err(invalid("rate_limit_per_user must be greater than 0"));
+        }
+        if self.queue_admission_limit == 0 || self.queue_admission_limit > queue_capacity {
+            return Err(invalid(format!(
+                "queue_admission_limit must be between 1 and the queue capacity ({queue_capacity})"
+            )));
+        }
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.log_level) {
+            return Err(invalid(format!("log_level is not a valid filter: {e}")));
+        }
+
+        Ok(())
+    }
+
+    /// Copy of these settings with the patch's fields replaced
+    pub fn apply(&self, patch: RuntimeSettingsPatch) -> Self {
+        Self {
+            rate_limit_per_user: patch
+                .rate_limit_per_user
+                .unwrap_or(self.rate_limit_per_user),
+            queue_admission_limit: patch
+                .queue_admission_limit
+                .unwrap_or(self.queue_admission_limit),
+            log_level: patch.log_level.unwrap_or_else(|| self.log_level.clone()),
+            sold_out_gate: patch.sold_out_gate.unwrap_or(self.sold_out_gate),
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> AppError {
+    AppError::Domain(DomainError::InvalidRuntimeSetting(message.into()))
+}
+
+/// Partial update of the runtime settings, unset fields are kept
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeSettingsPatch {
+    pub rate_limit_per_user: Option<u32>,
+    pub queue_admission_limit: Option<usize>,
+    pub log_level: Option<String>,
+    pub sold_out_gate: Option<SoldOutGate>,
+}
+
+impl RuntimeSettingsPatch {
+    /// Only the fields that differ between `from` and `to`
+    pub fn diff(from: &RuntimeSettings, to: &RuntimeSettings) -> Self {
+        Self {
+            rate_limit_per_user: Some(to.rate_limit_per_user)
+                .filter(|rate| *rate != from.rate_limit_per_user),
+            queue_admission_limit: Some(to.queue_admission_limit)
+                .filter(|limit| *limit != from.queue_admission_limit),
+            log_level: Some(&to.log_level)
+                .filter(|level| **level != from.log_level)
+                .cloned(),
+            sold_out_gate: Some(to.sold_out_gate).filter(|gate| *gate != from.sold_out_gate),
+        }
+    }
+}
+
+/// Where a runtime settings change came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsChangeSource {
+    AdminApi,
+    ConfigFile,
+}
+
+impl SettingsChangeSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AdminApi => "admin_api",
+            Self::ConfigFile => "config_file",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "admin_api" => Some(Self::AdminApi),
+            "config_file" => Some(Self::ConfigFile),
+            _ => None,
+        }
+    }
+}
+
+/// Audit entry for one applied change
+#[derive(Debug, Clone)]
+pub struct SettingsChange {
+    pub id: Uuid,
+    pub source: SettingsChangeSource,
+    /// Admin who made the change, `None` for config file reloads
+    pub changed_by: Option<Uuid>,
+    pub previous: RuntimeSettings,
+    pub current: RuntimeSettings,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl SettingsChange {
+    pub fn new(
+        source: SettingsChangeSource,
+        changed_by: Option<Uuid>,
+        previous: RuntimeSettings,
+        current: RuntimeSettings,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            source,
+            changed_by,
+            previous,
+            current,
+            changed_at: Utc::now(),
+        }
+    }
+}
+
+impl TryFrom<SettingsChangeRecord> for SettingsChange {
+    type Error = AppError;
+
+    fn try_from(value: SettingsChangeRecord) -> Result<Self, Self::Error> {
+        let decode = |json: serde_json::Value| {
+            serde_json::from_value(json).map_err(|e| AppError::Unexpected(e.into()))
+        };
+
+        Ok(Self {
+            id: value.id,
+            source: SettingsChangeSource::parse(&value.source).ok_or_else(|| {
+                AppError::Unexpected(anyhow::anyhow!(
+                    "unknown settings change source {}",
+                    value.source
+                ))
+            })?,
+            changed_by: value.changed_by,
+            previous: decode(value.previous)?,
+            current: decode(value.current)?,
+            changed_at: value.changed_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> RuntimeSettings {
+        RuntimeSettings {
+            rate_limit_per_user: 5,
+            queue_admission_limit: 100,
+            log_level: "info".to_string(),
+            sold_out_gate: SoldOutGate::Reject,
+        }
+    }
+
+    fn rejects(settings: RuntimeSettings) -> bool {
+        matches!(
+            settings.validate(100),
+            Err(AppError::Domain(DomainError::InvalidRuntimeSetting(_)))
+        )
+    }
+
+    #[test]
+    fn validates_against_the_queue_capacity() {
+        assert!(settings().validate(100).is_ok());
+
+        assert!(rejects(RuntimeSettings {
+            rate_limit_per_user: 0,
+            ..settings()
+        }));
+        assert!(rejects(RuntimeSettings {
+            queue_admission_limit: 0,
+            ..settings()
+        }));
+        assert!(rejects(RuntimeSettings {
+            queue_admission_limit: 101,
+            ..settings()
+        }));
+        assert!(rejects(RuntimeSettings {
+            log_level: "info,[".to_string(),
+            ..settings()
+        }));
+    }
+
+    #[test]
+    fn patches_replace_only_their_fields() {
+        let patched = settings().apply(RuntimeSettingsPatch {
+            queue_admission_limit: Some(50),
+            sold_out_gate: Some(SoldOutGate::Off),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            patched,
+            RuntimeSettings {
+                queue_admission_limit: 50,
+                sold_out_gate: SoldOutGate::Off,
+                ..settings()
+            }
+        );
+    }
+
+    #[test]
+    fn diff_holds_only_the_changed_fields_and_reapplies_them() {
+        let from = settings();
+        let to = RuntimeSettings {
+            log_level: "debug".to_string(),
+            ..settings()
+        };
+
+        let patch = RuntimeSettingsPatch::diff(&from, &to);
+        assert_eq!(patch.log_level.as_deref(), Some("debug"));
+        assert_eq!(patch.rate_limit_per_user, None);
+        assert_eq!(patch.queue_admission_limit, None);
+        assert_eq!(patch.sold_out_gate, None);
+        assert_eq!(from.apply(patch), to);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for source in [
+            SettingsChangeSource::AdminApi,
+            SettingsChangeSource::ConfigFile,
+        ] {
+            assert_eq!(SettingsChangeSource::parse(source.as_str()), Some(source));
+        }
+        assert_eq!(SoldOutGate::parse("off"), Some(SoldOutGate::Off));
+        assert_eq!(SoldOutGate::parse("allow"), None);
+    }
+}
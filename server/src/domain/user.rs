@@ -1,19 +1,191 @@
 use sqlx::types::chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::adapters::db::user::UserRecord;
+use crate::{
+    adapters::db::user::UserRecord,
+    errors::{AppError, DomainError},
+    logic::CreateUserCommand,
+};
+
+const EMAIL_MAX_LEN: usize = 254;
+const USERNAME_MIN_LEN: usize = 3;
+const USERNAME_MAX_LEN: usize = 50;
+
+/// Email address, stored lowercased so lookups and uniqueness ignore case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email(String);
+
+impl Email {
+    pub fn new(value: String) -> Result<Self, AppError> {
+        let normalized = value.trim().to_lowercase();
+
+        let valid = normalized.len() <= EMAIL_MAX_LEN
+            && !normalized.chars().any(char::is_whitespace)
+            && match normalized.split_once('@') {
+                Some((local, domain)) => {
+                    !local.is_empty()
+                        && !domain.contains('@')
+                        && domain.contains('.')
+                        && !domain.starts_with('.')
+                        && !domain.ends_with('.')
+                }
+                None => false,
+            };
+
+        if !valid {
+            return Err(AppError::Domain(DomainError::InvalidEmail(value)));
+        }
+
+        Ok(Self(normalized))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Public handle, 3 to 50 letters, digits, `_`, `-` or `.`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Username(String);
+
+impl Username {
+    pub fn new(value: String) -> Result<Self, AppError> {
+        let value = value.trim().to_owned();
+        let len = value.chars().count();
+
+        if !(USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&len)
+            || !value
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            return Err(AppError::Domain(DomainError::InvalidUsername));
+        }
+
+        Ok(Self(value))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Narrows a user listing, unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+    pub created_after: Option<DateTime<Utc>>,
+}
 
 #[derive(Debug, Clone)]
 pub struct User {
     pub id: Uuid,
+    /// `None` for users created before profiles existed
+    pub email: Option<Email>,
+    pub username: Option<Username>,
     pub created_at: DateTime<Utc>,
 }
 
-impl From<UserRecord> for User {
-    fn from(value: UserRecord) -> Self {
-        Self {
+impl TryFrom<CreateUserCommand> for User {
+    type Error = AppError;
+
+    fn try_from(value: CreateUserCommand) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            email: Some(Email::new(value.email)?),
+            username: Some(Username::new(value.username)?),
+            created_at: Utc::now(),
+        })
+    }
+}
+
+impl TryFrom<UserRecord> for User {
+    type Error = AppError;
+
+    fn try_from(value: UserRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: value.id,
+            email: value.email.map(Email::new).transpose()?,
+            username: value.username.map(Username::new).transpose()?,
             created_at: value.created_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(value: &str) -> Result<Email, AppError> {
+        Email::new(value.to_string())
+    }
+
+    fn username(value: &str) -> Result<Username, AppError> {
+        Username::new(value.to_string())
+    }
+
+    #[test]
+    fn email_is_trimmed_and_lowercased() {
+        assert_eq!(
+            email("  Jane.Doe@Example.COM ").unwrap().as_str(),
+            "jane.doe@example.com"
+        );
+    }
+
+    #[test]
+    fn email_needs_a_local_part_and_a_dotted_domain() {
+        for value in [
+            "",
+            "jane",
+            "@example.com",
+            "jane@",
+            "jane@localhost",
+            "jane@.example.com",
+            "jane@example.com.",
+            "jane@doe@example.com",
+            "jane doe@example.com",
+        ] {
+            assert!(
+                matches!(
+                    email(value),
+                    Err(AppError::Domain(DomainError::InvalidEmail(_)))
+                ),
+                "{value:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn email_is_limited_to_254_characters() {
+        let domain = "@example.com";
+        let longest = format!("{}{domain}", "a".repeat(EMAIL_MAX_LEN - domain.len()));
+
+        assert!(email(&longest).is_ok());
+        assert!(email(&format!("a{longest}")).is_err());
+    }
+
+    #[test]
+    fn username_is_trimmed() {
+        assert_eq!(username("  jane_doe ").unwrap().as_str(), "jane_doe");
+    }
+
+    #[test]
+    fn username_length_counts_characters() {
+        assert!(username("ab").is_err());
+        assert!(username("abc").is_ok());
+        assert!(username(&"é".repeat(USERNAME_MAX_LEN)).is_ok());
+        assert!(username(&"é".repeat(USERNAME_MAX_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn username_allows_only_letters_digits_and_separators() {
+        assert!(username("jane.doe-99_x").is_ok());
+        for value in ["jane doe", "jane@doe", "jane/doe"] {
+            assert!(
+                matches!(
+                    username(value),
+                    Err(AppError::Domain(DomainError::InvalidUsername))
+                ),
+                "{value:?} should be rejected"
+            );
         }
     }
 }
@@ -1,112 +1,192 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
+use std::time::Duration;
 
-use crate::errors::{AppError, DomainError};
+use crate::{
+    adapters::http::middleware::rate_limit_middleware::apply_rate_limit_headers,
+    domain::{order_guard::GuardRejection, rate_limit::RateLimitDecision},
+    errors::{AppError, DomainError},
+};
 
 #[derive(Debug)]
 pub struct ApiError {
     pub status: StatusCode,
     pub code: &'static str,
     pub message: String,
+    /// Sent as `Retry-After`, rounded up to whole seconds
+    pub retry_after: Option<Duration>,
+    /// Sent as `RateLimit-*` headers when a rate limit refused the request
+    pub rate_limit: Option<RateLimitDecision>,
 }
 
 impl From<AppError> for ApiError {
     fn from(value: AppError) -> Self {
         match value {
             // Domain errors -> 400 Bad Request
-            AppError::Domain(DomainError::ProductNameEmpty) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "PRODUCT_NAME_EMPTY",
-                message: "Product name cannot be empty".into(),
-            },
-            AppError::Domain(DomainError::ProductPriceInvalid) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "PRODUCT_PRICE_INVALID",
-                message: "Product price must be positive".into(),
-            },
-            AppError::Domain(DomainError::InvalidEmail(email)) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "INVALID_EMAIL",
-                message: format!("Invalid email format: {}", email),
-            },
-            AppError::Domain(DomainError::InvalidUsername) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "INVALID_USERNAME",
-                message: "Username must be between 3 and 50 characters".into(),
-            },
-            AppError::Domain(DomainError::InvalidFlashSaleStartTime) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "INVALID_FLASH_SALE_START_TIME",
-                message: "Flash sale start time must be in the future".into(),
-            },
-            AppError::Domain(DomainError::InvalidFlashSaleEndTime) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "INVALID_FLASH_SALE_END_TIME",
-                message: "Flash sale end time must be after start time".into(),
-            },
-            AppError::Domain(DomainError::InvalidFlashSaleQuantity) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "INVALID_FLASH_SALE_QUANTITY",
-                message: "Flash sale quantity must be positive".into(),
-            },
-            AppError::Domain(DomainError::InvalidOrderQuantity) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "INVALID_ORDER_QUANTITY",
-                message: "Order quantity must be positive".into(),
-            },
-            AppError::Domain(DomainError::OrderAlreadyCompleted) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "ORDER_ALREADY_COMPLETED",
-                message: "Cannot modify completed order".into(),
-            },
+            AppError::Domain(DomainError::InvalidCursor) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_CURSOR",
+                "Invalid pagination cursor".into(),
+            ),
+            AppError::Domain(DomainError::InvalidPageLimit { max }) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_PAGE_LIMIT",
+                format!("Page limit must be between 1 and {}", max),
+            ),
+            AppError::Domain(DomainError::ProductNameEmpty) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "PRODUCT_NAME_EMPTY",
+                "Product name cannot be empty".into(),
+            ),
+            AppError::Domain(DomainError::ProductPriceInvalid) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "PRODUCT_PRICE_INVALID",
+                "Product price must be positive".into(),
+            ),
+            AppError::Domain(DomainError::ProductDescriptionTooLong { max }) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "PRODUCT_DESCRIPTION_TOO_LONG",
+                format!("Product description must be at most {} characters", max),
+            ),
+            AppError::Domain(DomainError::InvalidSku(sku)) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_SKU",
+                format!(
+                    "Invalid SKU: {} (use up to 64 letters, digits, `-` or `_`)",
+                    sku
+                ),
+            ),
+            AppError::Domain(DomainError::InvalidImageUrl(url)) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_IMAGE_URL",
+                format!("Invalid image URL: {}", url),
+            ),
+            AppError::Domain(DomainError::InvalidEmail(email)) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_EMAIL",
+                format!("Invalid email format: {}", email),
+            ),
+            AppError::Domain(DomainError::InvalidUsername) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_USERNAME",
+                "Username must be 3 to 50 letters, digits, `_`, `-` or `.`".into(),
+            ),
+            AppError::Domain(DomainError::InvalidApiKeyScope(scope)) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_API_KEY_SCOPE",
+                format!("Unknown API key scope: {}", scope),
+            ),
+            AppError::Domain(DomainError::InvalidApiKeyRateLimit) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_API_KEY_RATE_LIMIT",
+                "API key rate limit must be positive".into(),
+            ),
+            AppError::Domain(DomainError::InvalidRuntimeSetting(msg)) => {
+                Self::new(StatusCode::BAD_REQUEST, "INVALID_RUNTIME_SETTING", msg)
+            }
+            AppError::Domain(DomainError::InvalidFlashSaleStartTime) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_FLASH_SALE_START_TIME",
+                "Flash sale start time must be in the future".into(),
+            ),
+            AppError::Domain(DomainError::InvalidFlashSaleEndTime) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_FLASH_SALE_END_TIME",
+                "Flash sale end time must be after start time".into(),
+            ),
+            AppError::Domain(DomainError::InvalidFlashSaleQuantity) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_FLASH_SALE_QUANTITY",
+                "Flash sale quantity must be positive".into(),
+            ),
+            AppError::Domain(DomainError::InvalidFlashSalePerUserLimit) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_FLASH_SALE_PER_USER_LIMIT",
+                "Flash sale per-user limit must be positive".into(),
+            ),
+            AppError::Domain(DomainError::InvalidFlashSaleQueueShare) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_FLASH_SALE_QUEUE_SHARE",
+                "Flash sale queue share must be greater than 0 and at most 1".into(),
+            ),
+            AppError::Domain(DomainError::InvalidFlashSaleAdmissionRate) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_FLASH_SALE_ADMISSION_RATE",
+                "Flash sale admission rate must be positive".into(),
+            ),
+            AppError::Domain(DomainError::InvalidFlashSaleSchedulingWeight) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_FLASH_SALE_SCHEDULING_WEIGHT",
+                "Flash sale scheduling weight must be positive".into(),
+            ),
+            AppError::Domain(DomainError::InvalidOrderGuardAccountAge) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_ORDER_GUARD_ACCOUNT_AGE",
+                "Minimum account age must be positive".into(),
+            ),
+            AppError::Domain(DomainError::InvalidOrderGuardVelocity) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_ORDER_GUARD_VELOCITY",
+                "Velocity limit needs a positive max_orders and window_secs".into(),
+            ),
+            AppError::Domain(DomainError::InvalidOrderQuantity) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_ORDER_QUANTITY",
+                "Order quantity must be positive".into(),
+            ),
+            AppError::Domain(DomainError::OrderAlreadyCompleted) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "ORDER_ALREADY_COMPLETED",
+                "Cannot modify completed order".into(),
+            ),
 
             // Repository errors
-            AppError::Repo(crate::errors::RepoError::NotFound { entity_type }) => Self {
-                status: StatusCode::NOT_FOUND,
-                code: "NOT_FOUND",
-                message: format!("{} not found", entity_type),
-            },
-            AppError::Repo(crate::errors::RepoError::Conflict { constraint }) => Self {
-                status: StatusCode::CONFLICT,
-                code: "CONFLICT",
-                message: format!("Resource already exists: {}", constraint),
-            },
-            AppError::Repo(crate::errors::RepoError::ForeignKeyViolation { constraint }) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "FOREIGN_KEY_VIOLATION",
-                message: format!("Invalid reference: {}", constraint),
-            },
-            AppError::Repo(crate::errors::RepoError::CheckViolation { constraint }) => Self {
-                status: StatusCode::BAD_REQUEST,
-                code: "CHECK_VIOLATION",
-                message: format!("Constraint violation: {}", constraint),
-            },
+            AppError::Repo(crate::errors::RepoError::NotFound { entity_type }) => Self::new(
+                StatusCode::NOT_FOUND,
+                "NOT_FOUND",
+                format!("{} not found", entity_type),
+            ),
+            AppError::Repo(crate::errors::RepoError::Conflict { constraint }) => {
+                Self::unique_conflict(&constraint)
+            }
+            AppError::Repo(crate::errors::RepoError::ForeignKeyViolation { constraint }) => {
+                Self::new(
+                    StatusCode::BAD_REQUEST,
+                    "FOREIGN_KEY_VIOLATION",
+                    format!("Invalid reference: {}", constraint),
+                )
+            }
+            AppError::Repo(crate::errors::RepoError::CheckViolation { constraint }) => Self::new(
+                StatusCode::BAD_REQUEST,
+                "CHECK_VIOLATION",
+                format!("Constraint violation: {}", constraint),
+            ),
             AppError::Repo(crate::errors::RepoError::SerializationFailure) => {
                 tracing::warn!("Database serialization failure (concurrent modification detected)");
-                Self {
-                    status: StatusCode::CONFLICT,
-                    code: "CONCURRENT_MODIFICATION",
-                    message: "Resource was modified by another request".into(),
-                }
+                Self::new(
+                    StatusCode::CONFLICT,
+                    "CONCURRENT_MODIFICATION",
+                    "Resource was modified by another request".into(),
+                )
             }
             AppError::Repo(crate::errors::RepoError::Transaction(ref err)) => {
                 tracing::error!(error = ?err, "Database transaction failed");
-                Self {
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                    code: "TRANSACTION_ERROR",
-                    message: "Database transaction failed".into(),
-                }
+                Self::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "TRANSACTION_ERROR",
+                    "Database transaction failed".into(),
+                )
             }
             AppError::Repo(crate::errors::RepoError::ConnectionPool(ref err)) => {
                 tracing::error!(error = ?err, "Database connection pool exhausted or unavailable");
-                Self {
-                    status: StatusCode::SERVICE_UNAVAILABLE,
-                    code: "DATABASE_UNAVAILABLE",
-                    message: "Database connection failed".into(),
-                }
+                Self::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "DATABASE_UNAVAILABLE",
+                    "Database connection failed".into(),
+                )
             }
             AppError::Repo(crate::errors::RepoError::Database {
                 ref source,
@@ -117,65 +197,113 @@ impl From<AppError> for ApiError {
                     operation = operation,
                     "Database operation failed"
                 );
-                Self {
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                    code: "DATABASE_ERROR",
-                    message: "Database operation failed".into(),
-                }
+                Self::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "DATABASE_ERROR",
+                    "Database operation failed".into(),
+                )
             }
 
             // Service errors
-            AppError::Service(crate::errors::ServiceError::Unauthenticated) => Self {
-                status: StatusCode::UNAUTHORIZED,
-                code: "UNAUTHENTICATED",
-                message: "Authentication required".into(),
-            },
-            AppError::Service(crate::errors::ServiceError::Forbidden(msg)) => Self {
-                status: StatusCode::FORBIDDEN,
-                code: "FORBIDDEN",
-                message: msg,
-            },
-            AppError::Service(crate::errors::ServiceError::BusinessRule(msg)) => Self {
-                status: StatusCode::UNPROCESSABLE_ENTITY,
-                code: "BUSINESS_RULE_VIOLATION",
-                message: msg,
-            },
-            AppError::Service(crate::errors::ServiceError::Conflict(msg)) => Self {
-                status: StatusCode::CONFLICT,
-                code: "CONFLICT",
-                message: msg,
-            },
-            AppError::Service(crate::errors::ServiceError::InvalidStateTransition(msg)) => Self {
-                status: StatusCode::CONFLICT,
-                code: "INVALID_STATE_TRANSITION",
-                message: msg,
-            },
+            AppError::Service(crate::errors::ServiceError::Unauthenticated) => Self::new(
+                StatusCode::UNAUTHORIZED,
+                "UNAUTHENTICATED",
+                "Authentication required".into(),
+            ),
+            AppError::Service(crate::errors::ServiceError::Forbidden(msg)) => {
+                Self::new(StatusCode::FORBIDDEN, "FORBIDDEN", msg)
+            }
+            AppError::Service(crate::errors::ServiceError::BusinessRule(msg)) => Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "BUSINESS_RULE_VIOLATION",
+                msg,
+            ),
+            AppError::Service(crate::errors::ServiceError::Conflict(msg)) => {
+                Self::new(StatusCode::CONFLICT, "CONFLICT", msg)
+            }
+            AppError::Service(crate::errors::ServiceError::SoldOut) => Self::new(
+                StatusCode::CONFLICT,
+                "SOLD_OUT",
+                "Flash sale is sold out".into(),
+            ),
+            AppError::Service(crate::errors::ServiceError::InvalidStateTransition(msg)) => {
+                Self::new(StatusCode::CONFLICT, "INVALID_STATE_TRANSITION", msg)
+            }
             AppError::Service(crate::errors::ServiceError::ExternalService { service, source }) => {
                 tracing::error!(
                     service = service,
                     error = ?source,
                     "External service call failed"
                 );
-                Self {
-                    status: StatusCode::BAD_GATEWAY,
-                    code: "EXTERNAL_SERVICE_ERROR",
-                    message: format!("External service error: {}", service),
-                }
+                Self::new(
+                    StatusCode::BAD_GATEWAY,
+                    "EXTERNAL_SERVICE_ERROR",
+                    format!("External service error: {}", service),
+                )
+            }
+            AppError::Service(crate::errors::ServiceError::CircuitOpen {
+                dependency,
+                retry_after,
+            }) => Self::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "DEPENDENCY_UNAVAILABLE",
+                format!("The {} is unavailable, please try again later", dependency),
+            )
+            .with_retry_after(retry_after),
+            AppError::Service(crate::errors::ServiceError::ChallengeRequired) => Self::new(
+                StatusCode::PRECONDITION_REQUIRED,
+                "CHALLENGE_REQUIRED",
+                "This sale requires a solved challenge from GET /flash-sales/{id}/challenge in X-Challenge and X-Challenge-Solution".into(),
+            ),
+            AppError::Service(crate::errors::ServiceError::ChallengeRejected(reason)) => Self::new(
+                StatusCode::FORBIDDEN,
+                "CHALLENGE_REJECTED",
+                format!("Challenge solution rejected: {reason}"),
+            ),
+            AppError::Service(crate::errors::ServiceError::OrderRejected(rejection)) => Self::new(
+                StatusCode::FORBIDDEN,
+                match rejection {
+                    GuardRejection::Blocklisted { .. } => "USER_BLOCKED",
+                    GuardRejection::AccountTooNew { .. } => "ACCOUNT_TOO_NEW",
+                    GuardRejection::VelocityExceeded { .. } => "ORDER_VELOCITY_EXCEEDED",
+                    GuardRejection::NotAllowlisted => "NOT_ALLOWLISTED",
+                },
+                rejection.to_string(),
+            ),
+            AppError::Service(crate::errors::ServiceError::RateLimitExceeded { retry_after }) => {
+                Self::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "RATE_LIMIT_EXCEEDED",
+                    "Too many requests".into(),
+                )
+                .with_retry_after(retry_after)
             }
-            AppError::Service(crate::errors::ServiceError::RateLimitExceeded) => Self {
-                status: StatusCode::TOO_MANY_REQUESTS,
-                code: "RATE_LIMIT_EXCEEDED",
-                message: "Too many requests".into(),
-            },
+            AppError::Service(crate::errors::ServiceError::IdempotencyKeyReused) => Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "IDEMPOTENCY_KEY_REUSED",
+                "Idempotency-Key was already used with different request parameters".into(),
+            ),
+            AppError::Service(crate::errors::ServiceError::IdempotencyRequestInProgress) => {
+                Self::new(
+                    StatusCode::CONFLICT,
+                    "IDEMPOTENCY_REQUEST_IN_PROGRESS",
+                    "A request with this Idempotency-Key is still in progress".into(),
+                )
+            }
+            AppError::Service(crate::errors::ServiceError::IdempotencyClaimLost) => Self::new(
+                StatusCode::CONFLICT,
+                "IDEMPOTENCY_CLAIM_LOST",
+                "The Idempotency-Key was taken over by another request".into(),
+            ),
 
             // Catch-all for unexpected errors
             AppError::Unexpected(ref err) => {
                 tracing::error!(error = ?err, "Unexpected error occurred");
-                Self {
-                    status: StatusCode::INTERNAL_SERVER_ERROR,
-                    code: "INTERNAL_ERROR",
-                    message: "An unexpected error occurred".into(),
-                }
+                Self::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "An unexpected error occurred".into(),
+                )
             }
         }
     }
@@ -189,11 +317,11 @@ impl From<anyhow::Error> for ApiError {
             return ApiError::from(app);
         }
 
-        Self {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            code: "INTERNAL_ERROR",
-            message: fallback_message,
-        }
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "INTERNAL_ERROR",
+            fallback_message,
+        )
     }
 }
 
@@ -210,48 +338,89 @@ impl IntoResponse for ApiError {
             message: self.message,
         });
 
-        (self.status, body).into_response()
+        let mut response = (self.status, body).into_response();
+        if let Some(retry_after) = self.retry_after {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(retry_after_secs(retry_after)),
+            );
+        }
+        if let Some(decision) = &self.rate_limit {
+            apply_rate_limit_headers(decision, response.headers_mut());
+        }
+        response
     }
 }
 
 impl ApiError {
-    pub fn transaction_error(source: sqlx::Error) -> Self {
+    pub fn new(status: StatusCode, code: &'static str, message: String) -> Self {
         Self {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            code: "TRANSACTION_ERROR",
-            message: format!("Database transaction failed: {}", source),
+            status,
+            code,
+            message,
+            retry_after: None,
+            rate_limit: None,
         }
     }
 
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = Some(retry_after);
+        self
+    }
+
+    pub fn with_rate_limit(mut self, decision: RateLimitDecision) -> Self {
+        self.rate_limit = Some(decision);
+        self
+    }
+
+    pub fn transaction_error(source: sqlx::Error) -> Self {
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "TRANSACTION_ERROR",
+            format!("Database transaction failed: {}", source),
+        )
+    }
+
     pub fn connection_error(_source: sqlx::Error) -> Self {
-        Self {
-            status: StatusCode::SERVICE_UNAVAILABLE,
-            code: "DATABASE_UNAVAILABLE",
-            message: "Database connection failed".into(),
-        }
+        Self::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "DATABASE_UNAVAILABLE",
+            "Database connection failed".into(),
+        )
     }
 
     pub fn service_unavailable(message: String) -> Self {
-        Self {
-            status: StatusCode::SERVICE_UNAVAILABLE,
-            code: "SERVICE_UNAVAILABLE",
+        Self::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "SERVICE_UNAVAILABLE",
             message,
-        }
+        )
     }
 
     pub fn internal(message: String) -> Self {
-        Self {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            code: "INTERNAL_ERROR",
-            message,
-        }
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", message)
+    }
+
+    /// Names the field behind known unique constraints instead of the constraint
+    fn unique_conflict(constraint: &str) -> Self {
+        let (code, message) = match constraint {
+            "users_email_unique" => ("EMAIL_TAKEN", "Email is already registered".to_string()),
+            "users_username_unique" => ("USERNAME_TAKEN", "Username is already taken".to_string()),
+            "products_sku_unique" => ("SKU_TAKEN", "SKU is already in use".to_string()),
+            _ => (
+                "CONFLICT",
+                format!("Resource already exists: {}", constraint),
+            ),
+        };
+
+        Self::new(StatusCode::CONFLICT, code, message)
     }
 
     pub fn bad_request(message: String) -> Self {
-        Self {
-            status: StatusCode::BAD_REQUEST,
-            code: "BAD_REQUEST",
-            message,
-        }
+        Self::new(StatusCode::BAD_REQUEST, "BAD_REQUEST", message)
     }
 }
+
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
@@ -1,5 +1,12 @@
 #[derive(Debug, thiserror::Error)]
 pub enum DomainError {
+    // Pagination
+    #[error("invalid pagination cursor")]
+    InvalidCursor,
+
+    #[error("page limit must be between 1 and {max}")]
+    InvalidPageLimit { max: u32 },
+
     // Product domain
     #[error("product name cannot be empty")]
     ProductNameEmpty,
@@ -7,13 +14,29 @@ pub enum DomainError {
     #[error("product price must be positive")]
     ProductPriceInvalid,
 
+    #[error("product description must be at most {max} characters")]
+    ProductDescriptionTooLong { max: usize },
+
+    #[error("invalid SKU: {0}")]
+    InvalidSku(String),
+
+    #[error("invalid image URL: {0}")]
+    InvalidImageUrl(String),
+
     // User domain
     #[error("invalid email format: {0}")]
     InvalidEmail(String),
 
-    #[error("username must be between 3 and 50 characters")]
+    #[error("username must be 3 to 50 letters, digits, `_`, `-` or `.`")]
     InvalidUsername,
 
+    // API key domain
+    #[error("unknown API key scope: {0}")]
+    InvalidApiKeyScope(String),
+
+    #[error("API key rate limit must be positive")]
+    InvalidApiKeyRateLimit,
+
     // Flash sale domain
     #[error("flash sale start time must be in the future")]
     InvalidFlashSaleStartTime,
@@ -24,6 +47,28 @@ pub enum DomainError {
     #[error("flash sale quantity must be positive")]
     InvalidFlashSaleQuantity,
 
+    #[error("flash sale per-user limit must be positive")]
+    InvalidFlashSalePerUserLimit,
+
+    #[error("flash sale queue share must be greater than 0 and at most 1")]
+    InvalidFlashSaleQueueShare,
+
+    #[error("flash sale admission rate must be positive")]
+    InvalidFlashSaleAdmissionRate,
+
+    #[error("flash sale scheduling weight must be positive")]
+    InvalidFlashSaleSchedulingWeight,
+
+    #[error("minimum account age must be positive")]
+    InvalidOrderGuardAccountAge,
+
+    #[error("velocity limit needs a positive order count and window")]
+    InvalidOrderGuardVelocity,
+
+    // Runtime settings domain
+    #[error("invalid runtime setting: {0}")]
+    InvalidRuntimeSetting(String),
+
     // Order domain
     #[error("order quantity must be positive")]
     InvalidOrderQuantity,
@@ -12,6 +12,10 @@ pub enum ServiceError {
     #[error("resource conflict: {0}")]
     Conflict(String),
 
+    /// Nothing left to sell, the sale can't recover from this
+    #[error("resource conflict: sold out")]
+    SoldOut,
+
     #[error("invalid state transition: {0}")]
     InvalidStateTransition(String),
 
@@ -22,6 +26,33 @@ pub enum ServiceError {
         source: anyhow::Error,
     },
 
+    /// A circuit breaker refuses calls to a failing dependency
+    #[error("{dependency} unavailable: circuit open")]
+    CircuitOpen {
+        dependency: &'static str,
+        retry_after: std::time::Duration,
+    },
+
+    /// The sale only takes orders with a solved proof-of-work challenge
+    #[error("challenge required")]
+    ChallengeRequired,
+
+    #[error("challenge rejected: {0}")]
+    ChallengeRejected(&'static str),
+
+    /// An order guard found the user not eligible for the sale
+    #[error("order rejected: {0}")]
+    OrderRejected(crate::domain::order_guard::GuardRejection),
+
     #[error("rate limit exceeded")]
-    RateLimitExceeded,
+    RateLimitExceeded { retry_after: std::time::Duration },
+
+    #[error("idempotency key was already used for a different request")]
+    IdempotencyKeyReused,
+
+    #[error("a request with this idempotency key is still in progress")]
+    IdempotencyRequestInProgress,
+
+    #[error("the idempotency key was taken over by another request")]
+    IdempotencyClaimLost,
 }
@@ -0,0 +1,229 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    adapters::http::dtos::api_key_dto::IssueApiKeyRequest,
+    domain::api_key::{ApiKey, ApiKeyScope, ApiKeySecret, hash_api_key},
+    errors::{AppError, DomainError, RepoError, ServiceError},
+    ports::ApiKeyRepo,
+};
+
+/// How long the old key keeps working after a rotation unless told otherwise
+pub const DEFAULT_ROTATION_OVERLAP_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone)]
+pub struct IssueApiKeyCommand {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub rate_limit_per_second: u32,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<IssueApiKeyRequest> for IssueApiKeyCommand {
+    type Error = AppError;
+
+    fn try_from(value: IssueApiKeyRequest) -> Result<Self, Self::Error> {
+        let rate_limit_per_second = u32::try_from(value.rate_limit_per_second)
+            .ok()
+            .filter(|rate| *rate > 0)
+            .ok_or(DomainError::InvalidApiKeyRateLimit)?;
+
+        Ok(Self {
+            name: value.name,
+            scopes: value
+                .scopes
+                .iter()
+                .map(|s| ApiKeyScope::parse(s))
+                .collect::<Result<_, _>>()?,
+            rate_limit_per_second,
+            expires_at: value.expires_at,
+        })
+    }
+}
+
+/// A freshly issued key together with its plaintext, which is not stored
+pub struct IssuedApiKey {
+    pub api_key: ApiKey,
+    pub secret: ApiKeySecret,
+}
+
+fn new_api_key(client_id: Uuid, command: IssueApiKeyCommand) -> IssuedApiKey {
+    let secret = ApiKeySecret::generate();
+    let api_key = ApiKey {
+        id: Uuid::new_v4(),
+        client_id,
+        name: command.name,
+        key_prefix: secret.prefix(),
+        key_hash: hash_api_key(secret.as_str()),
+        scopes: command.scopes,
+        rate_limit_per_second: command.rate_limit_per_second,
+        created_at: Utc::now(),
+        expires_at: command.expires_at,
+        revoked_at: None,
+    };
+
+    IssuedApiKey { api_key, secret }
+}
+
+async fn find_api_key<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+) -> Result<ApiKey, AppError> {
+    repo.find_by_id(conn, id).await?.ok_or_else(|| {
+        RepoError::NotFound {
+            entity_type: "api_key",
+        }
+        .into()
+    })
+}
+
+pub async fn issue_api_key<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    command: IssueApiKeyCommand,
+) -> Result<IssuedApiKey, AppError> {
+    let issued = new_api_key(Uuid::new_v4(), command);
+    let api_key = repo.save(conn, &issued.api_key).await?;
+
+    Ok(IssuedApiKey {
+        api_key,
+        secret: issued.secret,
+    })
+}
+
+/// Issues a replacement key for the same client
+///
+/// The old key stays valid for `overlap` so the client can roll the new key
+/// out without downtime
+pub async fn rotate_api_key<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+    overlap: Duration,
+) -> Result<IssuedApiKey, AppError> {
+    let now = Utc::now();
+    let old = find_api_key(conn, repo, id).await?;
+
+    if !old.is_usable_at(now) {
+        return Err(ServiceError::InvalidStateTransition(
+            "cannot rotate a revoked or expired API key".to_string(),
+        )
+        .into());
+    }
+
+    let issued = new_api_key(
+        old.client_id,
+        IssueApiKeyCommand {
+            name: old.name.clone(),
+            scopes: old.scopes.clone(),
+            rate_limit_per_second: old.rate_limit_per_second,
+            expires_at: None,
+        },
+    );
+    let api_key = repo.save(conn, &issued.api_key).await?;
+
+    let overlap_end = now + overlap;
+    let old_expires_at = old
+        .expires_at
+        .map_or(overlap_end, |expires_at| expires_at.min(overlap_end));
+    repo.update_validity(conn, old.id, Some(old_expires_at), None)
+        .await?;
+
+    Ok(IssuedApiKey {
+        api_key,
+        secret: issued.secret,
+    })
+}
+
+pub async fn revoke_api_key<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+) -> Result<ApiKey, AppError> {
+    let api_key = find_api_key(conn, repo, id).await?;
+
+    if api_key.revoked_at.is_some() {
+        return Ok(api_key);
+    }
+
+    repo.update_validity(conn, id, api_key.expires_at, Some(Utc::now()))
+        .await
+        .map_err(Into::into)
+}
+
+pub async fn get_api_keys<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+) -> Result<Vec<ApiKey>, AppError> {
+    repo.get_all(conn).await.map_err(Into::into)
+}
+
+/// Resolves a presented key, rejecting unknown, revoked and expired keys
+pub async fn authenticate_api_key<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    presented: &str,
+) -> Result<ApiKey, AppError> {
+    let api_key = repo
+        .find_by_hash(conn, &hash_api_key(presented))
+        .await?
+        .filter(|api_key| api_key.is_usable_at(Utc::now()))
+        .ok_or(ServiceError::Unauthenticated)?;
+
+    Ok(api_key)
+}
+
+/// Lets a partner client act for a user, linking twice is not an error
+pub async fn link_partner_user<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    client_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    if !repo.client_exists(conn, client_id).await? {
+        return Err(RepoError::NotFound {
+            entity_type: "api_client",
+        }
+        .into());
+    }
+
+    repo.link_user(conn, client_id, user_id).await?;
+    Ok(())
+}
+
+pub async fn unlink_partner_user<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    client_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    if !repo.unlink_user(conn, client_id, user_id).await? {
+        return Err(RepoError::NotFound {
+            entity_type: "partner_user",
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Rejects a partner acting for a user it was not linked to
+///
+/// Unknown users are never linked, so they are refused the same way
+pub async fn ensure_partner_user<R: ApiKeyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    client_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    if !repo.is_user_linked(conn, client_id, user_id).await? {
+        return Err(ServiceError::Forbidden(
+            "API client is not linked to the user in X-On-Behalf-Of".to_string(),
+        )
+        .into());
+    }
+
+    Ok(())
+}
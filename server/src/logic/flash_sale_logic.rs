@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    adapters::http::dtos::flash_sale_dto::{
+        CreateFlashSaleRequest, FlashSaleAdmissionRequest, FlashSaleGuardsRequest,
+    },
+    domain::{
+        flash_sale::{AdmissionBudget, FlashSale, FlashSaleDetails, FlashSaleStatusFilter},
+        order_guard::OrderGuardSettings,
+    },
+    errors::{AppError, RepoError, ServiceError},
+    ports::{FlashSaleRepo, ProductRepo},
+};
+
+/// Most sales a public listing returns
+pub const FLASH_SALE_LISTING_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone)]
+pub struct CreateFlashSaleCommand {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub total_inventory: i32,
+    pub per_user_limit: i32,
+    pub admission: AdmissionBudget,
+    pub guards: OrderGuardSettings,
+}
+
+impl TryFrom<CreateFlashSaleRequest> for CreateFlashSaleCommand {
+    type Error = AppError;
+
+    fn try_from(value: CreateFlashSaleRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: Uuid::new_v4(),
+            product_id: value.product_id,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            total_inventory: value.total_inventory,
+            per_user_limit: value.per_user_limit,
+            admission: value.admission.map(Into::into).unwrap_or_default(),
+            guards: value.guards.map(Into::into).unwrap_or_default(),
+        })
+    }
+}
+
+impl From<FlashSaleAdmissionRequest> for AdmissionBudget {
+    fn from(value: FlashSaleAdmissionRequest) -> Self {
+        let defaults = AdmissionBudget::default();
+
+        Self {
+            queue_share: value.queue_share,
+            rate_per_second: value.rate_per_second,
+            scheduling_weight: value
+                .scheduling_weight
+                .unwrap_or(defaults.scheduling_weight),
+            challenge_required: value
+                .challenge_required
+                .unwrap_or(defaults.challenge_required),
+        }
+    }
+}
+
+impl From<FlashSaleGuardsRequest> for OrderGuardSettings {
+    fn from(value: FlashSaleGuardsRequest) -> Self {
+        let defaults = OrderGuardSettings::default();
+
+        Self {
+            blocklist: value.blocklist.unwrap_or(defaults.blocklist),
+            min_account_age_secs: value.min_account_age_secs,
+            velocity: value.velocity,
+            allowlist_only: value.allowlist_only.unwrap_or(defaults.allowlist_only),
+        }
+    }
+}
+
+/// Schedules a sale for a product that is still in the catalog
+///
+/// The product row is share-locked so it cannot be archived until the
+/// surrounding transaction commits
+pub async fn create_flash_sale<FR: FlashSaleRepo + ?Sized, PR: ProductRepo + ?Sized>(
+    conn: &mut PgConnection,
+    flash_sale_repo: &FR,
+    product_repo: &PR,
+    command: CreateFlashSaleCommand,
+) -> Result<FlashSaleDetails, AppError> {
+    let flash_sale = FlashSale::try_from(command)?;
+
+    let product = product_repo
+        .find_by_id_with_lock(conn, flash_sale.product_id)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "product",
+        }))?;
+
+    if product.is_archived() {
+        return Err(ServiceError::BusinessRule(
+            "cannot create a flash sale for an archived product".to_string(),
+        )
+        .into());
+    }
+
+    let flash_sale = flash_sale_repo.save(conn, &flash_sale).await?;
+
+    Ok(FlashSaleDetails {
+        flash_sale,
+        product,
+    })
+}
+
+pub async fn get_flash_sale_details<R: FlashSaleRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+) -> Result<FlashSaleDetails, AppError> {
+    repo.find_details_by_id(conn, id)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "flash_sale",
+        }))
+}
+
+pub async fn list_flash_sales<R: FlashSaleRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    status: FlashSaleStatusFilter,
+    now: DateTime<Utc>,
+) -> Result<Vec<FlashSaleDetails>, AppError> {
+    let sales = match status {
+        FlashSaleStatusFilter::Live => repo.list_live(conn, now, FLASH_SALE_LISTING_LIMIT).await?,
+        FlashSaleStatusFilter::Upcoming => {
+            repo.list_upcoming(conn, now, FLASH_SALE_LISTING_LIMIT)
+                .await?
+        }
+    };
+
+    Ok(sales)
+}
+
+/// Replaces a sale's admission budget, taking effect for the next order
+pub async fn update_flash_sale_admission<R: FlashSaleRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+    admission: AdmissionBudget,
+) -> Result<FlashSale, AppError> {
+    admission.validate()?;
+
+    repo.update_admission(conn, id, &admission)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "flash_sale",
+        }))
+}
+
+/// Replaces a sale's order guards, taking effect for the next order
+pub async fn update_flash_sale_guards<R: FlashSaleRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+    guards: OrderGuardSettings,
+) -> Result<FlashSale, AppError> {
+    guards.validate()?;
+
+    repo.update_guards(conn, id, &guards)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "flash_sale",
+        }))
+}
@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{errors::AppError, ports::WorkerHeartbeatRepo};
+
+pub async fn record_worker_heartbeat<R: WorkerHeartbeatRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    worker_id: Uuid,
+    started_at: DateTime<Utc>,
+    seen_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    repo.beat(conn, worker_id, started_at, seen_at)
+        .await
+        .map_err(AppError::from)
+}
+
+/// When any worker process was last seen alive, `None` if none ever was
+pub async fn latest_worker_heartbeat<R: WorkerHeartbeatRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    repo.latest(conn).await.map_err(AppError::from)
+}
+
+pub async fn remove_worker_heartbeat<R: WorkerHeartbeatRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    worker_id: Uuid,
+) -> Result<(), AppError> {
+    repo.remove(conn, worker_id).await.map_err(AppError::from)
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgConnection;
+
+use crate::{
+    domain::idempotency::{IdempotencyRecord, IdempotentRequest, StoredResponse},
+    errors::{AppError, ServiceError},
+    ports::IdempotencyRepo,
+};
+
+#[derive(Debug, Clone)]
+pub struct BeginIdempotentRequestCommand {
+    pub scope: String,
+    pub idempotency_key: String,
+    pub request_fingerprint: String,
+    /// How long the key stays claimed if the request never finishes
+    pub lease: Duration,
+}
+
+/// Claims an idempotency key for a request
+///
+/// Returns the claim when the request is new and should be processed, or the
+/// stored response when it is a replay of a finished request
+pub async fn begin_idempotent_request<R: IdempotencyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    command: BeginIdempotentRequestCommand,
+) -> Result<IdempotentRequest, AppError> {
+    let record = IdempotencyRecord::in_progress(
+        command.scope,
+        command.idempotency_key,
+        command.request_fingerprint,
+        Utc::now() + command.lease,
+    );
+
+    if let Some(claimed_at) = repo.insert_in_progress(conn, &record).await? {
+        return Ok(IdempotentRequest::Claimed(claimed_at));
+    }
+
+    // The record may have been released between the insert and this read;
+    // the client sees it as in progress and retries
+    let existing = repo
+        .find(conn, &record.scope, &record.idempotency_key)
+        .await?
+        .ok_or(ServiceError::IdempotencyRequestInProgress)?;
+
+    if existing.request_fingerprint != record.request_fingerprint {
+        return Err(ServiceError::IdempotencyKeyReused.into());
+    }
+
+    existing
+        .response
+        .map(IdempotentRequest::Replay)
+        .ok_or_else(|| ServiceError::IdempotencyRequestInProgress.into())
+}
+
+/// Stores the response of the claim made at `claimed_at`, which is then
+/// replayed for `ttl`
+///
+/// Fails with [`ServiceError::IdempotencyClaimLost`] when another request
+/// took the key over after the claim's lease ended
+pub async fn complete_idempotent_request<R: IdempotencyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    scope: &str,
+    key: &str,
+    claimed_at: DateTime<Utc>,
+    response: &StoredResponse,
+    ttl: Duration,
+) -> Result<(), AppError> {
+    if repo
+        .complete(conn, scope, key, claimed_at, response, Utc::now() + ttl)
+        .await?
+    {
+        Ok(())
+    } else {
+        Err(ServiceError::IdempotencyClaimLost.into())
+    }
+}
+
+/// Releases the key so the client can retry, used when the request failed
+/// in a way that should not be replayed or never finished
+///
+/// A key whose response was already stored, or that another request took
+/// over, is kept
+pub async fn abandon_idempotent_request<R: IdempotencyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    scope: &str,
+    key: &str,
+    claimed_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    repo.delete(conn, scope, key, claimed_at)
+        .await
+        .map_err(Into::into)
+}
+
+pub async fn purge_expired_idempotency_records<R: IdempotencyRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+) -> Result<u64, AppError> {
+    repo.delete_expired(conn).await.map_err(Into::into)
+}
@@ -1,5 +1,18 @@
+pub mod api_key_logic;
+pub mod flash_sale_logic;
+pub mod health_logic;
+pub mod idempotency_logic;
+pub mod order_challenge_logic;
+pub mod order_guard_logic;
+pub mod order_job_logic;
 pub mod order_logic;
 pub mod product_logic;
+pub mod rate_limit_logic;
+pub mod settings_logic;
 pub mod user_logic;
 
-pub use crate::logic::{order_logic::*, product_logic::*, user_logic::*};
+pub use crate::logic::{
+    api_key_logic::*, flash_sale_logic::*, health_logic::*, idempotency_logic::*,
+    order_challenge_logic::*, order_guard_logic::*, order_job_logic::*, order_logic::*,
+    product_logic::*, rate_limit_logic::*, settings_logic::*, user_logic::*,
+};
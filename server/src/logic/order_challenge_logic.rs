@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Connection, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    domain::order_challenge::{ChallengeDifficulty, DifficultyPolicy},
+    errors::{AppError, RepoError},
+    ports::OrderChallengeRepo,
+};
+
+/// Spends a solved challenge on `order_id`, returns false when another order
+/// already spent it
+pub async fn use_challenge<R: OrderChallengeRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    nonce: &str,
+    order_id: Uuid,
+    expires_at: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    repo.use_nonce(conn, nonce, order_id, expires_at)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Adds this instance's `overflows` to the shared difficulty and moves it
+/// once its window is up
+///
+/// Runs in its own transaction holding the difficulty row, so instances
+/// reporting at once are counted one after the other. Returns the difficulty
+/// challenges are now issued at, and the overflow rate when it was reassessed.
+pub async fn sync_challenge_difficulty<R: OrderChallengeRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    overflows: u64,
+    policy: &DifficultyPolicy,
+) -> Result<(ChallengeDifficulty, Option<f64>), AppError> {
+    let mut tx = conn
+        .begin()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    let mut difficulty = repo.lock_difficulty(&mut tx, policy.base).await?;
+    difficulty.overflows = difficulty.overflows.saturating_add(overflows);
+    let rate = difficulty.advance(Utc::now(), policy);
+    repo.save_difficulty(&mut tx, &difficulty).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    Ok((difficulty, rate))
+}
+
+/// Removes used challenges that have expired, returns how many
+pub async fn purge_expired_challenges<R: OrderChallengeRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+) -> Result<u64, AppError> {
+    repo.delete_expired(conn).await.map_err(AppError::from)
+}
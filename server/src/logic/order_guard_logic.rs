@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    domain::order_guard::BlockedUser,
+    errors::{AppError, RepoError},
+    ports::{OrderGuardRepo, OrderRepo},
+};
+
+/// Stops the user from ordering in sales that apply the blocklist
+pub async fn block_user<R: OrderGuardRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    user_id: Uuid,
+    reason: Option<&str>,
+) -> Result<BlockedUser, AppError> {
+    repo.block_user(conn, user_id, reason)
+        .await
+        .map_err(AppError::from)
+}
+
+pub async fn unblock_user<R: OrderGuardRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    if !repo.unblock_user(conn, user_id).await? {
+        return Err(AppError::Repo(RepoError::NotFound {
+            entity_type: "blocked_user",
+        }));
+    }
+
+    Ok(())
+}
+
+pub async fn is_user_blocked<R: OrderGuardRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    user_id: Uuid,
+) -> Result<bool, AppError> {
+    repo.is_blocked(conn, user_id).await.map_err(AppError::from)
+}
+
+/// Invites users to a sale, returns how many were not invited yet
+pub async fn allow_flash_sale_users<R: OrderGuardRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    flash_sale_id: Uuid,
+    user_ids: &[Uuid],
+) -> Result<u64, AppError> {
+    repo.allow_users(conn, flash_sale_id, user_ids)
+        .await
+        .map_err(AppError::from)
+}
+
+pub async fn disallow_flash_sale_user<R: OrderGuardRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    flash_sale_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    if !repo.disallow_user(conn, flash_sale_id, user_id).await? {
+        return Err(AppError::Repo(RepoError::NotFound {
+            entity_type: "allowlist_entry",
+        }));
+    }
+
+    Ok(())
+}
+
+pub async fn is_user_allowed<R: OrderGuardRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    flash_sale_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, AppError> {
+    repo.is_allowed(conn, flash_sale_id, user_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Orders the user placed since `since` in any sale, failed ones excluded
+pub async fn count_recent_user_orders<R: OrderRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<i64, AppError> {
+    repo.count_by_user_since(conn, user_id, since)
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,87 @@
+use sqlx::{Connection, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        order::Order,
+        order_job::{OrderJob, OrderJobErrorCode, OrderJobTurn},
+    },
+    errors::{AppError, RepoError},
+    logic::order_logic::{CreateOrderCommand, create_order},
+    ports::{FlashSaleRepo, OrderJobRepo, OrderRepo},
+};
+
+/// Outcome of one job run by a worker
+pub struct ProcessedOrderJob {
+    pub order_id: Uuid,
+    pub result: Result<Order, AppError>,
+}
+
+pub async fn enqueue_order_job<R: OrderJobRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    command: &CreateOrderCommand,
+    turn: OrderJobTurn,
+) -> Result<(), AppError> {
+    repo.enqueue(conn, command, turn)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Claims the next pending job in fair order and places its order
+///
+/// Must run in a transaction: the job stays locked until it commits, and the
+/// order and the job outcome commit together. A failed order is rolled back
+/// to a savepoint so the failure can still be recorded. Returns `None` when
+/// no job is waiting.
+pub async fn process_next_order_job<
+    JR: OrderJobRepo + ?Sized,
+    FR: FlashSaleRepo + ?Sized,
+    OR: OrderRepo + ?Sized,
+>(
+    conn: &mut PgConnection,
+    job_repo: &JR,
+    flash_sale_repo: &FR,
+    order_repo: &OR,
+) -> Result<Option<ProcessedOrderJob>, AppError> {
+    let Some(job) = job_repo.claim_next(conn).await? else {
+        return Ok(None);
+    };
+    let order_id = job.command.order_id;
+
+    let mut savepoint = conn
+        .begin()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
+    let result = create_order(&mut savepoint, flash_sale_repo, order_repo, job.command).await;
+
+    match &result {
+        Ok(_) => {
+            savepoint
+                .commit()
+                .await
+                .map_err(|e| RepoError::Transaction(e.to_string()))?;
+            job_repo.complete(conn, order_id).await?;
+        }
+        Err(e) => {
+            savepoint
+                .rollback()
+                .await
+                .map_err(|e| RepoError::Transaction(e.to_string()))?;
+            job_repo
+                .fail(conn, order_id, &e.to_string(), OrderJobErrorCode::of(e))
+                .await?;
+        }
+    }
+
+    Ok(Some(ProcessedOrderJob { order_id, result }))
+}
+
+pub async fn get_finished_order_jobs<R: OrderJobRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    ids: &[Uuid],
+) -> Result<Vec<OrderJob>, AppError> {
+    repo.find_finished(conn, ids).await.map_err(AppError::from)
+}
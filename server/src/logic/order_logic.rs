@@ -1,8 +1,12 @@
-use sqlx::PgConnection;
+use chrono::Utc;
+use sqlx::{Connection, PgConnection};
 use uuid::Uuid;
 
 use crate::{
-    domain::order::Order,
+    domain::{
+        order::{Order, OrderFilter},
+        pagination::{Page, PageRequest},
+    },
     errors::{AppError, RepoError, ServiceError},
     ports::{FlashSaleRepo, OrderRepo},
 };
@@ -14,6 +18,15 @@ pub struct CreateOrderCommand {
     pub flash_sale_id: Uuid,
     pub quantity: i32,
     pub idempotency_key: String,
+    pub request_fingerprint: String,
+    /// Partner client placing the order on the user's behalf
+    pub api_client_id: Option<Uuid>,
+}
+
+impl CreateOrderCommand {
+    pub fn idempotency_owner(&self) -> Uuid {
+        Order::idempotency_owner(self.user_id, self.api_client_id)
+    }
 }
 
 pub async fn create_order<FR: FlashSaleRepo + ?Sized, OR: OrderRepo + ?Sized>(
@@ -24,10 +37,12 @@ pub async fn create_order<FR: FlashSaleRepo + ?Sized, OR: OrderRepo + ?Sized>(
 ) -> Result<Order, AppError> {
     // 1. Check for existing order with same idempotency key (Idempotent Response)
     if let Some(existing_order) = order_repo
-        .find_by_idempotency_key(conn, &command.idempotency_key)
+        .find_by_idempotency_key(conn, command.idempotency_owner(), &command.idempotency_key)
         .await
         .map_err(AppError::from)?
     {
+        ensure_same_request(&existing_order, &command)?;
+
         tracing::debug!(
             "Idempotent request detected: returning existing order {}",
             existing_order.id
@@ -45,6 +60,9 @@ pub async fn create_order<FR: FlashSaleRepo + ?Sized, OR: OrderRepo + ?Sized>(
         })?;
 
     // 3. Check Inventory
+    if flash_sale.remaining_inventory == 0 {
+        return Err(ServiceError::SoldOut.into());
+    }
     if flash_sale.remaining_inventory < command.quantity {
         return Err(ServiceError::Conflict("sold out".to_string()).into());
     }
@@ -54,27 +72,66 @@ pub async fn create_order<FR: FlashSaleRepo + ?Sized, OR: OrderRepo + ?Sized>(
         return Err(ServiceError::BusinessRule("flash sale is not active".to_string()).into());
     }
 
-    // 5. Decrement Inventory
+    // 5. Check the sale's velocity limit again
+    // The guard only counted orders placed when this one was admitted, orders
+    // queued alongside it may have been placed since. The lock keeps the
+    // user's concurrent orders in other sales from counting past each other
+    if let Some(limit) = flash_sale.guards.velocity {
+        order_repo
+            .lock_user_orders(conn, command.user_id)
+            .await
+            .map_err(AppError::from)?;
+        let recent = order_repo
+            .count_by_user_since(conn, command.user_id, limit.window_start(Utc::now()))
+            .await
+            .map_err(AppError::from)?;
+        if let Some(rejection) = limit.check(recent) {
+            return Err(ServiceError::OrderRejected(rejection).into());
+        }
+    }
+
+    // 6. Decrement Inventory
+    // Runs inside a savepoint so that losing the idempotency race below undoes
+    // the decrement and leaves the outer transaction usable for the re-query
+    let mut savepoint = conn
+        .begin()
+        .await
+        .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
     let mut updated_flash_sale = flash_sale.clone();
     updated_flash_sale.remaining_inventory -= command.quantity;
 
     flash_sale_repo
-        .update(conn, &updated_flash_sale)
+        .update(&mut savepoint, &updated_flash_sale)
         .await
         .map_err(AppError::from)?;
 
-    // 6. Create Order with idempotency key
+    // 7. Create Order with idempotency key
     let order = Order::new(
+        command.order_id,
         command.user_id,
         command.flash_sale_id,
         command.quantity,
         command.idempotency_key.clone(),
+        command.request_fingerprint.clone(),
+        command.api_client_id,
     );
 
-    // 7. Save order (handle race condition on unique constraint)
-    let saved_order = match order_repo.save(conn, &order).await {
-        Ok(order) => order,
+    // 8. Save order (handle race condition on unique constraint)
+    let saved_order = match order_repo.save(&mut savepoint, &order).await {
+        Ok(order) => {
+            savepoint
+                .commit()
+                .await
+                .map_err(|e| RepoError::Transaction(e.to_string()))?;
+            order
+        }
         Err(RepoError::Conflict { .. }) => {
+            savepoint
+                .rollback()
+                .await
+                .map_err(|e| RepoError::Transaction(e.to_string()))?;
+
             // Race condition: another request with same idempotency key succeeded
             // Re-query to get the existing order
             tracing::warn!(
@@ -82,16 +139,61 @@ pub async fn create_order<FR: FlashSaleRepo + ?Sized, OR: OrderRepo + ?Sized>(
                 command.idempotency_key
             );
 
-            order_repo
-                .find_by_idempotency_key(conn, &command.idempotency_key)
+            let existing_order = order_repo
+                .find_by_idempotency_key(
+                    conn,
+                    command.idempotency_owner(),
+                    &command.idempotency_key,
+                )
                 .await
                 .map_err(AppError::from)?
                 .ok_or_else(|| RepoError::NotFound {
                     entity_type: "Order",
-                })?
+                })?;
+
+            ensure_same_request(&existing_order, &command)?;
+            existing_order
         }
         Err(e) => return Err(AppError::from(e)),
     };
 
     Ok(saved_order)
 }
+
+pub async fn get_order<R: OrderRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+) -> Result<Order, AppError> {
+    repo.find_by_id(conn, id)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "order",
+        }))
+}
+
+pub async fn get_user_orders<R: OrderRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    user_id: Uuid,
+    filter: OrderFilter,
+    page: PageRequest,
+) -> Result<Page<Order>, AppError> {
+    repo.list_by_user(conn, user_id, &filter, &page)
+        .await
+        .map_err(Into::into)
+}
+
+/// Rejects a replayed idempotency key whose request parameters differ from
+/// the ones the key was first used with
+fn ensure_same_request(existing: &Order, command: &CreateOrderCommand) -> Result<(), AppError> {
+    if existing.request_fingerprint != command.request_fingerprint {
+        tracing::warn!(
+            idempotency_key = %command.idempotency_key,
+            "Idempotency key reused with different request parameters"
+        );
+        return Err(ServiceError::IdempotencyKeyReused.into());
+    }
+
+    Ok(())
+}
@@ -2,14 +2,25 @@ use sqlx::PgConnection;
 use uuid::Uuid;
 
 use crate::{
-    adapters::http::dtos::product_dto::CreateProductRequest, domain::product::Product,
-    errors::AppError, ports::ProductRepo,
+    adapters::http::dtos::product_dto::{CreateProductRequest, UpdateProductRequest},
+    domain::{
+        pagination::{MAX_PAGE_LIMIT, Page, PageRequest},
+        product::{ImageUrl, Product, ProductDescription, ProductFilter, ProductName, Sku},
+    },
+    errors::{AppError, DomainError, RepoError},
+    ports::ProductRepo,
 };
 
+/// Search results returned when the client does not ask for a number
+pub const DEFAULT_SEARCH_LIMIT: u32 = 20;
+
 #[derive(Debug, Clone)]
 pub struct CreateProductCommand {
     pub id: Uuid,
     pub name: String,
+    pub description: Option<String>,
+    pub sku: Option<String>,
+    pub image_urls: Vec<String>,
 }
 
 impl TryFrom<CreateProductRequest> for CreateProductCommand {
@@ -19,6 +30,46 @@ impl TryFrom<CreateProductRequest> for CreateProductCommand {
         Ok(Self {
             id: Uuid::new_v4(),
             name: value.name,
+            description: value.description,
+            sku: value.sku,
+            image_urls: value.image_urls,
+        })
+    }
+}
+
+/// Catalog details to change, `None` leaves the field as it is
+#[derive(Debug, Clone)]
+pub struct UpdateProductCommand {
+    pub name: Option<ProductName>,
+    /// `Some(None)` clears the description
+    pub description: Option<Option<ProductDescription>>,
+    /// `Some(None)` clears the SKU
+    pub sku: Option<Option<Sku>>,
+    pub image_urls: Option<Vec<ImageUrl>>,
+}
+
+impl TryFrom<UpdateProductRequest> for UpdateProductCommand {
+    type Error = AppError;
+
+    fn try_from(value: UpdateProductRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name.map(ProductName::new).transpose()?,
+            description: value
+                .description
+                .map(|d| {
+                    (!d.is_empty())
+                        .then(|| ProductDescription::new(d))
+                        .transpose()
+                })
+                .transpose()?,
+            sku: value
+                .sku
+                .map(|s| (!s.is_empty()).then(|| Sku::new(s)).transpose())
+                .transpose()?,
+            image_urls: value
+                .image_urls
+                .map(|urls| urls.into_iter().map(ImageUrl::new).collect())
+                .transpose()?,
         })
     }
 }
@@ -35,6 +86,85 @@ pub async fn save_product<R: ProductRepo + ?Sized>(
 pub async fn get_products<R: ProductRepo + ?Sized>(
     conn: &mut PgConnection,
     repo: &R,
+    filter: ProductFilter,
+    page: PageRequest,
+) -> Result<Page<Product>, AppError> {
+    repo.list(conn, &filter, &page).await.map_err(Into::into)
+}
+
+pub async fn get_product<R: ProductRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+) -> Result<Product, AppError> {
+    repo.find_by_id(conn, id)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "product",
+        }))
+}
+
+/// Applies the given fields on top of the current product
+///
+/// The row stays locked until the caller's transaction ends, so concurrent
+/// edits of different fields don't undo each other
+pub async fn update_product<R: ProductRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+    command: UpdateProductCommand,
+) -> Result<Product, AppError> {
+    let mut product = repo
+        .find_by_id_for_update(conn, id)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "product",
+        }))?;
+
+    if let Some(name) = command.name {
+        product.name = name;
+    }
+    if let Some(description) = command.description {
+        product.description = description;
+    }
+    if let Some(sku) = command.sku {
+        product.sku = sku;
+    }
+    if let Some(image_urls) = command.image_urls {
+        product.image_urls = image_urls;
+    }
+
+    repo.update(conn, product).await.map_err(Into::into)
+}
+
+/// Withdraws the product from listings and search; archiving twice is a no-op
+pub async fn archive_product<R: ProductRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+) -> Result<Product, AppError> {
+    repo.archive(conn, id).await.map_err(Into::into)
+}
+
+pub async fn search_products<R: ProductRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    query: &str,
+    limit: Option<u32>,
 ) -> Result<Vec<Product>, AppError> {
-    repo.get_all(conn).await.map_err(Into::into)
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+        return Err(AppError::Domain(DomainError::InvalidPageLimit {
+            max: MAX_PAGE_LIMIT,
+        }));
+    }
+
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    repo.search(conn, query, i64::from(limit))
+        .await
+        .map_err(Into::into)
 }
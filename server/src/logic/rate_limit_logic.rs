@@ -0,0 +1,30 @@
+use sqlx::PgConnection;
+
+use crate::{
+    domain::rate_limit::{RateLimitDecision, RateQuota},
+    errors::AppError,
+    ports::RateLimitRepo,
+};
+
+/// Counts a request of `key` against `quota` in the shared bucket
+pub async fn acquire_rate_limit<R: RateLimitRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    key: &str,
+    quota: RateQuota,
+) -> Result<RateLimitDecision, AppError> {
+    let interval = quota.emission_interval();
+    let bucket = repo
+        .acquire(conn, key, interval, interval * quota.burst)
+        .await?;
+
+    Ok(RateLimitDecision::from_gcra(quota, bucket))
+}
+
+/// Removes buckets that have refilled, returns how many
+pub async fn purge_expired_rate_limits<R: RateLimitRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+) -> Result<u64, AppError> {
+    repo.delete_expired(conn).await.map_err(AppError::from)
+}
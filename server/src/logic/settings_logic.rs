@@ -0,0 +1,55 @@
+use sqlx::PgConnection;
+
+use crate::{
+    adapters::http::dtos::settings_dto::UpdateRuntimeSettingsRequest,
+    domain::runtime_settings::{RuntimeSettingsPatch, SettingsChange, SoldOutGate},
+    errors::{AppError, DomainError},
+    ports::SettingsAuditRepo,
+};
+
+pub const DEFAULT_SETTINGS_AUDIT_LIMIT: i64 = 50;
+pub const MAX_SETTINGS_AUDIT_LIMIT: i64 = 200;
+
+impl TryFrom<UpdateRuntimeSettingsRequest> for RuntimeSettingsPatch {
+    type Error = AppError;
+
+    fn try_from(value: UpdateRuntimeSettingsRequest) -> Result<Self, Self::Error> {
+        let sold_out_gate = value
+            .sold_out_gate
+            .map(|gate| {
+                SoldOutGate::parse(&gate).ok_or_else(|| {
+                    DomainError::InvalidRuntimeSetting(format!(
+                        "sold_out_gate must be reject or off, got {gate}"
+                    ))
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            rate_limit_per_user: value.rate_limit_per_user,
+            queue_admission_limit: value.queue_admission_limit,
+            log_level: value.log_level,
+            sold_out_gate,
+        })
+    }
+}
+
+pub async fn record_settings_change<R: SettingsAuditRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    change: &SettingsChange,
+) -> Result<(), AppError> {
+    repo.record(conn, change).await.map_err(AppError::from)
+}
+
+pub async fn get_settings_changes<R: SettingsAuditRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    limit: Option<i64>,
+) -> Result<Vec<SettingsChange>, AppError> {
+    let limit = limit
+        .unwrap_or(DEFAULT_SETTINGS_AUDIT_LIMIT)
+        .clamp(1, MAX_SETTINGS_AUDIT_LIMIT);
+
+    repo.list_recent(conn, limit).await.map_err(AppError::from)
+}
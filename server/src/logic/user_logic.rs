@@ -1,21 +1,69 @@
 use sqlx::PgConnection;
 use uuid::Uuid;
 
-use crate::{domain::user::User, errors::AppError, ports::UserRepo};
+use crate::{
+    adapters::http::dtos::user_dto::{CreateUserRequest, UpdateUserRequest},
+    domain::{
+        pagination::{Page, PageRequest},
+        user::{Email, User, UserFilter, Username},
+    },
+    errors::{AppError, RepoError},
+    ports::UserRepo,
+};
+
+#[derive(Debug, Clone)]
+pub struct CreateUserCommand {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+}
+
+impl TryFrom<CreateUserRequest> for CreateUserCommand {
+    type Error = AppError;
+
+    fn try_from(value: CreateUserRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: Uuid::new_v4(),
+            email: value.email,
+            username: value.username,
+        })
+    }
+}
+
+/// Profile fields to change, `None` leaves the field as it is
+#[derive(Debug, Clone)]
+pub struct UpdateUserCommand {
+    pub email: Option<Email>,
+    pub username: Option<Username>,
+}
+
+impl TryFrom<UpdateUserRequest> for UpdateUserCommand {
+    type Error = AppError;
+
+    fn try_from(value: UpdateUserRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            email: value.email.map(Email::new).transpose()?,
+            username: value.username.map(Username::new).transpose()?,
+        })
+    }
+}
 
 pub async fn save_user<R: UserRepo + ?Sized>(
     conn: &mut PgConnection,
     repo: &R,
-    user: User,
+    command: CreateUserCommand,
 ) -> Result<User, AppError> {
+    let user = User::try_from(command)?;
     repo.save(conn, user).await.map_err(Into::into)
 }
 
 pub async fn get_users<R: UserRepo + ?Sized>(
     conn: &mut PgConnection,
     repo: &R,
-) -> Result<Vec<User>, AppError> {
-    repo.get_all(conn).await.map_err(Into::into)
+    filter: UserFilter,
+    page: PageRequest,
+) -> Result<Page<User>, AppError> {
+    repo.list(conn, &filter, &page).await.map_err(Into::into)
 }
 
 pub async fn get_user_by_id<R: UserRepo + ?Sized>(
@@ -25,3 +73,40 @@ pub async fn get_user_by_id<R: UserRepo + ?Sized>(
 ) -> Result<User, AppError> {
     repo.get_by_id(conn, id).await.map_err(Into::into)
 }
+
+pub async fn get_user_by_email<R: UserRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    email: String,
+) -> Result<User, AppError> {
+    let email = Email::new(email)?;
+
+    repo.find_by_email(conn, &email)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "user",
+        }))
+}
+
+pub async fn update_user<R: UserRepo + ?Sized>(
+    conn: &mut PgConnection,
+    repo: &R,
+    id: Uuid,
+    command: UpdateUserCommand,
+) -> Result<User, AppError> {
+    let mut user = repo
+        .find_by_id_for_update(conn, id)
+        .await?
+        .ok_or(AppError::Repo(RepoError::NotFound {
+            entity_type: "user",
+        }))?;
+
+    if let Some(email) = command.email {
+        user.email = Some(email);
+    }
+    if let Some(username) = command.username {
+        user.username = Some(username);
+    }
+
+    repo.update(conn, user).await.map_err(Into::into)
+}
@@ -1,6 +1,7 @@
-use flash_sale::app::runtime::run;
+use clap::Parser;
+use flash_sale::app::cli::Cli;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    run().await
+    Cli::parse().run().await
 }
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{domain::api_key::ApiKey, errors::RepoError};
+
+#[async_trait]
+pub trait ApiKeyRepo: Send + Sync {
+    async fn save(&self, conn: &mut PgConnection, api_key: &ApiKey) -> Result<ApiKey, RepoError>;
+    async fn find_by_id(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<ApiKey>, RepoError>;
+    async fn find_by_hash(
+        &self,
+        conn: &mut PgConnection,
+        key_hash: &str,
+    ) -> Result<Option<ApiKey>, RepoError>;
+    async fn get_all(&self, conn: &mut PgConnection) -> Result<Vec<ApiKey>, RepoError>;
+    async fn update_validity(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+        revoked_at: Option<DateTime<Utc>>,
+    ) -> Result<ApiKey, RepoError>;
+    /// Whether any key, usable or not, was issued to the client
+    async fn client_exists(
+        &self,
+        conn: &mut PgConnection,
+        client_id: Uuid,
+    ) -> Result<bool, RepoError>;
+    /// Lets the client act for the user, false if it already could
+    async fn link_user(
+        &self,
+        conn: &mut PgConnection,
+        client_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError>;
+    /// False when the user was not linked
+    async fn unlink_user(
+        &self,
+        conn: &mut PgConnection,
+        client_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError>;
+    async fn is_user_linked(
+        &self,
+        conn: &mut PgConnection,
+        client_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError>;
+}
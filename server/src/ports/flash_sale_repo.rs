@@ -1,18 +1,64 @@
-use crate::{domain::flash_sale::FlashSale, errors::RepoError};
+use crate::{
+    domain::{
+        flash_sale::{AdmissionBudget, FlashSale, FlashSaleDetails},
+        order_guard::OrderGuardSettings,
+    },
+    errors::RepoError,
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::PgConnection;
 use uuid::Uuid;
 
 #[async_trait]
 pub trait FlashSaleRepo: Send + Sync {
+    async fn save(
+        &self,
+        conn: &mut PgConnection,
+        flash_sale: &FlashSale,
+    ) -> Result<FlashSale, RepoError>;
     async fn find_by_id_with_lock(
         &self,
         conn: &mut PgConnection,
         id: Uuid,
     ) -> Result<Option<FlashSale>, RepoError>;
+    /// Plain read without a row lock, for public display
+    async fn find_details_by_id(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<FlashSaleDetails>, RepoError>;
+    /// Sales running at `now`, ending soonest first
+    async fn list_live(
+        &self,
+        conn: &mut PgConnection,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<FlashSaleDetails>, RepoError>;
+    /// Sales starting after `now`, starting soonest first
+    async fn list_upcoming(
+        &self,
+        conn: &mut PgConnection,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<FlashSaleDetails>, RepoError>;
     async fn update(
         &self,
         conn: &mut PgConnection,
         flash_sale: &FlashSale,
     ) -> Result<FlashSale, RepoError>;
+    /// Replaces the sale's admission budget, `None` if there is no such sale
+    async fn update_admission(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        admission: &AdmissionBudget,
+    ) -> Result<Option<FlashSale>, RepoError>;
+    /// Replaces the sale's order guard settings, `None` if there is no such sale
+    async fn update_guards(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        guards: &OrderGuardSettings,
+    ) -> Result<Option<FlashSale>, RepoError>;
 }
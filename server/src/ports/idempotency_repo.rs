@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+
+use crate::{
+    domain::idempotency::{IdempotencyRecord, StoredResponse},
+    errors::RepoError,
+};
+
+#[async_trait]
+pub trait IdempotencyRepo: Send + Sync {
+    /// Claims the key for a new request
+    ///
+    /// Returns when the claim was made, or `None` when an unexpired record
+    /// already holds the key
+    async fn insert_in_progress(
+        &self,
+        conn: &mut PgConnection,
+        record: &IdempotencyRecord,
+    ) -> Result<Option<DateTime<Utc>>, RepoError>;
+    async fn find(
+        &self,
+        conn: &mut PgConnection,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<IdempotencyRecord>, RepoError>;
+    /// Stores the response of the claim made at `claimed_at`
+    ///
+    /// Returns false when the claim was lost, its lease ended and another
+    /// request took the key over
+    async fn complete(
+        &self,
+        conn: &mut PgConnection,
+        scope: &str,
+        key: &str,
+        claimed_at: DateTime<Utc>,
+        response: &StoredResponse,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, RepoError>;
+    /// Removes the record if the claim made at `claimed_at` still holds it
+    async fn delete(
+        &self,
+        conn: &mut PgConnection,
+        scope: &str,
+        key: &str,
+        claimed_at: DateTime<Utc>,
+    ) -> Result<(), RepoError>;
+    async fn delete_expired(&self, conn: &mut PgConnection) -> Result<u64, RepoError>;
+}
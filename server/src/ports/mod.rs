@@ -1,9 +1,27 @@
+pub mod api_key_repo;
 pub mod flash_sale_repo;
+pub mod idempotency_repo;
+pub mod order_challenge_repo;
+pub mod order_guard_repo;
+pub mod order_job_repo;
 pub mod order_repo;
 pub mod product_repo;
+pub mod rate_limit_repo;
+pub mod settings_audit_repo;
+pub mod token_verifier;
 pub mod user_repo;
+pub mod worker_heartbeat_repo;
 
+pub use api_key_repo::ApiKeyRepo;
 pub use flash_sale_repo::FlashSaleRepo;
+pub use idempotency_repo::IdempotencyRepo;
+pub use order_challenge_repo::OrderChallengeRepo;
+pub use order_guard_repo::OrderGuardRepo;
+pub use order_job_repo::OrderJobRepo;
 pub use order_repo::OrderRepo;
 pub use product_repo::ProductRepo;
+pub use rate_limit_repo::RateLimitRepo;
+pub use settings_audit_repo::SettingsAuditRepo;
+pub use token_verifier::TokenVerifier;
 pub use user_repo::UserRepo;
+pub use worker_heartbeat_repo::WorkerHeartbeatRepo;
@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{domain::order_challenge::ChallengeDifficulty, errors::RepoError};
+
+#[async_trait]
+pub trait OrderChallengeRepo: Send + Sync {
+    /// Records the challenge `nonce` as used by `order_id`
+    ///
+    /// Returns false when another order already used it; the same order may
+    /// present it again.
+    async fn use_nonce(
+        &self,
+        conn: &mut PgConnection,
+        nonce: &str,
+        order_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, RepoError>;
+    /// Drops used challenges that have expired, returns how many
+    async fn delete_expired(&self, conn: &mut PgConnection) -> Result<u64, RepoError>;
+    /// Locks the shared difficulty for the rest of the transaction, creating
+    /// it at `base_difficulty` if there is none yet
+    async fn lock_difficulty(
+        &self,
+        conn: &mut PgConnection,
+        base_difficulty: u32,
+    ) -> Result<ChallengeDifficulty, RepoError>;
+    async fn save_difficulty(
+        &self,
+        conn: &mut PgConnection,
+        difficulty: &ChallengeDifficulty,
+    ) -> Result<(), RepoError>;
+}
@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{domain::order_guard::BlockedUser, errors::RepoError};
+
+#[async_trait]
+pub trait OrderGuardRepo: Send + Sync {
+    /// Adds the user to the blocklist, or updates the reason if already there
+    async fn block_user(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<BlockedUser, RepoError>;
+    /// Returns whether the user was on the blocklist
+    async fn unblock_user(&self, conn: &mut PgConnection, user_id: Uuid)
+    -> Result<bool, RepoError>;
+    async fn is_blocked(&self, conn: &mut PgConnection, user_id: Uuid) -> Result<bool, RepoError>;
+    /// Adds users to the sale's allowlist, returns how many were new
+    async fn allow_users(
+        &self,
+        conn: &mut PgConnection,
+        flash_sale_id: Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<u64, RepoError>;
+    /// Returns whether the user was on the sale's allowlist
+    async fn disallow_user(
+        &self,
+        conn: &mut PgConnection,
+        flash_sale_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError>;
+    async fn is_allowed(
+        &self,
+        conn: &mut PgConnection,
+        flash_sale_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, RepoError>;
+}
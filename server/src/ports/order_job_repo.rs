@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    domain::order_job::{OrderJob, OrderJobErrorCode, OrderJobTurn},
+    errors::RepoError,
+    logic::order_logic::CreateOrderCommand,
+};
+
+#[async_trait]
+pub trait OrderJobRepo: Send + Sync {
+    /// Adds a pending job, re-queueing one that failed before
+    ///
+    /// The job's turn comes `1 / turn.weight` after the last pending job of
+    /// its sale in its lane, or after the job first in line if that is later.
+    async fn enqueue(
+        &self,
+        conn: &mut PgConnection,
+        command: &CreateOrderCommand,
+        turn: OrderJobTurn,
+    ) -> Result<(), RepoError>;
+    /// Locks the next pending job no other worker holds
+    ///
+    /// Jobs come up by the turn they were queued with, so lanes and sales
+    /// take turns by weight; ties go to the older job.
+    ///
+    /// The lock lasts until the surrounding transaction ends
+    async fn claim_next(&self, conn: &mut PgConnection) -> Result<Option<OrderJob>, RepoError>;
+    async fn complete(&self, conn: &mut PgConnection, id: Uuid) -> Result<(), RepoError>;
+    async fn fail(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        error: &str,
+        error_code: Option<OrderJobErrorCode>,
+    ) -> Result<(), RepoError>;
+    /// The jobs among `ids` that are no longer pending
+    async fn find_finished(
+        &self,
+        conn: &mut PgConnection,
+        ids: &[Uuid],
+    ) -> Result<Vec<OrderJob>, RepoError>;
+}
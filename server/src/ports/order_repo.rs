@@ -1,13 +1,49 @@
-use crate::{domain::order::Order, errors::RepoError};
+use crate::{
+    domain::{
+        order::{Order, OrderFilter},
+        pagination::{Page, PageRequest},
+    },
+    errors::RepoError,
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::PgConnection;
+use uuid::Uuid;
 
 #[async_trait]
 pub trait OrderRepo: Send + Sync {
     async fn save(&self, conn: &mut PgConnection, order: &Order) -> Result<Order, RepoError>;
+    /// The order `owner` placed with `key`, see [`Order::idempotency_owner`]
     async fn find_by_idempotency_key(
         &self,
         conn: &mut PgConnection,
+        owner: Uuid,
         key: &str,
     ) -> Result<Option<Order>, RepoError>;
+    async fn find_by_id(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<Order>, RepoError>;
+    async fn list_by_user(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        filter: &OrderFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Order>, RepoError>;
+    /// Orders the user placed at or after `since`, across all sales
+    async fn count_by_user_since(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<i64, RepoError>;
+    /// Holds the user's orders until the transaction ends, so concurrent
+    /// transactions counting and placing them for the user take turns
+    async fn lock_user_orders(
+        &self,
+        conn: &mut PgConnection,
+        user_id: Uuid,
+    ) -> Result<(), RepoError>;
 }
@@ -2,15 +2,51 @@ use async_trait::async_trait;
 use sqlx::PgConnection;
 use uuid::Uuid;
 
-use crate::{domain::product::Product, errors::RepoError};
+use crate::{
+    domain::{
+        pagination::{Page, PageRequest},
+        product::{Product, ProductFilter},
+    },
+    errors::RepoError,
+};
 
 #[async_trait]
 pub trait ProductRepo: Send + Sync {
     async fn save(&self, conn: &mut PgConnection, product: Product) -> Result<Product, RepoError>;
-    async fn get_all(&self, conn: &mut PgConnection) -> Result<Vec<Product>, RepoError>;
+    async fn list(
+        &self,
+        conn: &mut PgConnection,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, RepoError>;
     async fn find_by_id(
         &self,
         conn: &mut PgConnection,
         id: Uuid,
     ) -> Result<Option<Product>, RepoError>;
+    /// Reads the product with a share lock, holding off a concurrent archive
+    /// until the transaction ends
+    async fn find_by_id_with_lock(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<Product>, RepoError>;
+    /// Reads the product locked for update, so concurrent edits queue up
+    async fn find_by_id_for_update(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<Product>, RepoError>;
+    /// Overwrites the catalog details of an existing product
+    async fn update(&self, conn: &mut PgConnection, product: Product)
+    -> Result<Product, RepoError>;
+    /// Stamps `archived_at` unless already archived
+    async fn archive(&self, conn: &mut PgConnection, id: Uuid) -> Result<Product, RepoError>;
+    /// Unarchived products whose name resembles `query`, best match first
+    async fn search(
+        &self,
+        conn: &mut PgConnection,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<Product>, RepoError>;
 }
@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use std::time::Duration;
+
+use crate::{domain::rate_limit::GcraBucket, errors::RepoError};
+
+#[async_trait]
+pub trait RateLimitRepo: Send + Sync {
+    /// Counts one request against the bucket of `key` in a single atomic step
+    ///
+    /// The request is allowed when it leaves the bucket at most `window`
+    /// ahead of now, each request moving it `emission_interval` further.
+    async fn acquire(
+        &self,
+        conn: &mut PgConnection,
+        key: &str,
+        emission_interval: Duration,
+        window: Duration,
+    ) -> Result<GcraBucket, RepoError>;
+    /// Drops buckets that have refilled completely, returns how many
+    async fn delete_expired(&self, conn: &mut PgConnection) -> Result<u64, RepoError>;
+}
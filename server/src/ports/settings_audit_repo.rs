@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+
+use crate::{domain::runtime_settings::SettingsChange, errors::RepoError};
+
+#[async_trait]
+pub trait SettingsAuditRepo: Send + Sync {
+    async fn record(
+        &self,
+        conn: &mut PgConnection,
+        change: &SettingsChange,
+    ) -> Result<(), RepoError>;
+    /// Most recent changes first
+    async fn list_recent(
+        &self,
+        conn: &mut PgConnection,
+        limit: i64,
+    ) -> Result<Vec<SettingsChange>, RepoError>;
+}
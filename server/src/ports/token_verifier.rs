@@ -0,0 +1,6 @@
+use crate::{domain::auth::AuthenticatedUser, errors::ServiceError};
+
+pub trait TokenVerifier: Send + Sync {
+    /// Verifies a bearer token and returns the identity it carries
+    fn verify(&self, token: &str) -> Result<AuthenticatedUser, ServiceError>;
+}
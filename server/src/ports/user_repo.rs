@@ -2,11 +2,35 @@ use async_trait::async_trait;
 use sqlx::PgConnection;
 use uuid::Uuid;
 
-use crate::{domain::user::User, errors::RepoError};
+use crate::{
+    domain::{
+        pagination::{Page, PageRequest},
+        user::{Email, User, UserFilter},
+    },
+    errors::RepoError,
+};
 
 #[async_trait]
 pub trait UserRepo: Send + Sync {
     async fn save(&self, conn: &mut PgConnection, user: User) -> Result<User, RepoError>;
-    async fn get_all(&self, conn: &mut PgConnection) -> Result<Vec<User>, RepoError>;
+    async fn list(
+        &self,
+        conn: &mut PgConnection,
+        filter: &UserFilter,
+        page: &PageRequest,
+    ) -> Result<Page<User>, RepoError>;
     async fn get_by_id(&self, conn: &mut PgConnection, id: Uuid) -> Result<User, RepoError>;
+    /// Reads the user locked for update, so concurrent edits queue up
+    async fn find_by_id_for_update(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<User>, RepoError>;
+    async fn find_by_email(
+        &self,
+        conn: &mut PgConnection,
+        email: &Email,
+    ) -> Result<Option<User>, RepoError>;
+    /// Overwrites the profile fields of an existing user
+    async fn update(&self, conn: &mut PgConnection, user: User) -> Result<User, RepoError>;
 }
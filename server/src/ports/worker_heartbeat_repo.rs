@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::RepoError;
+
+#[async_trait]
+pub trait WorkerHeartbeatRepo: Send + Sync {
+    /// Records that the worker was alive at `seen_at`
+    async fn beat(
+        &self,
+        conn: &mut PgConnection,
+        worker_id: Uuid,
+        started_at: DateTime<Utc>,
+        seen_at: DateTime<Utc>,
+    ) -> Result<(), RepoError>;
+    /// Most recent heartbeat of any worker
+    async fn latest(&self, conn: &mut PgConnection) -> Result<Option<DateTime<Utc>>, RepoError>;
+    /// Drops the row of a worker shutting down cleanly
+    async fn remove(&self, conn: &mut PgConnection, worker_id: Uuid) -> Result<(), RepoError>;
+}